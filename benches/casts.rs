@@ -0,0 +1,122 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rattish::{
+    coercible_trait,
+    db::{
+        hash_map::HashMapTypeDatabase, vtable::VtableTypeDatabase, TypeDatabaseEntryExt,
+        TypeDatabaseExt, TypeDatabaseMut,
+    },
+    DynCast, DynImplements,
+};
+use std::{any::Any, cell::RefCell, fmt, rc::Rc};
+
+trait Foo: Any {}
+coercible_trait!(Foo);
+
+impl Foo for i32 {}
+impl Foo for u8 {}
+
+/// A database in which `i32` is one of many implementors of `fmt::Debug`.
+fn populated() -> HashMapTypeDatabase {
+    let mut db = HashMapTypeDatabase::default();
+    let entry = db.get_entry_mut::<dyn fmt::Debug>();
+    entry.register::<i8>();
+    entry.register::<i16>();
+    entry.register::<i32>();
+    entry.register::<i64>();
+    entry.register::<i128>();
+    entry.register::<u16>();
+    entry.register::<u32>();
+    entry.register::<u64>();
+    entry.register::<String>();
+    db.get_entry_mut::<dyn fmt::Display>().register::<i32>();
+    db
+}
+
+fn hits_and_misses(c: &mut Criterion) {
+    let db = populated();
+    let hit: &dyn Any = &123i32;
+    let miss: &dyn Any = &123u8;
+
+    c.bench_function("cast hit", |b| {
+        b.iter(|| black_box(hit).dyn_cast::<dyn fmt::Debug>(&db).is_ok())
+    });
+    c.bench_function("cast miss", |b| {
+        b.iter(|| black_box(miss).dyn_cast::<dyn fmt::Debug>(&db).is_err())
+    });
+    c.bench_function("cast unknown target", |b| {
+        b.iter(|| black_box(hit).dyn_cast::<dyn fmt::Binary>(&db).is_err())
+    });
+}
+
+fn wrapper_chains(c: &mut Criterion) {
+    let db = populated();
+    let shallow: &dyn Foo = &123i32;
+    let deep: Rc<RefCell<RefCell<dyn Foo>>> = Rc::new(RefCell::new(RefCell::new(123i32)));
+
+    c.bench_function("cast through reference", |b| {
+        b.iter(|| black_box(shallow).dyn_cast::<dyn fmt::Debug>(&db).is_ok())
+    });
+    c.bench_function("cast through Rc<RefCell<RefCell>>", |b| {
+        b.iter(|| {
+            black_box(deep.clone())
+                .dyn_cast::<dyn fmt::Debug>(&db)
+                .is_ok()
+        })
+    });
+}
+
+fn entry_reuse(c: &mut Criterion) {
+    let db = populated();
+    let entry = db.get_db_entry::<dyn fmt::Debug>().unwrap();
+    let value: &dyn Any = &123i32;
+
+    c.bench_function("cast via database", |b| {
+        b.iter(|| black_box(value).dyn_cast::<dyn fmt::Debug>(&db).is_ok())
+    });
+    c.bench_function("cast via reused entry", |b| {
+        b.iter(|| entry.cast(black_box(value)).is_ok())
+    });
+}
+
+fn backends(c: &mut Criterion) {
+    let db = populated();
+    let index = db.trait_index();
+    let mask = index
+        .mask::<(&dyn fmt::Debug, &dyn fmt::Display)>()
+        .unwrap();
+    let value: &dyn Any = &123i32;
+
+    let mut vtables = VtableTypeDatabase::default();
+    unsafe {
+        vtables.register::<dyn Foo, dyn fmt::Debug, i32>();
+        vtables.register::<dyn Foo, dyn fmt::Debug, u8>();
+    }
+    let source: &dyn Foo = &123i32;
+
+    c.bench_function("hash map: implements all", |b| {
+        b.iter(|| {
+            black_box(value)
+                .dyn_implements_all::<(&dyn fmt::Debug, &dyn fmt::Display)>(&db)
+                .is_ok()
+        })
+    });
+    c.bench_function("trait index: implements all", |b| {
+        b.iter(|| index.implements_all(black_box(value).type_id(), &mask))
+    });
+    c.bench_function("vtable: cast_ref", |b| {
+        b.iter(|| {
+            vtables
+                .cast_ref::<dyn Foo, dyn fmt::Debug>(black_box(source))
+                .is_some()
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    hits_and_misses,
+    wrapper_chains,
+    entry_reuse,
+    backends
+);
+criterion_main!(benches);
@@ -1,16 +1,17 @@
+use super::{Cached, Coerced, Coercible, Metadata, Pointer, TypeIdDeterminationError::WouldBlock};
 use core::{
-    any::Any,
+    any::{type_name, Any},
     cell::{Cell, Ref, RefCell, RefMut, UnsafeCell},
     ptr,
 };
 
 #[cfg(feature = "alloc")]
 use super::TypeIdDeterminationError::UnableToUpgradeWeakReference;
-#[cfg(feature = "alloc")]
-use core::any::type_name;
 
+#[cfg(all(feature = "alloc", not(feature = "std"), target_has_atomic = "ptr"))]
+use alloc::sync;
 #[cfg(all(feature = "alloc", not(feature = "std")))]
-use alloc::{boxed::Box, rc, sync};
+use alloc::{boxed::Box, rc};
 
 #[cfg(feature = "std")]
 use std::{boxed::Box, rc, sync};
@@ -20,13 +21,13 @@ coercible_trait!(Any);
 coercibles! {
     <T, U>(self, metadata) {
         *const T => *const T::Coerced<U> {
-            ptr::from_raw_parts(self.cast(), metadata)
+            ptr::from_raw_parts(self.cast::<()>(), metadata)
         },
         *mut T => *mut T::Coerced<U> {
-            ptr::from_raw_parts_mut(self.cast(), metadata)
+            ptr::from_raw_parts_mut(self.cast::<()>(), metadata)
         },
         ptr::NonNull<T> => ptr::NonNull<T::Coerced<U>> {
-            ptr::NonNull::from_raw_parts(self.cast(), metadata)
+            ptr::NonNull::from_raw_parts(self.cast::<()>(), metadata)
         },
         @'a &'a T|&T => &'a T::Coerced<U> {
             ptr::NonNull::from(self).coerce(metadata).as_ref()
@@ -36,7 +37,8 @@ coercibles! {
         } as _,
         Cell<T> => Cell<T::Coerced<U>>,
         RefCell<T> => RefCell<T::Coerced<U>> as {
-            self.borrow().innermost_type_id()
+            self.try_borrow()
+                .map_err(|_| WouldBlock { type_name: type_name::<Self>() })?
         },
         @'a Ref<'a, T>|Ref<'_, T> => Ref<'a, T::Coerced<U>> {
             Self::map(self, |r| r.coerce(metadata))
@@ -56,8 +58,14 @@ coercibles! {
         } as {
             self.upgrade()
                 .ok_or(UnableToUpgradeWeakReference { type_name: type_name::<Self>() })?
-                .innermost_type_id()
         },
+    }
+}
+
+// `alloc::sync` is only available on targets with atomic pointers
+#[cfg(target_has_atomic = "ptr")]
+coercibles! {
+    <T, U>(self, metadata) {
         #["alloc"] sync::Arc<T> => sync::Arc<T::Coerced<U>> {
             sync::Arc::from_raw(Self::into_raw(self).coerce(metadata))
         } as _,
@@ -66,7 +74,72 @@ coercibles! {
         } as {
             self.upgrade()
                 .ok_or(UnableToUpgradeWeakReference { type_name: type_name::<Self>() })?
-                .innermost_type_id()
         },
     }
 }
+
+innermost_any! {
+    &T => |r| r,
+    &mut T => |r| r,
+    Ref<'_, T> => |r| r,
+    RefMut<'_, T> => |r| r,
+    #["alloc"] Box<T> => |b| b,
+    #["alloc"] rc::Rc<T> => |rc| rc,
+}
+
+#[cfg(target_has_atomic = "ptr")]
+innermost_any! {
+    #["alloc"] sync::Arc<T> => |arc| arc,
+}
+
+innermost_any_mut! {
+    &mut T => |r| r,
+    RefMut<'_, T> => |r| r,
+    Cell<T> => Cell::get_mut,
+    RefCell<T> => RefCell::get_mut,
+    UnsafeCell<T> => UnsafeCell::get_mut,
+    #["alloc"] Box<T> => |b| b,
+}
+
+// `Cached` cannot implement `Pointer` generically, as `P::Coerced<U>` is not
+// known to be `Sized`; it is, however, for each of these pointers.
+macro_rules! cached_pointers {
+    ($( $(#[$feature:literal])? $(@$lt:lifetime)? $ty:ty, )+) => {$(
+        $( #[cfg(feature = $feature)] )?
+        impl<$($lt,)? T> Pointer for Cached<$ty>
+        where
+            T: ?Sized + Coercible,
+        {
+            unsafe fn coerce<U>(self, metadata: Metadata<Coerced<Self::Inner, U>>) -> Self::Coerced<U>
+            where
+                U: ?Sized,
+                Self::Coerced<U>: Sized,
+            {
+                // coercion does not change the concrete type
+                Cached {
+                    type_id: self.type_id,
+                    pointer: self.pointer.coerce(metadata),
+                }
+            }
+        }
+    )+};
+}
+
+cached_pointers! {
+    *const T,
+    *mut T,
+    ptr::NonNull<T>,
+    @'a &'a T,
+    @'a &'a mut T,
+    @'a Ref<'a, T>,
+    @'a RefMut<'a, T>,
+    #["alloc"] Box<T>,
+    #["alloc"] rc::Rc<T>,
+    #["alloc"] rc::Weak<T>,
+}
+
+#[cfg(target_has_atomic = "ptr")]
+cached_pointers! {
+    #["alloc"] sync::Arc<T>,
+    #["alloc"] sync::Weak<T>,
+}
@@ -11,19 +11,102 @@ macro_rules! coercible_trait {
         }
 
         unsafe impl $crate::container::InnermostTypeId for dyn $trait {
-            #[cfg_attr(feature = "tracing", $crate::tracing::instrument(skip_all))]
             fn innermost_type_id(
                 &self,
             ) -> Result<::core::any::TypeId, $crate::container::TypeIdDeterminationError> {
+                #[cfg(feature = "tracing")]
+                let _span = $crate::trace::nested(|| {
+                    $crate::tracing::info_span!("innermost_type_id").entered()
+                });
                 let type_id = ::core::any::Any::type_id(self);
                 #[cfg(feature = "tracing")]
-                $crate::tracing::info!("found type_id {:?}", type_id);
+                $crate::trace::nested(|| $crate::tracing::info!("found type_id {:?}", type_id));
                 Ok(type_id)
             }
+
+            fn with_innermost_any<F, R>(
+                &self,
+                f: F,
+            ) -> Result<R, $crate::container::TypeIdDeterminationError>
+            where
+                F: ::core::ops::FnOnce(&dyn ::core::any::Any) -> R,
+            {
+                #[cfg(feature = "tracing")]
+                let _span = $crate::trace::nested(|| {
+                    $crate::tracing::info_span!("with_innermost_any").entered()
+                });
+                Ok(f(self))
+            }
+        }
+
+        impl $crate::container::InnermostAny for dyn $trait {
+            fn innermost_any(&self) -> &dyn ::core::any::Any {
+                self
+            }
+        }
+
+        impl $crate::container::InnermostAnyMut for dyn $trait {
+            fn innermost_any_mut(&mut self) -> &mut dyn ::core::any::Any {
+                self
+            }
         }
     };
 }
 
+/// Implement [`Coercible`][super::Coercible] and
+/// [`InnermostTypeId`][super::InnermostTypeId] for the given concrete types, in
+/// order to be able to cast *from* pointers to their instances without first
+/// erasing them to [`dyn Any`](core::any::Any).
+///
+/// A blanket implementation for every `T: Sized + Any` is not possible, as it
+/// would overlap with those for the pointer and wrapper types.
+#[macro_export]
+macro_rules! coercible_type {
+    ($( $ty:ty ),+ $(,)?) => {$(
+        unsafe impl $crate::container::Coercible for $ty {
+            type Coerced<U: 'static + ?::core::marker::Sized> = U;
+            type Inner = Self;
+            type Innermost = Self;
+        }
+
+        unsafe impl $crate::container::InnermostTypeId for $ty {
+            fn innermost_type_id(
+                &self,
+            ) -> Result<::core::any::TypeId, $crate::container::TypeIdDeterminationError> {
+                #[cfg(feature = "tracing")]
+                let _span =
+                    $crate::trace::nested(|| $crate::tracing::info_span!("innermost_type_id").entered());
+                Ok(::core::any::TypeId::of::<Self>())
+            }
+
+            fn with_innermost_any<F, R>(
+                &self,
+                f: F,
+            ) -> Result<R, $crate::container::TypeIdDeterminationError>
+            where
+                F: ::core::ops::FnOnce(&dyn ::core::any::Any) -> R,
+            {
+                #[cfg(feature = "tracing")]
+                let _span =
+                    $crate::trace::nested(|| $crate::tracing::info_span!("with_innermost_any").entered());
+                Ok(f(self))
+            }
+        }
+
+        impl $crate::container::InnermostAny for $ty {
+            fn innermost_any(&self) -> &dyn ::core::any::Any {
+                self
+            }
+        }
+
+        impl $crate::container::InnermostAnyMut for $ty {
+            fn innermost_any_mut(&mut self) -> &mut dyn ::core::any::Any {
+                self
+            }
+        }
+    )+};
+}
+
 macro_rules! coercibles {
     (
         <$t:ident, $u:ident>($self:ident, $metadata:ident) {
@@ -36,7 +119,7 @@ macro_rules! coercibles {
             <$t, $u>($self, $metadata) {
                 $(#[$feature])?
                 $(@$lt $tx|)? $ty => $coerced $($coerce)? as {
-                    (**$self).innermost_type_id()
+                    &**$self
                 },
                 $($rest)*
             }
@@ -45,7 +128,7 @@ macro_rules! coercibles {
     (
         <$t:ident, $u:ident>($self:ident, $metadata:ident) {
             $(#[$feature:literal])?
-            $(@$lt:lifetime $tx:ty|)? $ty:ty => $coerced:ty $($coerce:block)? as $type:block,
+            $(@$lt:lifetime $tx:ty|)? $ty:ty => $coerced:ty $($coerce:block)? as $inner:block,
             $($rest:tt)*
         }
     ) => {
@@ -54,10 +137,18 @@ macro_rules! coercibles {
         where
             $t: ?::core::marker::Sized + $crate::container::InnermostTypeId,
         {
-            #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
-                Self = ::core::any::type_name::<Self>(),
-            )))]
-            fn innermost_type_id(&$self) -> Result<::core::any::TypeId, $crate::container::TypeIdDeterminationError> $type
+            fn innermost_type_id(&$self) -> Result<::core::any::TypeId, $crate::container::TypeIdDeterminationError> {
+                nested_span!("innermost_type_id", Self = ::core::any::type_name::<Self>());
+                $crate::container::InnermostTypeId::innermost_type_id(&*$inner)
+            }
+
+            fn with_innermost_any<F, R>(&$self, f: F) -> Result<R, $crate::container::TypeIdDeterminationError>
+            where
+                F: ::core::ops::FnOnce(&dyn ::core::any::Any) -> R,
+            {
+                nested_span!("with_innermost_any", Self = ::core::any::type_name::<Self>());
+                $crate::container::InnermostTypeId::with_innermost_any(&*$inner, f)
+            }
         }
 
         coercibles! {
@@ -80,15 +171,16 @@ macro_rules! coercibles {
         where
             $t: ?::core::marker::Sized + $crate::container::Coercible,
         {
-            #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
-                Self = ::core::any::type_name::<Self>(),
-                U = ::core::any::type_name::<U>(),
-            )))]
             unsafe fn coerce<U>($self, $metadata: $crate::container::Metadata<$crate::container::Coerced<Self::Inner, U>>) -> Self::Coerced<U>
             where
                 U: ?::core::marker::Sized,
                 Self::Coerced<U>: ::core::marker::Sized,
             {
+                nested_span!(
+                    "coerce",
+                    Self = ::core::any::type_name::<Self>(),
+                    U = ::core::any::type_name::<U>(),
+                );
                 #[allow(unused_unsafe)]
                 unsafe {$coerce}
             }
@@ -142,3 +234,33 @@ macro_rules! coercibles {
     };
     (<$t:ident, $u:ident>($self:ident, $metadata:ident) {}) => {};
 }
+
+macro_rules! innermost_any {
+    ($( $(#[$feature:literal])? $ty:ty => $inner:expr, )+) => {$(
+        $( #[cfg(feature = $feature)] )?
+        impl<T> $crate::container::InnermostAny for $ty
+        where
+            T: ?::core::marker::Sized + $crate::container::InnermostAny,
+        {
+            fn innermost_any(&self) -> &dyn ::core::any::Any {
+                let inner: fn(&Self) -> &T = $inner;
+                inner(self).innermost_any()
+            }
+        }
+    )+};
+}
+
+macro_rules! innermost_any_mut {
+    ($( $(#[$feature:literal])? $ty:ty => $inner:expr, )+) => {$(
+        $( #[cfg(feature = $feature)] )?
+        impl<T> $crate::container::InnermostAnyMut for $ty
+        where
+            T: ?::core::marker::Sized + $crate::container::InnermostAnyMut,
+        {
+            fn innermost_any_mut(&mut self) -> &mut dyn ::core::any::Any {
+                let inner: fn(&mut Self) -> &mut T = $inner;
+                inner(self).innermost_any_mut()
+            }
+        }
+    )+};
+}
@@ -17,11 +17,26 @@ mod impls;
 #[cfg(test)]
 mod tests;
 
-use core::{any::TypeId, ptr};
+use core::{
+    any::{Any, TypeId},
+    fmt,
+    marker::Unsize,
+    ptr,
+};
 
 /// The [`Pointee::Metadata`][ptr::Pointee::Metadata] of `U`.
 pub type Metadata<U> = <U as ptr::Pointee>::Metadata;
 
+/// The [`Metadata<U>`] of concrete type `I`, as obtained by unsizing a pointer
+/// to an `I` into a pointer to a `U`.
+pub const fn metadata_of<U, I>() -> Metadata<U>
+where
+    U: ?Sized,
+    I: Unsize<U>,
+{
+    ptr::metadata(ptr::null::<I>() as *const U)
+}
+
 /// A type that inherits [`Pointee::Metadata`][ptr::Pointee::Metadata]
 /// from a contained type, pointers to which are therefore coercible if that
 /// contained type is coercible.
@@ -69,18 +84,66 @@ pub type Coerced<T: Coercible, U> = T::Coerced<U>;
 
 /// Error that arose whilst determining a pointee's concrete type.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-#[cfg_attr(feature = "thiserror", derive(thiserror::Error))]
 #[non_exhaustive]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum TypeIdDeterminationError {
     /// The concrete type could not be determined because the pointer traverses
     /// a weak reference to some data that is no longer available.
-    #[cfg_attr(feature = "thiserror", error("{type_name} was dangling"))]
     UnableToUpgradeWeakReference {
         /// The name of the Weak reference type that could not be upgraded
         type_name: &'static str,
     },
+
+    /// The concrete type could not be determined because the pointer traverses
+    /// some container whose value is not presently available (for example, a
+    /// cell that has not yet been initialized).
+    ValueUnavailable {
+        /// The name of the container type whose value was unavailable
+        type_name: &'static str,
+        /// Why the value was unavailable
+        detail: &'static str,
+    },
+
+    /// The concrete type could not be determined without blocking, because the
+    /// pointer traverses a lock that is held elsewhere.
+    WouldBlock {
+        /// The name of the lock type that could not be acquired
+        type_name: &'static str,
+    },
+
+    /// The concrete type could not be determined because the pointer traverses
+    /// a lock that has been poisoned.
+    Poisoned {
+        /// The name of the lock type that was poisoned
+        type_name: &'static str,
+    },
+
+    /// The concrete type could not be determined for some other reason.
+    Other(&'static str),
+}
+
+impl fmt::Display for TypeIdDeterminationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnableToUpgradeWeakReference { type_name } => {
+                write!(f, "{} was dangling", type_name)
+            }
+            Self::ValueUnavailable { type_name, detail } => {
+                write!(f, "{} was unavailable: {}", type_name, detail)
+            }
+            Self::WouldBlock { type_name } => write!(f, "{} would block", type_name),
+            Self::Poisoned { type_name } => write!(f, "{} was poisoned", type_name),
+            Self::Other(reason) => f.write_str(reason),
+        }
+    }
 }
 
+#[cfg(feature = "error-in-core")]
+impl core::error::Error for TypeIdDeterminationError {}
+
+#[cfg(all(feature = "std", not(feature = "error-in-core")))]
+impl std::error::Error for TypeIdDeterminationError {}
+
 /// A dereferenceable type that inherits
 /// [`Pointee::Metadata`][ptr::Pointee::Metadata] from a contained type,
 /// pointers to which are therefore coercible if that contained type is
@@ -98,6 +161,48 @@ pub unsafe trait InnermostTypeId {
     /// [`Any::type_id`][core::any::Any::type_id]), this should just delegate to
     /// the contained type's `innermost_type_id`.
     fn innermost_type_id(&self) -> Result<TypeId, TypeIdDeterminationError>;
+
+    /// Calls `f` with a reference to the *concrete* value underlying
+    /// [`<Self as ptr::Pointee>::Metadata`][ptr::Pointee::Metadata], returning
+    /// its result.
+    ///
+    /// Unless `Self` is a leaf, such as a `dyn Trait` (in which case this
+    /// should upcast `self` to [`dyn Any`][Any]), this should just delegate to
+    /// the contained type's `with_innermost_any`.
+    ///
+    /// The default implementation fails without calling `f`, so pointers to
+    /// types that do not override it cannot be cast to targets that were
+    /// registered with a predicate.
+    fn with_innermost_any<F, R>(&self, f: F) -> Result<R, TypeIdDeterminationError>
+    where
+        F: FnOnce(&dyn Any) -> R,
+    {
+        let _ = f;
+        Err(TypeIdDeterminationError::Other(
+            "concrete value is not accessible",
+        ))
+    }
+}
+
+/// A type through which the *concrete* value underlying
+/// [`<Self as ptr::Pointee>::Metadata`][ptr::Pointee::Metadata] can be borrowed
+/// directly (that is, without traversing any weak reference or interior
+/// mutability).
+pub trait InnermostAny
+where
+    Self: InnermostTypeId,
+{
+    /// Returns a shared reference to the concrete value, as [`dyn Any`][Any].
+    fn innermost_any(&self) -> &dyn Any;
+}
+
+/// A type through which the *concrete* value underlying
+/// [`<Self as ptr::Pointee>::Metadata`][ptr::Pointee::Metadata] can be
+/// exclusively borrowed directly, given exclusive access to `Self`.
+pub trait InnermostAnyMut {
+    /// Returns an exclusive reference to the concrete value, as
+    /// [`dyn Any`][Any].
+    fn innermost_any_mut(&mut self) -> &mut dyn Any;
 }
 
 /// A [`Sized`] type that inherits [`Pointee::Metadata`][ptr::Pointee::Metadata]
@@ -118,3 +223,141 @@ where
         U: ?Sized,
         Self::Coerced<U>: Sized;
 }
+
+/// A [`Pointer`] whose innermost type is concrete, and which can therefore be
+/// coerced without reference to any database.
+pub trait CoerceTo
+where
+    Self: Pointer,
+{
+    /// Coerce the innermost type to `U`, through any intermediate wrappers:
+    /// for example, `Rc<RefCell<Qux>>` becomes `Rc<RefCell<dyn Bar>>`.
+    fn coerce_to<U>(self) -> Self::Coerced<U>
+    where
+        U: 'static + ?Sized,
+        Self::Coerced<U>: Sized,
+        Self::Innermost: Sized + Unsize<U>,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        // the metadata of `Self` is that of its innermost type
+        unsafe { self.coerce(metadata_of::<U, Self::Innermost>()) }
+    }
+}
+
+impl<P> CoerceTo for P where P: Pointer {}
+
+/// A pointer whose concrete type's [`TypeId`] is determined once and then
+/// cached, which avoids repeatedly traversing a chain of wrappers such as
+/// `Rc<RefCell<Box<dyn Foo>>>` to determine it.
+#[derive(Debug)]
+pub struct Cached<P>
+where
+    P: ?Sized,
+{
+    type_id: Option<TypeId>,
+    pointer: P,
+}
+
+impl<P> Cached<P> {
+    /// Wrap `pointer`, determining and caching its concrete type's [`TypeId`].
+    ///
+    /// # Safety
+    /// The concrete type underlying `pointer` must not change (for example,
+    /// through interior mutability) for as long as it is cached; that is,
+    /// until the cache is [`invalidate`][Cached::invalidate]d or
+    /// [`refresh`][Cached::refresh]ed, or `pointer` is accessed with
+    /// [`get_mut`][Cached::get_mut].
+    pub unsafe fn new(pointer: P) -> Result<Self, TypeIdDeterminationError>
+    where
+        P: InnermostTypeId,
+    {
+        Ok(Self {
+            type_id: Some(pointer.innermost_type_id()?),
+            pointer,
+        })
+    }
+
+    /// Unwrap the pointer.
+    pub fn into_inner(self) -> P {
+        self.pointer
+    }
+}
+
+impl<P> Cached<P>
+where
+    P: ?Sized,
+{
+    /// The cached [`TypeId`], if any.
+    pub fn cached_type_id(&self) -> Option<TypeId> {
+        self.type_id
+    }
+
+    /// The wrapped pointer.
+    pub fn get(&self) -> &P {
+        &self.pointer
+    }
+
+    /// The wrapped pointer, which may be mutated: the cache is therefore
+    /// invalidated.
+    pub fn get_mut(&mut self) -> &mut P {
+        self.invalidate();
+        &mut self.pointer
+    }
+
+    /// Discard the cached [`TypeId`], so that it is determined afresh on every
+    /// use until [`refresh`][Cached::refresh]ed.
+    pub fn invalidate(&mut self) {
+        self.type_id = None;
+    }
+
+    /// Determine and cache the concrete type's [`TypeId`] afresh.
+    ///
+    /// # Safety
+    /// As for [`new`][Cached::new].
+    pub unsafe fn refresh(&mut self) -> Result<TypeId, TypeIdDeterminationError>
+    where
+        P: InnermostTypeId,
+    {
+        self.type_id = None;
+        let type_id = self.pointer.innermost_type_id()?;
+        self.type_id = Some(type_id);
+        Ok(type_id)
+    }
+}
+
+unsafe impl<P> Coercible for Cached<P>
+where
+    P: ?Sized + Coercible,
+{
+    type Coerced<U: 'static + ?Sized> = Cached<P::Coerced<U>>;
+    type Inner = P::Inner;
+    type Innermost = P::Innermost;
+}
+
+unsafe impl<P> InnermostTypeId for Cached<P>
+where
+    P: ?Sized + InnermostTypeId,
+{
+    fn innermost_type_id(&self) -> Result<TypeId, TypeIdDeterminationError> {
+        match self.type_id {
+            Some(type_id) => Ok(type_id),
+            None => self.pointer.innermost_type_id(),
+        }
+    }
+
+    fn with_innermost_any<F, R>(&self, f: F) -> Result<R, TypeIdDeterminationError>
+    where
+        F: FnOnce(&dyn Any) -> R,
+    {
+        self.pointer.with_innermost_any(f)
+    }
+}
+
+impl<P> InnermostAny for Cached<P>
+where
+    P: ?Sized + InnermostAny,
+{
+    fn innermost_any(&self) -> &dyn Any {
+        self.pointer.innermost_any()
+    }
+}
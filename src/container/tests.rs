@@ -1,6 +1,9 @@
 #![allow(trivial_casts)]
 
-use super::{InnermostTypeId, Metadata, Pointer};
+use super::{
+    metadata_of, Cached, CoerceTo, InnermostAny, InnermostAnyMut, InnermostTypeId, Metadata,
+    Pointer, TypeIdDeterminationError,
+};
 use core::{
     any::{Any, TypeId},
     cell::{Ref, RefCell, RefMut},
@@ -8,6 +11,9 @@ use core::{
     ptr,
 };
 
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
 #[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::{boxed::Box, rc, sync};
 
@@ -20,7 +26,7 @@ use super::TypeIdDeterminationError::UnableToUpgradeWeakReference;
 type T = i32;
 type U = dyn PartialEq<T>;
 const ADDRESS: *mut () = 0xdeadbeef_usize as _;
-const METADATA: Metadata<U> = ptr::metadata::<U>(ptr::null::<T>());
+const METADATA: Metadata<U> = metadata_of::<U, T>();
 
 trait Foo {
     fn double(&mut self);
@@ -35,6 +41,17 @@ impl Foo for i32 {
     }
 }
 
+struct Qux(i32);
+crate::coercible_type!(Qux);
+impl Foo for Qux {
+    fn double(&mut self) {
+        self.0 *= 2;
+    }
+    fn get(&self) -> i32 {
+        self.0
+    }
+}
+
 fn metadata<T: Unsize<U>, U: ?Sized>() -> Metadata<U> {
     ptr::metadata::<U>(ptr::null::<T>())
 }
@@ -207,6 +224,15 @@ fn compound_types_transitively_coerce() {
     }
 }
 
+#[test]
+fn concrete_types_statically_coerce_through_wrappers() {
+    let cell = RefCell::new(Qux(12345));
+    let coerced: &RefCell<dyn Foo> = (&cell).coerce_to::<dyn Foo>();
+    coerced.borrow_mut().double();
+
+    assert_eq!(coerced.borrow().get(), 12345 * 2);
+}
+
 #[test]
 fn innermost_type_id_of_ref() {
     let ptr: &dyn Any = &12345;
@@ -223,6 +249,18 @@ fn innermost_type_id_of_mut_ref() {
     assert_eq!(type_id, TypeId::of::<i32>());
 }
 
+#[test]
+fn innermost_type_id_of_mutably_borrowed_cell_would_block() {
+    let cell = RefCell::new(12345);
+    let compound: &RefCell<dyn Any> = &cell;
+    let _borrow = cell.borrow_mut();
+
+    assert!(matches!(
+        compound.innermost_type_id(),
+        Err(TypeIdDeterminationError::WouldBlock { .. })
+    ));
+}
+
 #[test]
 fn innermost_type_id_of_cell_ref() {
     let cell = RefCell::new(12345);
@@ -324,3 +362,92 @@ fn innermost_type_id_of_compound_types_are_transitive() {
 
     assert_eq!(type_id, TypeId::of::<i32>());
 }
+
+#[test]
+fn innermost_any_of_compound_types_are_transitive() {
+    let cell = RefCell::new(12345);
+    let compound: &RefCell<dyn Any> = &cell;
+    let value = compound.with_innermost_any(|any| any.downcast_ref::<T>().copied());
+
+    assert_eq!(value, Ok(Some(12345)));
+}
+
+#[test]
+fn innermost_any_is_borrowed_directly() {
+    let mut cell = RefCell::new(12345);
+    let compound: &mut RefCell<dyn Any> = &mut cell;
+    let value = compound
+        .borrow()
+        .innermost_any()
+        .downcast_ref::<T>()
+        .copied();
+    assert_eq!(value, Some(12345));
+
+    *compound.innermost_any_mut().downcast_mut::<T>().unwrap() *= 2;
+    assert_eq!(cell.into_inner(), 24690);
+}
+
+#[test]
+fn cached_type_id_is_reused_until_invalidated() {
+    let cell = RefCell::new(12345);
+    let compound: &RefCell<dyn Any> = &cell;
+    let mut cached = unsafe { Cached::new(compound) }.unwrap();
+
+    {
+        // determining the type afresh would require borrowing the cell
+        let _borrow = cell.borrow_mut();
+        assert_eq!(cached.innermost_type_id(), Ok(TypeId::of::<i32>()));
+    }
+
+    cached.invalidate();
+    assert_eq!(cached.cached_type_id(), None);
+    assert_eq!(cached.innermost_type_id(), Ok(TypeId::of::<i32>()));
+
+    unsafe { cached.refresh() }.unwrap();
+    let coerced = unsafe { cached.coerce::<U>(METADATA) };
+    assert_eq!(coerced.cached_type_id(), Some(TypeId::of::<i32>()));
+    assert!(coerced.get().borrow().eq(&12345));
+}
+
+struct Pending;
+
+unsafe impl InnermostTypeId for Pending {
+    fn innermost_type_id(&self) -> Result<TypeId, TypeIdDeterminationError> {
+        self.with_innermost_any(|any| any.type_id())
+    }
+
+    fn with_innermost_any<F, R>(&self, _f: F) -> Result<R, TypeIdDeterminationError>
+    where
+        F: FnOnce(&dyn Any) -> R,
+    {
+        Err(TypeIdDeterminationError::ValueUnavailable {
+            type_name: "Pending",
+            detail: "not yet initialized",
+        })
+    }
+}
+
+#[test]
+fn third_party_containers_report_unavailable_values() {
+    let type_id = Pending.innermost_type_id();
+
+    assert_eq!(
+        type_id,
+        Err(TypeIdDeterminationError::ValueUnavailable {
+            type_name: "Pending",
+            detail: "not yet initialized",
+        })
+    );
+}
+
+#[cfg(feature = "error-in-core")]
+#[test]
+fn errors_chain_through_core_error() {
+    use crate::db::error::DatabaseEntryError;
+    use core::error::Error;
+
+    let reason = TypeIdDeterminationError::UnableToUpgradeWeakReference { type_name: "Weak" };
+    let error = DatabaseEntryError::<U, &dyn Any>::from(reason);
+
+    assert!(error.source().unwrap().is::<TypeIdDeterminationError>());
+}
@@ -0,0 +1,79 @@
+//! A bump arena, in which values of heterogeneous types are stored contiguously
+//! and are dropped together with the arena.
+
+use std::{
+    alloc::Layout,
+    mem::{self, MaybeUninit},
+    ptr::{self, NonNull},
+};
+
+/// The size of each chunk of the arena, unless a larger value is allocated.
+const CHUNK_SIZE: usize = 4096;
+
+/// A function that drops the value to which its argument points.
+type DropFn = unsafe fn(NonNull<u8>);
+
+/// A bump arena of values that are [`Send`] and [`Sync`].
+#[derive(Default)]
+pub(super) struct Arena {
+    chunks: Vec<Box<[MaybeUninit<u8>]>>,
+    used: usize,
+    drops: Vec<(NonNull<u8>, DropFn)>,
+}
+
+// the arena only holds values that are themselves `Send` and `Sync`
+unsafe impl Send for Arena {}
+unsafe impl Sync for Arena {}
+
+impl Arena {
+    /// Move `value` into the arena, returning a pointer to it that remains
+    /// valid until the arena is dropped.
+    pub(super) fn alloc<T>(&mut self, value: T) -> NonNull<T>
+    where
+        T: Send + Sync,
+    {
+        let layout = Layout::new::<T>();
+        let pointer = self.reserve(layout).cast::<T>();
+        unsafe {
+            ptr::write(pointer.as_ptr(), value);
+        }
+        if mem::needs_drop::<T>() {
+            self.drops.push((pointer.cast(), drop::<T>));
+        }
+        pointer
+    }
+
+    /// Reserve space for a value with the given `layout`.
+    fn reserve(&mut self, layout: Layout) -> NonNull<u8> {
+        if let Some(chunk) = self.chunks.last_mut() {
+            let start = unsafe { chunk.as_mut_ptr().add(self.used) };
+            let offset = start.align_offset(layout.align());
+            if self.used + offset + layout.size() <= chunk.len() {
+                self.used += offset + layout.size();
+                return unsafe { NonNull::new_unchecked(start.add(offset).cast()) };
+            }
+        }
+
+        let size = CHUNK_SIZE.max(layout.size() + layout.align());
+        self.chunks
+            .push(vec![MaybeUninit::uninit(); size].into_boxed_slice());
+        self.used = 0;
+        self.reserve(layout)
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        for &(pointer, drop) in self.drops.iter().rev() {
+            unsafe { drop(pointer) }
+        }
+    }
+}
+
+/// Drop the `T` at `pointer`.
+///
+/// # Safety
+/// `pointer` must point to a valid `T`, which must not be used again.
+unsafe fn drop<T>(pointer: NonNull<u8>) {
+    ptr::drop_in_place(pointer.cast::<T>().as_ptr());
+}
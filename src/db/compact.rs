@@ -0,0 +1,97 @@
+//! Compact storage of metadata, enabled by the `compact-metadata` feature.
+//!
+//! Rather than each registration holding its own (lazily evaluated) metadata,
+//! it holds only a `u32` index into a [`MetadataTable`] that is shared by every
+//! entry of a database, and in which equal metadata is stored only once.
+//! Metadata is thereby evaluated upon registration, even if registered lazily.
+
+use super::Metadata;
+use std::{cell::UnsafeCell, convert::TryFrom, marker::PhantomData, mem, ptr};
+
+/// The index of some metadata of `U` in a [`MetadataTable`].
+pub(super) struct MetadataIndex<U>
+where
+    U: ?Sized,
+{
+    index: u32,
+    target: PhantomData<fn() -> *const U>,
+}
+
+/// A deduplicated table of type-erased metadata, indexed by `u32`.
+#[derive(Default)]
+pub(super) struct MetadataTable {
+    values: UnsafeCell<Vec<usize>>,
+    // indices into `values`, ordered by the values to which they refer
+    sorted: UnsafeCell<Vec<u32>>,
+}
+
+// the table is only mutated through `intern`, whose callers guarantee that it
+// is not concurrently accessed
+unsafe impl Sync for MetadataTable {}
+
+impl MetadataTable {
+    /// The index of `metadata` in the table, inserting it if not present.
+    ///
+    /// # Safety
+    /// The table must not be accessed concurrently, as is guaranteed if the
+    /// caller has exclusive access to every entry that shares the table.
+    pub(super) unsafe fn intern<U>(&self, metadata: Metadata<U>) -> MetadataIndex<U>
+    where
+        U: ?Sized,
+    {
+        let value = erase::<U>(metadata);
+        let values = &mut *self.values.get();
+        let sorted = &mut *self.sorted.get();
+        let index = match sorted.binary_search_by_key(&value, |&index| values[index as usize]) {
+            Ok(position) => sorted[position],
+            Err(position) => {
+                let index = u32::try_from(values.len()).expect("metadata table is full");
+                values.push(value);
+                sorted.insert(position, index);
+                index
+            }
+        };
+        MetadataIndex {
+            index,
+            target: PhantomData,
+        }
+    }
+
+    /// The metadata at `index`.
+    ///
+    /// # Safety
+    /// `index` must have been returned by [`intern`][Self::intern] on this
+    /// table.
+    pub(super) unsafe fn get<U>(&self, index: &MetadataIndex<U>) -> Metadata<U>
+    where
+        U: ?Sized,
+    {
+        let value = (&*self.values.get())[index.index as usize];
+        ptr::read((&value as *const usize).cast())
+    }
+
+    /// The approximate number of bytes allocated on the heap by this table.
+    pub(super) fn memory_footprint(&self) -> usize {
+        unsafe {
+            (&*self.values.get()).capacity() * mem::size_of::<usize>()
+                + (&*self.sorted.get()).capacity() * mem::size_of::<u32>()
+        }
+    }
+}
+
+/// `metadata` as a `usize`, which is at least as large as any metadata.
+fn erase<U>(metadata: Metadata<U>) -> usize
+where
+    U: ?Sized,
+{
+    assert!(mem::size_of::<Metadata<U>>() <= mem::size_of::<usize>());
+    let mut value = 0usize;
+    unsafe {
+        ptr::copy_nonoverlapping(
+            (&metadata as *const Metadata<U>).cast::<u8>(),
+            (&mut value as *mut usize).cast::<u8>(),
+            mem::size_of::<Metadata<U>>(),
+        );
+    }
+    value
+}
@@ -1,37 +1,100 @@
 //! Database errors
 
-use crate::container::TypeIdDeterminationError;
+use super::{TypeDatabase, TypeDatabaseExt};
+use crate::{
+    container::{Coerced, Coercible, Metadata, TypeIdDeterminationError},
+    DynCast,
+};
 use core::{
     any::{type_name, TypeId},
     fmt,
     marker::PhantomData,
+    ptr,
 };
 
-#[cfg(feature = "thiserror")]
-use thiserror::Error;
+#[cfg(any(feature = "debug-registry", feature = "debug-casts"))]
+use core::panic::Location;
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::string::{String, ToString};
+#[cfg(all(feature = "names", not(feature = "std")))]
+use alloc::sync::Arc;
+#[cfg(all(feature = "names", feature = "std"))]
+use std::sync::Arc;
+
+#[cfg(feature = "error-in-core")]
+use core::error::Error;
+#[cfg(all(feature = "std", not(feature = "error-in-core")))]
+use std::error::Error;
+
+/// The kind of a [`DatabaseError`], [`DatabaseEntryError`] or [`CastError`],
+/// independent of their type parameters.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The database has not been initialized.
+    NotInitialized,
+    /// The database has been sealed.
+    Sealed,
+    /// The requested target is not registered in the database.
+    TargetNotRegistered,
+    /// The concrete type is not registered for the requested target.
+    TypeNotRegisteredForTarget,
+    /// The concrete type is registered for the requested target, but the
+    /// instance does not satisfy its predicate.
+    PredicateNotSatisfied,
+    /// The concrete type could not be determined.
+    TypeDetermination,
+}
 
 /// Error that arose on accessing a database.
-#[cfg_attr(feature = "thiserror", derive(Error))]
 #[non_exhaustive]
 pub enum DatabaseError<U>
 where
     U: ?Sized,
 {
     /// The database has not been initialized.
-    #[cfg_attr(feature = "thiserror", error("database not initialized"))]
     NotInitialized,
 
+    /// The database has been sealed, and can no longer be mutated.
+    Sealed,
+
     /// The `requested_type` is not registered in the database.
-    #[cfg_attr(feature = "thiserror", error(
-        "requested type <{}> not registered in database",
-        type_name::<U>(),
-    ))]
     RequestedTypeNotInDatabase {
         /// The type that was requested.
         requested_type: PhantomData<U>,
     },
 }
 
+impl<U> DatabaseError<U>
+where
+    U: ?Sized,
+{
+    /// The kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::NotInitialized => ErrorKind::NotInitialized,
+            Self::Sealed => ErrorKind::Sealed,
+            Self::RequestedTypeNotInDatabase { .. } => ErrorKind::TargetNotRegistered,
+        }
+    }
+
+    /// This same error, for a request of `V` rather than of `U`.
+    pub(crate) fn with_requested_type<V>(self) -> DatabaseError<V>
+    where
+        V: ?Sized,
+    {
+        match self {
+            Self::NotInitialized => DatabaseError::NotInitialized,
+            Self::Sealed => DatabaseError::Sealed,
+            Self::RequestedTypeNotInDatabase { .. } => DatabaseError::RequestedTypeNotInDatabase {
+                requested_type: PhantomData,
+            },
+        }
+    }
+}
+
 impl<U> fmt::Debug for DatabaseError<U>
 where
     U: ?Sized,
@@ -44,6 +107,8 @@ where
         match *self {
             NotInitialized => write!(f, "NotInitialized"),
 
+            Sealed => write!(f, "Sealed"),
+
             RequestedTypeNotInDatabase { requested_type: _ } => f
                 .debug_tuple("RequestedTypeNotInDatabase")
                 .field(&type_name::<U>())
@@ -52,8 +117,45 @@ where
     }
 }
 
+impl<U> fmt::Display for DatabaseError<U>
+where
+    U: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotInitialized => f.write_str("database not initialized"),
+            Self::Sealed => f.write_str("database sealed"),
+            Self::RequestedTypeNotInDatabase { .. } => write!(
+                f,
+                "requested type <{}> not registered in database",
+                type_name::<U>(),
+            ),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "error-in-core"))]
+impl<U> Error for DatabaseError<U> where U: ?Sized {}
+
+#[cfg(feature = "defmt")]
+impl<U> defmt::Format for DatabaseError<U>
+where
+    U: ?Sized,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Self::NotInitialized => defmt::write!(f, "database not initialized"),
+            Self::Sealed => defmt::write!(f, "database sealed"),
+            Self::RequestedTypeNotInDatabase { .. } => defmt::write!(
+                f,
+                "requested type <{=str}> not registered in database",
+                type_name::<U>(),
+            ),
+        }
+    }
+}
+
 /// Error that arose on accessing a database entry.
-#[cfg_attr(feature = "thiserror", derive(Error))]
 #[non_exhaustive]
 pub enum DatabaseEntryError<U, P>
 where
@@ -61,7 +163,6 @@ where
     P: ?Sized,
 {
     /// The specified database error occurred.
-    #[cfg_attr(feature = "thiserror", error(transparent))]
     DatabaseError {
         /// The database error.
         error: DatabaseError<U>,
@@ -69,13 +170,8 @@ where
 
     /// The concrete type underlying the provided instance of `instance_type`
     /// could not be determined, for the specified `reason`.
-    #[cfg_attr(feature = "thiserror", error(
-        "unable to determine concrete type from provided instance of <{}>: {reason}",
-        type_name::<P>(),
-    ))]
     ConcreteTypeDeterminationFailure {
         /// The reason that the concrete type could not be determined.
-        #[cfg_attr(feature = "thiserror", source)]
         reason: TypeIdDeterminationError,
 
         /// The pointer type.
@@ -85,11 +181,6 @@ where
     /// The provided instance of `P` has the underlying concrete type with the
     /// specified `type_id`, but that type is not registered in the database for
     /// the `requested_type`.
-    #[cfg_attr(feature = "thiserror", error(
-        "provided instance of <{}> has concrete {type_id:?}, which is not registered in the database for target type <{}>",
-        type_name::<P>(),
-        type_name::<U>(),
-    ))]
     ConcreteTypeNotRegisteredForTarget {
         /// The [`TypeId`] of the concrete type underlying the provided instance
         /// of `P`.
@@ -100,9 +191,100 @@ where
 
         /// The pointer type.
         instance_type: PhantomData<P>,
+
+        /// Names that may help to explain the omission.
+        candidates: Candidates,
+    },
+
+    /// The provided instance of `P` has the underlying concrete type with the
+    /// specified `type_id`, which is registered in the database for the
+    /// `requested_type` subject to a predicate that the instance does not
+    /// satisfy.
+    PredicateNotSatisfied {
+        /// The [`TypeId`] of the concrete type underlying the provided instance
+        /// of `P`.
+        type_id: TypeId,
+
+        /// The type that was requested.
+        requested_type: PhantomData<U>,
+
+        /// The pointer type.
+        instance_type: PhantomData<P>,
     },
 }
 
+impl<U, P> DatabaseEntryError<U, P>
+where
+    U: 'static + ?Sized,
+    P: ?Sized,
+{
+    /// The kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::DatabaseError { error } => error.kind(),
+            Self::ConcreteTypeDeterminationFailure { .. } => ErrorKind::TypeDetermination,
+            Self::ConcreteTypeNotRegisteredForTarget { .. } => {
+                ErrorKind::TypeNotRegisteredForTarget
+            }
+            Self::PredicateNotSatisfied { .. } => ErrorKind::PredicateNotSatisfied,
+        }
+    }
+
+    /// The [`TypeId`] of the concrete type underlying the provided instance,
+    /// if it was determined.
+    pub fn concrete_type_id(&self) -> Option<TypeId> {
+        match *self {
+            Self::ConcreteTypeNotRegisteredForTarget { type_id, .. }
+            | Self::PredicateNotSatisfied { type_id, .. } => Some(type_id),
+            _ => None,
+        }
+    }
+
+    /// This same error, for an instance of `Q` rather than of `P`.
+    pub(crate) fn with_instance_type<Q>(self) -> DatabaseEntryError<U, Q>
+    where
+        Q: ?Sized,
+    {
+        self.retyped()
+    }
+
+    /// This same error, for a request of `V` on an instance of `Q`.
+    fn retyped<V, Q>(self) -> DatabaseEntryError<V, Q>
+    where
+        V: 'static + ?Sized,
+        Q: ?Sized,
+    {
+        match self {
+            Self::DatabaseError { error } => DatabaseEntryError::DatabaseError {
+                error: error.with_requested_type(),
+            },
+            Self::ConcreteTypeDeterminationFailure { reason, .. } => {
+                DatabaseEntryError::ConcreteTypeDeterminationFailure {
+                    reason,
+                    instance_type: PhantomData,
+                }
+            }
+            Self::ConcreteTypeNotRegisteredForTarget {
+                type_id,
+                candidates,
+                ..
+            } => DatabaseEntryError::ConcreteTypeNotRegisteredForTarget {
+                type_id,
+                requested_type: PhantomData,
+                instance_type: PhantomData,
+                candidates,
+            },
+            Self::PredicateNotSatisfied { type_id, .. } => {
+                DatabaseEntryError::PredicateNotSatisfied {
+                    type_id,
+                    requested_type: PhantomData,
+                    instance_type: PhantomData,
+                }
+            }
+        }
+    }
+}
+
 impl<U, P> fmt::Debug for DatabaseEntryError<U, P>
 where
     U: 'static + ?Sized,
@@ -129,16 +311,150 @@ where
                 ref type_id,
                 requested_type: _,
                 instance_type: _,
+                ref candidates,
             } => f
                 .debug_tuple("ConcreteTypeNotRegisteredForTarget")
                 .field(type_id)
                 .field(&type_name::<U>())
                 .field(&type_name::<P>())
+                .field(candidates)
+                .finish(),
+
+            PredicateNotSatisfied {
+                ref type_id,
+                requested_type: _,
+                instance_type: _,
+            } => f
+                .debug_tuple("PredicateNotSatisfied")
+                .field(type_id)
+                .field(&type_name::<U>())
+                .field(&type_name::<P>())
                 .finish(),
         }
     }
 }
 
+impl<U, P> fmt::Display for DatabaseEntryError<U, P>
+where
+    U: 'static + ?Sized,
+    P: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.erased().fmt(type_name::<U>(), type_name::<P>(), f)
+    }
+}
+
+impl<U, P> DatabaseEntryError<U, P>
+where
+    U: 'static + ?Sized,
+    P: ?Sized,
+{
+    /// The data of this error, erased of its type parameters.
+    fn erased(&self) -> ErasedEntryError<'_> {
+        match self {
+            Self::DatabaseError {
+                error: DatabaseError::RequestedTypeNotInDatabase { .. },
+            } => ErasedEntryError::TargetNotInDatabase,
+            Self::DatabaseError { error } => ErasedEntryError::Database(error),
+            Self::ConcreteTypeDeterminationFailure { reason, .. } => {
+                ErasedEntryError::DeterminationFailure(reason)
+            }
+            Self::ConcreteTypeNotRegisteredForTarget {
+                type_id,
+                candidates,
+                ..
+            } => ErasedEntryError::NotRegistered(*type_id, candidates),
+            Self::PredicateNotSatisfied { type_id, .. } => {
+                ErasedEntryError::PredicateNotSatisfied(*type_id)
+            }
+        }
+    }
+}
+
+/// The data of a [`DatabaseEntryError`], erased of its type parameters so that
+/// formatting is not monomorphized for every target and pointer type.
+enum ErasedEntryError<'a> {
+    TargetNotInDatabase,
+    Database(&'a dyn fmt::Display),
+    DeterminationFailure(&'a TypeIdDeterminationError),
+    NotRegistered(TypeId, &'a Candidates),
+    PredicateNotSatisfied(TypeId),
+}
+
+impl ErasedEntryError<'_> {
+    /// Format this error, which arose casting to `target` from `pointer`.
+    fn fmt(self, target: &str, pointer: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TargetNotInDatabase => {
+                write!(f, "requested type <{}> not registered in database", target)
+            }
+            Self::Database(error) => fmt::Display::fmt(error, f),
+            Self::DeterminationFailure(reason) => write!(
+                f,
+                "unable to determine concrete type from provided instance of <{}>: {}",
+                pointer, reason,
+            ),
+            Self::NotRegistered(type_id, candidates) => write!(
+                f,
+                "provided instance of <{}> has concrete {:?}, which is not registered in the database for target type <{}>{}",
+                pointer, type_id, target, candidates,
+            ),
+            Self::PredicateNotSatisfied(type_id) => write!(
+                f,
+                "provided instance of <{}> has concrete {:?}, which is registered in the database for target type <{}> but does not satisfy its predicate",
+                pointer, type_id, target,
+            ),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "error-in-core"))]
+impl<U, P> Error for DatabaseEntryError<U, P>
+where
+    U: 'static + ?Sized,
+    P: ?Sized,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::DatabaseError { error } => error.source(),
+            Self::ConcreteTypeDeterminationFailure { reason, .. } => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<U, P> defmt::Format for DatabaseEntryError<U, P>
+where
+    U: 'static + ?Sized,
+    P: ?Sized,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Self::DatabaseError { error } => defmt::Format::format(error, f),
+            Self::ConcreteTypeDeterminationFailure { reason, .. } => defmt::write!(
+                f,
+                "unable to determine concrete type from provided instance of <{=str}>: {}",
+                type_name::<P>(),
+                reason,
+            ),
+            Self::ConcreteTypeNotRegisteredForTarget { candidates, .. } => defmt::write!(
+                f,
+                "provided instance of <{=str}> has a concrete type that is not registered in the database for target type <{=str}>{}",
+                type_name::<P>(),
+                type_name::<U>(),
+                candidates,
+            ),
+            Self::PredicateNotSatisfied { .. } => defmt::write!(
+                f,
+                "provided instance of <{=str}> has a concrete type that is registered in the database for target type <{=str}> but does not satisfy its predicate",
+                type_name::<P>(),
+                type_name::<U>(),
+            ),
+        }
+    }
+}
+
 impl<U, P> From<DatabaseError<U>> for DatabaseEntryError<U, P>
 where
     U: 'static + ?Sized,
@@ -162,9 +478,123 @@ where
     }
 }
 
+/// Error that arose on viewing an instance of `P` as every target in the list
+/// `T` together (see [`dyn_cast_multi`][crate::DynCastRef::dyn_cast_multi]).
+#[non_exhaustive]
+pub struct MultiCastError<T, P>
+where
+    T: 'static + ?Sized,
+    P: ?Sized,
+{
+    /// The [`TypeId`] of the first target in the list that could not be viewed.
+    pub target: TypeId,
+
+    /// The name of that target.
+    pub target_name: &'static str,
+
+    /// The error that arose on viewing that target.
+    pub source: DatabaseEntryError<T, P>,
+}
+
+impl<T, P> MultiCastError<T, P>
+where
+    T: 'static + ?Sized,
+    P: ?Sized,
+{
+    /// The error `source` that arose on viewing an instance of `P` as `U`.
+    pub(crate) fn new<U>(source: DatabaseEntryError<U, P>) -> Self
+    where
+        U: 'static + ?Sized,
+    {
+        Self {
+            target: TypeId::of::<U>(),
+            target_name: type_name::<U>(),
+            source: source.retyped(),
+        }
+    }
+
+    /// The kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        self.source.kind()
+    }
+
+    /// The [`TypeId`] of the concrete type underlying the instance, if it was
+    /// determined.
+    pub fn concrete_type_id(&self) -> Option<TypeId> {
+        self.source.concrete_type_id()
+    }
+}
+
+impl<T, P> fmt::Debug for MultiCastError<T, P>
+where
+    T: 'static + ?Sized,
+    P: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiCastError")
+            .field("target", &self.target_name)
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl<T, P> fmt::Display for MultiCastError<T, P>
+where
+    T: 'static + ?Sized,
+    P: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.source
+            .erased()
+            .fmt(self.target_name, type_name::<P>(), f)
+    }
+}
+
+#[cfg(any(feature = "std", feature = "error-in-core"))]
+impl<T, P> Error for MultiCastError<T, P>
+where
+    T: 'static + ?Sized,
+    P: ?Sized,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.source()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T, P> defmt::Format for MultiCastError<T, P>
+where
+    T: 'static + ?Sized,
+    P: ?Sized,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match &self.source {
+            DatabaseEntryError::DatabaseError {
+                error: DatabaseError::RequestedTypeNotInDatabase { .. },
+            } => defmt::write!(
+                f,
+                "requested type <{=str}> not registered in database",
+                self.target_name,
+            ),
+            DatabaseEntryError::ConcreteTypeNotRegisteredForTarget { candidates, .. } => defmt::write!(
+                f,
+                "provided instance of <{=str}> has a concrete type that is not registered in the database for target type <{=str}>{}",
+                type_name::<P>(),
+                self.target_name,
+                candidates,
+            ),
+            DatabaseEntryError::PredicateNotSatisfied { .. } => defmt::write!(
+                f,
+                "provided instance of <{=str}> has a concrete type that is registered in the database for target type <{=str}> but does not satisfy its predicate",
+                type_name::<P>(),
+                self.target_name,
+            ),
+            source => defmt::Format::format(source, f),
+        }
+    }
+}
+
 /// Error that arose on attempting to cast `pointer` to `U`.
-#[cfg_attr(feature = "thiserror", derive(Error))]
-#[cfg_attr(feature = "thiserror", error("{source}"))]
 #[non_exhaustive]
 pub struct CastError<U, P>
 where
@@ -175,6 +605,99 @@ where
     /// The (unmodified) pointer on which casting had been attempted, in order
     /// to return ownership back to the caller.
     pub pointer: P,
+    #[cfg(feature = "debug-casts")]
+    location: &'static Location<'static>,
+}
+
+impl<U, P> CastError<U, P>
+where
+    U: 'static + ?Sized,
+{
+    /// The error `source` that arose on attempting to cast `pointer`, from
+    /// the caller's location.
+    #[cfg_attr(feature = "debug-casts", track_caller)]
+    pub fn new(source: DatabaseEntryError<U, P>, pointer: P) -> Self {
+        Self {
+            source,
+            pointer,
+            #[cfg(feature = "debug-casts")]
+            location: Location::caller(),
+        }
+    }
+
+    /// The location of the call that attempted the cast.
+    #[cfg(feature = "debug-casts")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "debug-casts")))]
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// The kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        self.source.kind()
+    }
+
+    /// The [`TypeId`] of the concrete type underlying the pointer, if it was
+    /// determined.
+    pub fn concrete_type_id(&self) -> Option<TypeId> {
+        self.source.concrete_type_id()
+    }
+
+    /// The name of the target type `U`.
+    pub fn target_type_name(&self) -> &'static str {
+        type_name::<U>()
+    }
+
+    /// The (unmodified) pointer on which casting had been attempted.
+    pub fn into_pointer(self) -> P {
+        self.pointer
+    }
+
+    /// Map the pointer with `f`, retaining the error that arose.
+    pub fn map_pointer<Q, F>(self, f: F) -> CastError<U, Q>
+    where
+        F: FnOnce(P) -> Q,
+    {
+        CastError {
+            source: self.source.with_instance_type(),
+            pointer: f(self.pointer),
+            #[cfg(feature = "debug-casts")]
+            location: self.location,
+        }
+    }
+
+    /// Attempt the cast again, against `db`: for example, after registering
+    /// the concrete type or with a fallback database.
+    #[cfg_attr(feature = "debug-casts", track_caller)]
+    pub fn retry_with<DB>(self, db: &DB) -> Result<P::Coerced<U>, Self>
+    where
+        P: DynCast<DB>,
+        P::Coerced<U>: Sized,
+        P::Inner: Coercible,
+        Coerced<P::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+        DB: TypeDatabaseExt,
+    {
+        self.pointer.dyn_cast(db)
+    }
+
+    /// Suggest the name, if known to `db`, of the concrete type that was not
+    /// registered for the target.
+    #[cfg_attr(not(feature = "names"), allow(unused_mut, unused_variables))]
+    pub(crate) fn named_by<DB>(mut self, db: &DB) -> Self
+    where
+        DB: ?Sized + TypeDatabase,
+    {
+        #[cfg(feature = "names")]
+        if let DatabaseEntryError::ConcreteTypeNotRegisteredForTarget {
+            type_id,
+            ref mut candidates,
+            ..
+        } = self.source
+        {
+            candidates.type_name = db.concrete_type_name(type_id);
+        }
+        self
+    }
 }
 
 impl<U, P> fmt::Debug for CastError<U, P>
@@ -186,9 +709,539 @@ where
         let Self {
             ref source,
             pointer: _,
+            #[cfg(feature = "debug-casts")]
+            location,
         } = *self;
-        f.debug_struct("Error")
-            .field("source", source)
-            .finish_non_exhaustive()
+        let mut f = f.debug_struct("Error");
+        f.field("source", source);
+        #[cfg(feature = "debug-casts")]
+        f.field("location", &location);
+        f.finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "anyhow")]
+#[cfg_attr(docsrs, doc(cfg(feature = "anyhow")))]
+impl<U, P> CastError<U, P>
+where
+    U: 'static + ?Sized,
+{
+    /// Convert into an [`anyhow::Error`], dropping the pointer but retaining
+    /// the names of the pointer and target types in its context.
+    pub fn into_anyhow(self) -> anyhow::Error {
+        anyhow::Error::msg(self.source.to_string()).context(format!(
+            "unable to cast <{}> to <{}>",
+            type_name::<P>(),
+            type_name::<U>(),
+        ))
+    }
+}
+
+impl<U, P> fmt::Display for CastError<U, P>
+where
+    U: 'static + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.source, f)?;
+        #[cfg(feature = "debug-casts")]
+        write!(f, " (cast at {})", self.location)?;
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "std", feature = "error-in-core"))]
+impl<U, P> Error for CastError<U, P>
+where
+    U: 'static + ?Sized,
+    P: 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<U, P> defmt::Format for CastError<U, P>
+where
+    U: 'static + ?Sized,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::Format::format(&self.source, f);
+        #[cfg(feature = "debug-casts")]
+        defmt::write!(
+            f,
+            " (cast at {=str}:{=u32})",
+            self.location.file(),
+            self.location.line(),
+        );
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+impl<U, P> CastError<U, P>
+where
+    U: 'static + ?Sized,
+{
+    /// Convert into an [`OwnedCastError`], dropping the pointer so that the
+    /// error can be sent between threads irrespective of its type.
+    pub fn into_owned(self) -> OwnedCastError {
+        OwnedCastError {
+            kind: self.kind(),
+            target_type_name: type_name::<U>(),
+            pointer_type_name: type_name::<P>(),
+            concrete_type_id: self.concrete_type_id(),
+            message: self.to_string(),
+        }
+    }
+}
+
+/// A [`CastError`] from which the pointer has been dropped (see
+/// [`into_owned`][CastError::into_owned]).
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct OwnedCastError {
+    /// The kind of the error that arose.
+    pub kind: ErrorKind,
+    /// The name of the target type.
+    pub target_type_name: &'static str,
+    /// The name of the pointer type on which casting had been attempted.
+    pub pointer_type_name: &'static str,
+    /// The [`TypeId`] of the concrete type underlying the pointer, if it was
+    /// determined.
+    pub concrete_type_id: Option<TypeId>,
+    /// A description of the error that arose.
+    pub message: String,
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for OwnedCastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unable to cast <{}> to <{}>: {}",
+            self.pointer_type_name, self.target_type_name, self.message,
+        )
+    }
+}
+
+#[cfg(all(feature = "alloc", any(feature = "std", feature = "error-in-core")))]
+impl Error for OwnedCastError {}
+
+#[cfg(all(feature = "alloc", feature = "defmt"))]
+impl defmt::Format for OwnedCastError {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "unable to cast <{=str}> to <{=str}>: {=str}",
+            self.pointer_type_name,
+            self.target_type_name,
+            self.message.as_str(),
+        );
+    }
+}
+
+/// Error that arose on attempting to upgrade a weak reference and cast the
+/// resulting strong reference `P` to `U` (see
+/// [`dyn_cast_upgraded`][crate::DynCastUpgraded::dyn_cast_upgraded]).
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+#[non_exhaustive]
+pub enum UpgradeCastError<U, P>
+where
+    U: 'static + ?Sized,
+{
+    /// The weak reference could not be upgraded, as the value to which it
+    /// referred is no longer available.
+    Dangling {
+        /// The name of the Weak reference type that could not be upgraded
+        type_name: &'static str,
+    },
+
+    /// The upgraded strong reference could not be cast.
+    Cast(CastError<U, P>),
+}
+
+#[cfg(feature = "alloc")]
+impl<U, P> fmt::Debug for UpgradeCastError<U, P>
+where
+    U: 'static + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dangling { type_name } => f
+                .debug_struct("Dangling")
+                .field("type_name", type_name)
+                .finish(),
+            Self::Cast(error) => f.debug_tuple("Cast").field(error).finish(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<U, P> fmt::Display for UpgradeCastError<U, P>
+where
+    U: 'static + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dangling { type_name } => write!(f, "{} was dangling", type_name),
+            Self::Cast(error) => fmt::Display::fmt(error, f),
+        }
+    }
+}
+
+#[cfg(all(feature = "alloc", any(feature = "std", feature = "error-in-core")))]
+impl<U, P> Error for UpgradeCastError<U, P> where U: 'static + ?Sized {}
+
+#[cfg(all(feature = "alloc", feature = "defmt"))]
+impl<U, P> defmt::Format for UpgradeCastError<U, P>
+where
+    U: 'static + ?Sized,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Self::Dangling { type_name } => defmt::write!(f, "{=str} was dangling", type_name),
+            Self::Cast(error) => defmt::Format::format(error, f),
+        }
+    }
+}
+
+/// Error that arose at some step of a chain of casts (see
+/// [`and_then_cast`][crate::AndThenCast::and_then_cast]).
+pub enum ChainedCastError<E, U, P>
+where
+    U: 'static + ?Sized,
+{
+    /// An earlier step of the chain failed with the given error, so this step
+    /// was not attempted.
+    Previous(E),
+
+    /// This step of the chain failed with the given error, which holds the
+    /// pointer that resulted from the earlier steps.
+    Cast(CastError<U, P>),
+}
+
+impl<E, U, P> fmt::Debug for ChainedCastError<E, U, P>
+where
+    E: fmt::Debug,
+    U: 'static + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Previous(error) => f.debug_tuple("Previous").field(error).finish(),
+            Self::Cast(error) => f.debug_tuple("Cast").field(error).finish(),
+        }
+    }
+}
+
+impl<E, U, P> fmt::Display for ChainedCastError<E, U, P>
+where
+    E: fmt::Display,
+    U: 'static + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Previous(error) => fmt::Display::fmt(error, f),
+            Self::Cast(error) => fmt::Display::fmt(error, f),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "error-in-core"))]
+impl<E, U, P> Error for ChainedCastError<E, U, P>
+where
+    E: fmt::Debug + fmt::Display,
+    U: 'static + ?Sized,
+{
+}
+
+#[cfg(feature = "defmt")]
+impl<E, U, P> defmt::Format for ChainedCastError<E, U, P>
+where
+    E: defmt::Format,
+    U: 'static + ?Sized,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Self::Previous(error) => defmt::Format::format(error, f),
+            Self::Cast(error) => defmt::Format::format(error, f),
+        }
+    }
+}
+
+/// Names that may help to explain why a concrete type is not registered for
+/// a target: these are only collected with the `names` feature.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Candidates {
+    /// The name of the concrete type, if known to the database (for example,
+    /// because it is registered for some other target).
+    #[cfg(feature = "names")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "names")))]
+    pub type_name: Option<&'static str>,
+
+    /// The names of the concrete types that are registered for the target,
+    /// shared with the entry that holds them.
+    #[cfg(feature = "names")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "names")))]
+    pub registered: Arc<[&'static str]>,
+}
+
+impl fmt::Display for Candidates {
+    #[cfg_attr(not(feature = "names"), allow(unused_variables))]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "names")]
+        {
+            if let Some(type_name) = self.type_name {
+                write!(
+                    f,
+                    "; did you forget to register <{}> (for example, with rtti!)?",
+                    type_name,
+                )?;
+            }
+            for (i, name) in self.registered.iter().enumerate() {
+                let separator = if i == 0 {
+                    "; registered types are"
+                } else {
+                    ","
+                };
+                write!(f, "{} <{}>", separator, name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Candidates {
+    #[cfg_attr(not(feature = "names"), allow(unused_variables))]
+    fn format(&self, f: defmt::Formatter<'_>) {
+        #[cfg(feature = "names")]
+        {
+            if let Some(type_name) = self.type_name {
+                defmt::write!(
+                    f,
+                    "; did you forget to register <{=str}> (for example, with rtti!)?",
+                    type_name,
+                );
+            }
+            if !self.registered.is_empty() {
+                defmt::write!(f, "; registered types are {=[?]}", self.registered[..]);
+            }
+        }
+    }
+}
+
+/// Details of an existing registration with which another conflicts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct PreviousRegistration {
+    /// The name with which the concrete type was previously registered, if any.
+    pub type_name: Option<&'static str>,
+
+    /// The location of the previous registration, if known.
+    #[cfg(feature = "debug-registry")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "debug-registry")))]
+    pub location: Option<&'static Location<'static>>,
+}
+
+impl fmt::Display for PreviousRegistration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "previously registered as <{}>",
+            self.type_name.unwrap_or("?")
+        )?;
+        #[cfg(feature = "debug-registry")]
+        if let Some(location) = self.location {
+            write!(f, " at {}", location)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for PreviousRegistration {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "previously registered as <{=str}>",
+            self.type_name.unwrap_or("?"),
+        );
+        #[cfg(feature = "debug-registry")]
+        if let Some(location) = self.location {
+            defmt::write!(f, " at {=str}:{=u32}", location.file(), location.line());
+        }
+    }
+}
+
+/// Error that arose on attempting to register a concrete type as an
+/// implementor of `U`.
+#[non_exhaustive]
+pub enum RegistrationError<U>
+where
+    U: ?Sized,
+{
+    /// The concrete type with the specified `type_id` is already registered
+    /// for `U`, with the same metadata.
+    AlreadyRegistered {
+        /// The [`TypeId`] of the concrete type.
+        type_id: TypeId,
+
+        /// The existing registration.
+        previous: PreviousRegistration,
+
+        /// The target type.
+        target: PhantomData<U>,
+    },
+
+    /// The concrete type with the specified `type_id` is already registered
+    /// for `U`, but with different metadata: this may indicate that distinct
+    /// definitions of the type have been linked, for example into different
+    /// dynamic libraries.  However, as vtables are not guaranteed to be unique,
+    /// it can also arise from registrations made in different codegen units.
+    ConflictingRegistration {
+        /// The [`TypeId`] of the concrete type.
+        type_id: TypeId,
+
+        /// The existing registration.
+        previous: PreviousRegistration,
+
+        /// The target type.
+        target: PhantomData<U>,
+    },
+
+    /// The concrete type with the specified `type_id` cannot be tagged with
+    /// `name`, because another concrete type is already so tagged for `U`.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    DuplicateTag {
+        /// The [`TypeId`] of the concrete type.
+        type_id: TypeId,
+
+        /// The tag that is already in use.
+        name: &'static str,
+
+        /// The target type.
+        target: PhantomData<U>,
+    },
+}
+
+impl<U> fmt::Debug for RegistrationError<U>
+where
+    U: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[allow(clippy::enum_glob_use)]
+        use RegistrationError::*;
+
+        #[allow(clippy::unneeded_field_pattern)]
+        match *self {
+            AlreadyRegistered {
+                ref type_id,
+                ref previous,
+                target: _,
+            } => f
+                .debug_tuple("AlreadyRegistered")
+                .field(type_id)
+                .field(previous)
+                .field(&type_name::<U>())
+                .finish(),
+
+            ConflictingRegistration {
+                ref type_id,
+                ref previous,
+                target: _,
+            } => f
+                .debug_tuple("ConflictingRegistration")
+                .field(type_id)
+                .field(previous)
+                .field(&type_name::<U>())
+                .finish(),
+
+            #[cfg(feature = "serde")]
+            DuplicateTag {
+                ref type_id,
+                name,
+                target: _,
+            } => f
+                .debug_tuple("DuplicateTag")
+                .field(type_id)
+                .field(&name)
+                .field(&type_name::<U>())
+                .finish(),
+        }
+    }
+}
+
+impl<U> fmt::Display for RegistrationError<U>
+where
+    U: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlreadyRegistered {
+                type_id, previous, ..
+            } => write!(
+                f,
+                "concrete {:?} already registered for target type <{}>, {}",
+                type_id,
+                type_name::<U>(),
+                previous,
+            ),
+            Self::ConflictingRegistration {
+                type_id, previous, ..
+            } => write!(
+                f,
+                "concrete {:?} already registered with conflicting metadata for target type <{}>, {}",
+                type_id,
+                type_name::<U>(),
+                previous,
+            ),
+            #[cfg(feature = "serde")]
+            Self::DuplicateTag { type_id, name, .. } => write!(
+                f,
+                "concrete {:?} cannot be tagged {:?} for target type <{}>, as another type already is",
+                type_id,
+                name,
+                type_name::<U>(),
+            ),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "error-in-core"))]
+impl<U> Error for RegistrationError<U> where U: ?Sized {}
+
+#[cfg(feature = "defmt")]
+impl<U> defmt::Format for RegistrationError<U>
+where
+    U: ?Sized,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Self::AlreadyRegistered { previous, .. } => defmt::write!(
+                f,
+                "concrete type already registered for target type <{=str}>, {}",
+                type_name::<U>(),
+                previous,
+            ),
+            Self::ConflictingRegistration { previous, .. } => defmt::write!(
+                f,
+                "concrete type already registered with conflicting metadata for target type <{=str}>, {}",
+                type_name::<U>(),
+                previous,
+            ),
+            #[cfg(feature = "serde")]
+            Self::DuplicateTag { name, .. } => defmt::write!(
+                f,
+                "concrete type cannot be tagged {=str} for target type <{=str}>, as another type already is",
+                name,
+                type_name::<U>(),
+            ),
+        }
     }
 }
@@ -1,70 +1,1369 @@
 //! A [`HashMap`] implementation of a [`TypeDatabase`].
 
-use super::{Metadata, TypeDatabase, TypeDatabaseEntry};
+use super::{
+    arena::Arena,
+    observer::Observer,
+    raw::{ErasedFatPtr, ErasedRef, ViewVisitor},
+    resolve,
+    small_map::{SmallMap, TypeIdMap},
+    token::Registration,
+    trait_index::TraitIndex,
+    Cloner, Constructor, Detail, DetailKind, Equality, Hashing, Metadata, OverwritePolicy,
+    Predicate, TypeDatabase, TypeDatabaseEntry, TypeDatabaseEntryExt, TypeDatabaseMut,
+};
+use crate::container::{InnermostAny, InnermostTypeId, TypeIdDeterminationError};
 use std::{
     any::{type_name, Any, TypeId},
     collections::HashMap,
     fmt,
+    marker::Unsize,
+    mem,
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    sync::{Arc, OnceLock},
 };
 
+#[cfg(feature = "debug-registry")]
+use std::panic::Location;
+
+#[cfg(feature = "compact-metadata")]
+use super::compact::{MetadataIndex, MetadataTable};
+
+#[cfg(feature = "serde")]
+use super::tagged::Tag;
+
 #[cfg(feature = "global")]
-use std::lazy::SyncOnceCell;
+use super::error::DatabaseError;
+#[cfg(feature = "global")]
+use std::sync::{LazyLock, Mutex, PoisonError};
 
 /// A [`TypeDatabase`] backed by a [`HashMap`].
-#[derive(Debug, Default)]
-pub struct HashMapTypeDatabase(HashMap<TypeId, Box<dyn Any + Send + Sync>>);
+#[derive(Default)]
+pub struct HashMapTypeDatabase {
+    entries: TypeIdMap<StoredEntry>,
+    arena: Arena,
+    subtraits: TypeIdMap<Vec<ErasedFallback>>,
+    target_names: HashMap<&'static str, TypeId>,
+    observer: Option<Arc<dyn Observer>>,
+    overwrite_policy: OverwritePolicy,
+    #[cfg(feature = "compact-metadata")]
+    metadata_table: Arc<MetadataTable>,
+    /// The name of every registered concrete type, built upon first demand
+    /// and discarded whenever any entry may be mutated.
+    #[cfg(feature = "names")]
+    concrete_names: OnceLock<TypeIdMap<&'static str>>,
+    /// The index, if built by [`index_traits`][Self::index_traits], that is
+    /// discarded whenever any entry may be mutated.
+    indexed: Option<TraitIndex>,
+    /// The targets for which each concrete type is registered, built upon
+    /// first demand and discarded whenever any entry may be mutated.
+    registered_targets: OnceLock<TypeIdMap<Vec<TypeId>>>,
+}
+
+/// Lookup of the metadata for a concrete type, with the given [`TypeId`], as
+/// an implementor of `U` (and of any predicate to which that is subject) from
+/// its registration as an implementor of some subtrait of `U`.
+type Fallback<U> = fn(&HashMapTypeDatabase, TypeId) -> Option<(Metadata<U>, Option<Predicate>)>;
+
+/// A [`Fallback`] whose target has been erased, stored under the [`TypeId`] of
+/// that target so that it need never be downcast.
+#[derive(Clone, Copy)]
+struct ErasedFallback(fn());
+
+impl ErasedFallback {
+    /// Erase the target of `fallback`.
+    fn new<U>(fallback: Fallback<U>) -> Self
+    where
+        U: ?Sized,
+    {
+        Self(unsafe { mem::transmute_copy(&fallback) })
+    }
+
+    /// Recover the [`Fallback`] from which this was erased.
+    ///
+    /// # Safety
+    /// This must have been erased from a [`Fallback<U>`].
+    unsafe fn typed<U>(self) -> Fallback<U>
+    where
+        U: ?Sized,
+    {
+        mem::transmute_copy(&self.0)
+    }
+}
+
+/// The metadata and any predicate with which the concrete type with the given
+/// `type_id` is registered in the entry of `db` for `U`.
+fn registered<U>(
+    db: &HashMapTypeDatabase,
+    type_id: TypeId,
+) -> Option<(Metadata<U>, Option<Predicate>)>
+where
+    U: 'static + ?Sized,
+{
+    let entry = db.get_entry::<U>()?;
+    Some((entry.metadata(type_id)?, entry.predicate(type_id)))
+}
+
+/// A [`Fallback`] to the registrations of `Sub`.
+fn upcast_metadata<Sub, Super>(
+    db: &HashMapTypeDatabase,
+    type_id: TypeId,
+) -> Option<(Metadata<Super>, Option<Predicate>)>
+where
+    Sub: 'static + ?Sized + Unsize<Super>,
+    Super: 'static + ?Sized,
+{
+    let (metadata, predicate) =
+        registered::<Sub>(db, type_id).or_else(|| db.fallback_metadata::<Sub>(type_id))?;
+    let sub: *const Sub = ptr::from_raw_parts(ptr::null::<()>(), metadata);
+    Some((ptr::metadata(sub as *const Super), predicate))
+}
+
+/// A [`Fallback`] to the registrations of `Bare`, for `Marked` that differs
+/// from `Bare` only by auto-trait markers and therefore has identical metadata.
+///
+/// Only `Bare`'s own registrations are consulted, so that the fallback in the
+/// opposite direction cannot recurse.
+fn marked_metadata<Bare, Marked>(
+    db: &HashMapTypeDatabase,
+    type_id: TypeId,
+) -> Option<(Metadata<Marked>, Option<Predicate>)>
+where
+    Bare: 'static + ?Sized,
+    Marked: 'static + ?Sized,
+{
+    let (metadata, predicate) = registered::<Bare>(db, type_id)?;
+    Some((unsafe { mem::transmute_copy(&metadata) }, predicate))
+}
 
 /// A [`TypeDatabaseEntry`] backed by a [`HashMap`].
-pub struct HashMapTypeDatabaseEntry<U>(HashMap<TypeId, Metadata<U>>)
+pub struct HashMapTypeDatabaseEntry<U>
+where
+    U: ?Sized,
+{
+    implementors: SmallMap<Implementor<U>>,
+    side: SideTables<U>,
+    observer: Option<Arc<dyn Observer>>,
+    overwrite_policy: OverwritePolicy,
+    #[cfg(feature = "compact-metadata")]
+    metadata_table: Arc<MetadataTable>,
+    /// The sorted names of the registered concrete types, built upon first
+    /// demand and discarded whenever they may change.
+    #[cfg(feature = "names")]
+    registered_names: OnceLock<Arc<[&'static str]>>,
+}
+
+/// The metadata of an [`Implementor`], or `None` if it is instead provided
+/// lazily through the entry's [`SideTables`].
+#[cfg(not(feature = "compact-metadata"))]
+type StoredMetadata<U> = Option<Metadata<U>>;
+
+/// The metadata of an [`Implementor`], as an index into its entry's
+/// [`MetadataTable`].
+#[cfg(feature = "compact-metadata")]
+type StoredMetadata<U> = MetadataIndex<U>;
+
+/// The registration of a concrete type as an implementor of `U`.
+///
+/// Only those details that every registration has (with the enabled features)
+/// are held here: the rest are in the entry's [`SideTables`].
+struct Implementor<U>
+where
+    U: ?Sized,
+{
+    metadata: StoredMetadata<U>,
+    #[cfg(feature = "names")]
+    type_name: Option<&'static str>,
+    #[cfg(feature = "debug-registry")]
+    location: Option<&'static Location<'static>>,
+}
+
+impl<U> Implementor<U>
+where
+    U: ?Sized,
+{
+    /// The name of the concrete type, if recorded.
+    fn type_name(&self) -> Option<&'static str> {
+        #[cfg(feature = "names")]
+        return self.type_name;
+        #[cfg(not(feature = "names"))]
+        None
+    }
+}
+
+impl<U> From<StoredMetadata<U>> for Implementor<U>
+where
+    U: ?Sized,
+{
+    fn from(metadata: StoredMetadata<U>) -> Self {
+        Self {
+            metadata,
+            #[cfg(feature = "names")]
+            type_name: None,
+            #[cfg(feature = "debug-registry")]
+            location: None,
+        }
+    }
+}
+
+/// The details of registrations that few of them have, keyed by the [`TypeId`]
+/// of the registered concrete type, so that those without any occupy no space.
+struct SideTables<U>
+where
+    U: ?Sized,
+{
+    predicates: TypeIdMap<Predicate>,
+    namespaces: TypeIdMap<&'static str>,
+    cloners: TypeIdMap<Cloner<U>>,
+    constructors: TypeIdMap<Constructor<U>>,
+    equalities: TypeIdMap<Equality>,
+    hashings: TypeIdMap<Hashing>,
+    #[cfg(feature = "serde")]
+    tags: TypeIdMap<Tag<U>>,
+    #[cfg(feature = "serde")]
+    tag_names: HashMap<&'static str, TypeId>,
+    #[cfg(not(feature = "compact-metadata"))]
+    lazy: TypeIdMap<LazyMetadata<U>>,
+}
+
+impl<U> SideTables<U>
+where
+    U: ?Sized,
+{
+    /// Forget every detail of the registration of the concrete type with the
+    /// given `type_id`.
+    fn remove(&mut self, type_id: &TypeId) {
+        self.predicates.remove(type_id);
+        self.namespaces.remove(type_id);
+        self.cloners.remove(type_id);
+        self.constructors.remove(type_id);
+        self.equalities.remove(type_id);
+        self.hashings.remove(type_id);
+        #[cfg(not(feature = "compact-metadata"))]
+        self.lazy.remove(type_id);
+        #[cfg(feature = "serde")]
+        if let Some(tag) = self.tags.remove(type_id) {
+            self.forget_tag_name(tag.name, type_id);
+        }
+    }
+
+    /// Forget that `name` is the tag of the concrete type with the given
+    /// `type_id`, unless it has since been given to another.
+    #[cfg(feature = "serde")]
+    fn forget_tag_name(&mut self, name: &str, type_id: &TypeId) {
+        if self.tag_names.get(name) == Some(type_id) {
+            self.tag_names.remove(name);
+        }
+    }
+
+    /// The approximate number of bytes allocated on the heap by these tables.
+    fn heap_footprint(&self) -> usize {
+        #[cfg(feature = "serde")]
+        let tags = map_footprint(&self.tags) + map_footprint(&self.tag_names);
+        #[cfg(not(feature = "serde"))]
+        let tags = 0;
+        #[cfg(not(feature = "compact-metadata"))]
+        let lazy = map_footprint(&self.lazy);
+        #[cfg(feature = "compact-metadata")]
+        let lazy = 0;
+        map_footprint(&self.predicates)
+            + map_footprint(&self.namespaces)
+            + map_footprint(&self.cloners)
+            + map_footprint(&self.constructors)
+            + map_footprint(&self.equalities)
+            + map_footprint(&self.hashings)
+            + tags
+            + lazy
+    }
+}
+
+impl<U> Default for SideTables<U>
+where
+    U: ?Sized,
+{
+    fn default() -> Self {
+        Self {
+            predicates: TypeIdMap::default(),
+            namespaces: TypeIdMap::default(),
+            cloners: TypeIdMap::default(),
+            constructors: TypeIdMap::default(),
+            equalities: TypeIdMap::default(),
+            hashings: TypeIdMap::default(),
+            #[cfg(feature = "serde")]
+            tags: TypeIdMap::default(),
+            #[cfg(feature = "serde")]
+            tag_names: HashMap::new(),
+            #[cfg(not(feature = "compact-metadata"))]
+            lazy: TypeIdMap::default(),
+        }
+    }
+}
+
+/// Metadata that will be materialized upon first access by evaluating its
+/// `provider`.
+#[cfg(not(feature = "compact-metadata"))]
+struct LazyMetadata<U>
+where
+    U: ?Sized,
+{
+    metadata: OnceLock<Metadata<U>>,
+    provider: fn() -> Metadata<U>,
+}
+
+#[cfg(not(feature = "compact-metadata"))]
+impl<U> LazyMetadata<U>
+where
+    U: ?Sized,
+{
+    fn new(provider: fn() -> Metadata<U>) -> Self {
+        Self {
+            metadata: OnceLock::new(),
+            provider,
+        }
+    }
+
+    fn get(&self) -> Metadata<U> {
+        *self.metadata.get_or_init(self.provider)
+    }
+}
+
+/// The type-erased interface of a [`HashMapTypeDatabaseEntry`].
+trait ErasedEntry
+where
+    Self: Any + Send + Sync + fmt::Debug,
+{
+    fn remove_namespace(&mut self, namespace: &str);
+
+    fn set_overwrite_policy(&mut self, policy: OverwritePolicy);
+
+    fn iter_namespace<'a>(&'a self, namespace: &'a str) -> Box<dyn Iterator<Item = TypeId> + 'a>;
+
+    fn target_name(&self) -> &'static str;
+
+    fn type_names(&self) -> Box<dyn Iterator<Item = (TypeId, Option<&'static str>)> + '_>;
+
+    fn fmt_verbose(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    fn memory_footprint(&self) -> usize;
+
+    fn implementors_among(
+        &self,
+        db: &HashMapTypeDatabase,
+        type_ids: &[TypeId],
+    ) -> Vec<(TypeId, bool)>;
+
+    unsafe fn cast_raw(
+        &self,
+        db: &HashMapTypeDatabase,
+        type_id: TypeId,
+        data: *const (),
+    ) -> Option<ErasedFatPtr>;
+
+    fn implemented_by(&self, db: &HashMapTypeDatabase, data: &dyn Any) -> bool;
+
+    fn view(&self, db: &HashMapTypeDatabase, data: &dyn Any) -> Option<ErasedFatPtr>;
+}
+
+impl<U> ErasedEntry for HashMapTypeDatabaseEntry<U>
+where
+    U: 'static + ?Sized,
+{
+    fn remove_namespace(&mut self, namespace: &str) {
+        HashMapTypeDatabaseEntry::remove_namespace(self, namespace);
+    }
+
+    fn set_overwrite_policy(&mut self, policy: OverwritePolicy) {
+        self.overwrite_policy = policy;
+    }
+
+    fn iter_namespace<'a>(&'a self, namespace: &'a str) -> Box<dyn Iterator<Item = TypeId> + 'a> {
+        Box::new(HashMapTypeDatabaseEntry::iter_namespace(self, namespace))
+    }
+
+    fn target_name(&self) -> &'static str {
+        type_name::<U>()
+    }
+
+    fn type_names(&self) -> Box<dyn Iterator<Item = (TypeId, Option<&'static str>)> + '_> {
+        Box::new(
+            self.implementors
+                .iter()
+                .map(|(&type_id, implementor)| (type_id, implementor.type_name())),
+        )
+    }
+
+    fn fmt_verbose(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut implementors: Vec<_> = self.implementors.iter().collect();
+        implementors.sort_unstable_by_key(|(_, implementor)| implementor.type_name());
+        f.debug_map()
+            .entries(implementors.into_iter().map(|(&type_id, implementor)| {
+                let details = debug_with(move |f| {
+                    let mut f = f.debug_struct("Implementor");
+                    f.field("type_id", &type_id)
+                        .field("namespace", &self.side.namespaces.get(&type_id))
+                        .field("predicated", &self.side.predicates.contains_key(&type_id));
+                    #[cfg(feature = "debug-registry")]
+                    f.field("location", &implementor.location);
+                    f.finish()
+                });
+                (name_or_id(type_id, implementor.type_name()), details)
+            }))
+            .finish()
+    }
+
+    fn memory_footprint(&self) -> usize {
+        HashMapTypeDatabaseEntry::memory_footprint(self)
+    }
+
+    fn implementors_among(
+        &self,
+        db: &HashMapTypeDatabase,
+        type_ids: &[TypeId],
+    ) -> Vec<(TypeId, bool)> {
+        type_ids
+            .iter()
+            .filter_map(|&type_id| {
+                let predicate = if self.contains(type_id) {
+                    self.predicate(type_id)
+                } else {
+                    db.fallback_metadata::<U>(type_id)?.1
+                };
+                Some((type_id, predicate.is_some()))
+            })
+            .collect()
+    }
+
+    unsafe fn cast_raw(
+        &self,
+        db: &HashMapTypeDatabase,
+        type_id: TypeId,
+        data: *const (),
+    ) -> Option<ErasedFatPtr> {
+        if self.contains(type_id) {
+            return TypeDatabaseEntryExt::cast_raw(self, type_id, data);
+        }
+        match db.fallback_metadata::<U>(type_id)? {
+            (metadata, None) => Some(ErasedFatPtr::new::<U>(ptr::from_raw_parts(data, metadata))),
+            (_, Some(_)) => None,
+        }
+    }
+
+    fn implemented_by(&self, db: &HashMapTypeDatabase, data: &dyn Any) -> bool {
+        self.view(db, data).is_some()
+    }
+
+    fn view(&self, db: &HashMapTypeDatabase, data: &dyn Any) -> Option<ErasedFatPtr> {
+        let fallback = |type_id| db.fallback_metadata::<U>(type_id);
+        let metadata = resolve(Ok(self), Any::type_id(data), data, fallback).ok()?;
+        let pointer = ptr::from_raw_parts::<U>((data as *const dyn Any).cast::<()>(), metadata);
+        Some(ErasedFatPtr::new(pointer))
+    }
+}
+
+/// A [`HashMapTypeDatabaseEntry`], of a type that is identified by the key
+/// under which it is stored, which lives in its database's [`Arena`].
+struct StoredEntry(NonNull<dyn ErasedEntry>);
+
+// the entry is itself `Send` and `Sync`, and is owned by the database
+unsafe impl Send for StoredEntry {}
+unsafe impl Sync for StoredEntry {}
+
+impl StoredEntry {
+    /// Move `entry` into `arena`.
+    fn new<U>(arena: &mut Arena, entry: HashMapTypeDatabaseEntry<U>) -> Self
+    where
+        U: 'static + ?Sized,
+    {
+        Self(arena.alloc(entry))
+    }
+
+    /// The stored entry, as an entry for `U`.
+    ///
+    /// # Safety
+    /// The entry must be for `U`, that is it must be keyed by `U`'s [`TypeId`].
+    unsafe fn get<U>(&self) -> &HashMapTypeDatabaseEntry<U>
+    where
+        U: 'static + ?Sized,
+    {
+        self.validate::<U>();
+        self.0.cast().as_ref()
+    }
+
+    /// The stored entry, as an exclusive reference to an entry for `U`.
+    ///
+    /// # Safety
+    /// The entry must be for `U`, that is it must be keyed by `U`'s [`TypeId`].
+    unsafe fn get_mut<U>(&mut self) -> &mut HashMapTypeDatabaseEntry<U>
+    where
+        U: 'static + ?Sized,
+    {
+        self.validate::<U>();
+        self.0.cast().as_mut()
+    }
+
+    #[cfg_attr(not(feature = "validate"), allow(clippy::unused_self))]
+    fn validate<U>(&self)
+    where
+        U: 'static + ?Sized,
+    {
+        #[cfg(feature = "validate")]
+        assert!(
+            (&**self as &dyn Any).is::<HashMapTypeDatabaseEntry<U>>(),
+            "entry keyed by <{}> is of the wrong type",
+            type_name::<U>(),
+        );
+    }
+}
+
+impl Deref for StoredEntry {
+    type Target = dyn ErasedEntry;
+
+    fn deref(&self) -> &Self::Target {
+        // the entry lives as long as the database's arena, which outlives it
+        unsafe { self.0.as_ref() }
+    }
+}
+
+impl DerefMut for StoredEntry {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { self.0.as_mut() }
+    }
+}
+
+impl fmt::Debug for StoredEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// The approximate number of bytes allocated on the heap by `map`, which
+/// comprises a bucket and a control byte for each element of its capacity.
+fn map_footprint<K, V, S>(map: &HashMap<K, V, S>) -> usize {
+    map.capacity() * (mem::size_of::<(K, V)>() + 1)
+}
+
+/// A 64-bit [FNV-1a] hash, whose output is stable across builds and platforms.
+///
+/// [FNV-1a]: http://www.isthe.com/chongo/tech/comp/fnv/index.html#FNV-1a
+#[cfg(feature = "names")]
+struct Fingerprinter(u64);
+
+#[cfg(feature = "names")]
+impl Fingerprinter {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Write `name`, terminated so that consecutive names cannot be confused.
+    fn write_name(&mut self, name: Option<&str>) {
+        self.write(name.unwrap_or("?").as_bytes());
+        self.write(&[0]);
+    }
+}
+
+impl HashMapTypeDatabase {
+    /// Instantiate an empty database, the activity on which will be notified to
+    /// `observer`.
+    pub fn with_observer(observer: Arc<dyn Observer>) -> Self {
+        Self {
+            observer: Some(observer),
+            ..Self::default()
+        }
+    }
+
+    /// Set the policy for registering a concrete type as an implementor of a
+    /// target for which it is already registered, in every entry of this
+    /// database.
+    pub fn set_overwrite_policy(&mut self, policy: OverwritePolicy) {
+        self.overwrite_policy = policy;
+        for entry in self.entries.values_mut() {
+            entry.set_overwrite_policy(policy);
+        }
+    }
+
+    /// Declare that `Sub` is a subtrait of `Super`, so that concrete types
+    /// registered as implementors of `Sub` are also found when casting to
+    /// `Super`, even if not themselves registered as implementors of `Super`.
+    ///
+    /// Declarations are followed transitively: if `Low` is declared a subtrait
+    /// of `Mid`, and `Mid` of `High`, then implementors of `Low` are also found
+    /// when casting to `High`, so each trait need only be related to its
+    /// immediate supertraits.
+    ///
+    /// Any predicate with which a concrete type is registered as an implementor
+    /// of `Sub` applies equally when it is found as an implementor of `Super`.
+    pub fn declare_subtrait<Sub, Super>(&mut self)
+    where
+        Sub: 'static + ?Sized + Unsize<Super>,
+        Super: 'static + ?Sized,
+    {
+        nested_span!(
+            "declare_subtrait",
+            Sub = type_name::<Sub>(),
+            Super = type_name::<Super>()
+        );
+        // so that `Super` is enumerated alongside other targets
+        self.get_entry_mut::<Super>();
+        self.add_fallback::<Super>(upcast_metadata::<Sub, Super>);
+    }
+
+    /// Declare that `Marked` differs from `Bare` only by auto-trait markers (for
+    /// example, `dyn Bar + Send + Sync` and `dyn Bar`), so that concrete types
+    /// registered as implementors of either are also found when casting to the
+    /// other.
+    ///
+    /// # Safety
+    /// `Marked` must be `Bare` with only additional auto traits, and every
+    /// concrete type registered as an implementor of `Bare` must implement
+    /// those auto traits.
+    pub unsafe fn declare_marker_variant<Bare, Marked>(&mut self)
+    where
+        Bare: 'static + ?Sized,
+        Marked: 'static + ?Sized + Unsize<Bare>,
+    {
+        nested_span!(
+            "declare_marker_variant",
+            Bare = type_name::<Bare>(),
+            Marked = type_name::<Marked>(),
+        );
+        assert_eq!(
+            mem::size_of::<Metadata<Bare>>(),
+            mem::size_of::<Metadata<Marked>>(),
+            "<{}> and <{}> have incompatible metadata",
+            type_name::<Bare>(),
+            type_name::<Marked>(),
+        );
+        self.declare_subtrait::<Marked, Bare>();
+        self.get_entry_mut::<Marked>();
+        self.add_fallback::<Marked>(marked_metadata::<Bare, Marked>);
+    }
+
+    /// Consult `fallback` when casting to `U` finds no registration.
+    fn add_fallback<U>(&mut self, fallback: Fallback<U>)
+    where
+        U: 'static + ?Sized,
+    {
+        self.subtraits
+            .entry(TypeId::of::<U>())
+            .or_default()
+            .push(ErasedFallback::new::<U>(fallback));
+    }
+
+    /// Give target `U` the stable `name` by which it can be selected at runtime,
+    /// for example with [`implements_name`][Self::implements_name] or
+    /// [`cast_by_name`][Self::cast_by_name].
+    ///
+    /// Targets can also be selected by their full type name, as rendered by the
+    /// compiler.
+    pub fn name_target<U>(&mut self, name: &'static str)
+    where
+        U: 'static + ?Sized,
+    {
+        nested_span!("name_target", ?name, U = type_name::<U>());
+        let _ = self.target_names.insert(name, TypeId::of::<U>());
+    }
+
+    /// Make `alias` an alternative name for the target that is already named
+    /// `canonical` (by [`name_target`][Self::name_target] or its full type
+    /// name), for example so that lookups by a trait's legacy name continue to
+    /// succeed after it has been renamed or moved.
+    ///
+    /// Returns whether `canonical` named a target of this database.
+    pub fn alias_target(&mut self, alias: &'static str, canonical: &str) -> bool {
+        nested_span!("alias_target", ?alias, ?canonical);
+        match self.target_named(canonical) {
+            Some(target) => {
+                let _ = self.target_names.insert(alias, target);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The [`TypeId`] of the target with the given `name`.
+    fn target_named(&self, name: &str) -> Option<TypeId> {
+        match self.target_names.get(name) {
+            Some(&target) => Some(target),
+            None => self
+                .entries
+                .iter()
+                .find(|(_, entry)| entry.target_name() == name)
+                .map(|(&target, _)| target),
+        }
+    }
+
+    /// The entry for the target with the given `name`.
+    fn entry_named(&self, name: &str) -> Option<&dyn ErasedEntry> {
+        self.entries
+            .get(&self.target_named(name)?)
+            .map(|entry| &**entry)
+    }
+
+    /// A fingerprint of this database's contents, computed from the names of
+    /// its targets and of the concrete types registered as their implementors.
+    ///
+    /// Databases with the same registrations have the same fingerprint,
+    /// irrespective of the order in which those registrations were made, and
+    /// even if built separately (such as by a host and a dynamically loaded
+    /// plugin) provided that the compiler renders the same type names.
+    #[cfg(feature = "names")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "names")))]
+    pub fn fingerprint(&self) -> u64 {
+        let mut entries: Vec<_> = self
+            .entries
+            .values()
+            .map(|entry| {
+                let mut type_names: Vec<_> = entry.type_names().map(|(_, name)| name).collect();
+                type_names.sort_unstable();
+                (entry.target_name(), type_names)
+            })
+            .filter(|(_, type_names)| !type_names.is_empty())
+            .collect();
+        entries.sort_unstable();
+
+        let mut fingerprinter = Fingerprinter::new();
+        for (target_name, type_names) in entries {
+            fingerprinter.write_name(Some(target_name));
+            for type_name in type_names {
+                fingerprinter.write_name(type_name);
+            }
+            fingerprinter.write(&[0]);
+        }
+        fingerprinter.0
+    }
+
+    /// The name of the target, or of the registered concrete type, with the
+    /// given `type_id`.
+    #[cfg(feature = "names")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "names")))]
+    pub fn name_of(&self, type_id: TypeId) -> Option<&'static str> {
+        self.entries
+            .get(&type_id)
+            .map(|entry| entry.target_name())
+            .or_else(|| self.concrete_type_name(type_id))
+    }
+
+    /// The [`TypeId`] of the target, or of the registered concrete type, with
+    /// the given `name`.
+    #[cfg(feature = "names")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "names")))]
+    pub fn type_id_of(&self, name: &str) -> Option<TypeId> {
+        self.entries
+            .iter()
+            .find(|(_, entry)| entry.target_name() == name)
+            .map(|(&type_id, _)| type_id)
+            .or_else(|| {
+                self.entries.values().find_map(|entry| {
+                    entry
+                        .type_names()
+                        .find(|&(_, type_name)| type_name == Some(name))
+                        .map(|(type_id, _)| type_id)
+                })
+            })
+    }
+
+    /// Create a new instance of the registered concrete type with the given
+    /// `name`, if it is a constructible implementor of `U`.
+    ///
+    /// See [`TypeDatabaseExt::create`].
+    #[cfg(feature = "names")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "names")))]
+    pub fn create_named<U>(&self, name: &str) -> Option<Box<U>>
+    where
+        U: 'static + ?Sized,
+    {
+        super::TypeDatabaseExt::create::<U>(self, self.type_id_of(name)?)
+    }
+
+    /// The approximate number of bytes allocated on the heap by this database
+    /// and its entries.
+    pub fn memory_footprint(&self) -> usize {
+        #[cfg(feature = "compact-metadata")]
+        let table = self.metadata_table.memory_footprint();
+        #[cfg(not(feature = "compact-metadata"))]
+        let table = 0;
+        table
+            + map_footprint(&self.entries)
+            + self
+                .entries
+                .values()
+                .map(|entry| mem::size_of_val(&**entry) + entry.memory_footprint())
+                .sum::<usize>()
+    }
+
+    /// Build a [`TraitIndex`] of this database's targets, by which the targets
+    /// for which a concrete type is registered can be tested together.
+    pub fn trait_index(&self) -> TraitIndex {
+        let mut type_ids: Vec<_> = self
+            .entries
+            .values()
+            .flat_map(|entry| entry.type_names().map(|(type_id, _)| type_id))
+            .collect();
+        type_ids.sort_unstable();
+        type_ids.dedup();
+        TraitIndex::new(
+            self.entries
+                .iter()
+                .map(|(&target, entry)| (target, entry.implementors_among(self, &type_ids))),
+        )
+    }
+
+    /// Build a [`TraitIndex`] of this database's targets and retain it, so
+    /// that [`dyn_implements_all`][crate::DynImplements::dyn_implements_all]
+    /// and [`dyn_implements_any`][crate::DynImplements::dyn_implements_any]
+    /// can consult it rather than look up each target, until the database is
+    /// next mutated.
+    ///
+    /// The global [`DB`] is indexed when it is sealed.
+    pub fn index_traits(&mut self) {
+        nested_span!("index_traits");
+        self.indexed = Some(self.trait_index());
+    }
+
+    /// Cast `data`, a pointer to an instance of the concrete type with the
+    /// given `type_id`, to a type-erased pointer to the `target` type; or
+    /// `None` if that concrete type is not registered as an implementor of
+    /// `target`.
+    ///
+    /// See [`TypeDatabaseEntryExt::cast_raw`].
+    ///
+    /// # Safety
+    /// `data` must point to an instance of the concrete type with the given
+    /// `type_id`.
+    pub unsafe fn cast_raw(
+        &self,
+        target: TypeId,
+        type_id: TypeId,
+        data: *const (),
+    ) -> Option<ErasedFatPtr> {
+        self.entries.get(&target)?.cast_raw(self, type_id, data)
+    }
+
+    /// Iterate over the [`TypeId`]s of every target of which `value`'s ultimate
+    /// concrete type is a registered implementor.
+    pub fn traits_of<P>(
+        &self,
+        value: &P,
+    ) -> Result<impl Iterator<Item = TypeId>, TypeIdDeterminationError>
+    where
+        P: ?Sized + InnermostTypeId,
+    {
+        let targets = value.with_innermost_any(|data| {
+            self.candidate_entries(Any::type_id(data))
+                .filter(|(_, entry)| entry.implemented_by(self, data))
+                .map(|(target, _)| target)
+                .collect::<Vec<_>>()
+        })?;
+        Ok(targets.into_iter())
+    }
+
+    /// Call `visitor` with a view of `value`'s ultimate concrete instance as
+    /// each target of which its type is a registered implementor.
+    pub fn for_each_view<P, V>(
+        &self,
+        value: &P,
+        visitor: &mut V,
+    ) -> Result<(), TypeIdDeterminationError>
+    where
+        P: ?Sized + InnermostTypeId,
+        V: ?Sized + ViewVisitor,
+    {
+        value.with_innermost_any(|data| {
+            for (_, entry) in self.candidate_entries(Any::type_id(data)) {
+                if let Some(view) = entry.view(self, data) {
+                    // `view` points to `data`, which is borrowed for the
+                    // duration of this closure
+                    visitor.visit(unsafe { ErasedRef::new(view) });
+                }
+            }
+        })
+    }
+
+    /// The entries, with their targets, that may hold an implementation for the
+    /// concrete type with the given `type_id`: those in which it is registered,
+    /// and those whose targets have fallbacks.
+    fn candidate_entries(
+        &self,
+        type_id: TypeId,
+    ) -> impl Iterator<Item = (TypeId, &dyn ErasedEntry)> {
+        let registered = self
+            .registered_targets
+            .get_or_init(|| {
+                let mut targets = TypeIdMap::<Vec<_>>::default();
+                for (&target, entry) in &self.entries {
+                    for (type_id, _) in entry.type_names() {
+                        targets.entry(type_id).or_default().push(target);
+                    }
+                }
+                targets
+            })
+            .get(&type_id)
+            .map_or(&[][..], Vec::as_slice);
+        let fallbacks = self
+            .subtraits
+            .keys()
+            .filter(move |target| !registered.contains(target));
+        registered
+            .iter()
+            .chain(fallbacks)
+            .filter_map(move |target| Some((*target, &**self.entries.get(target)?)))
+    }
+
+    /// Whether `value`'s ultimate concrete type is a registered implementor of
+    /// the target with the given `name`.
+    pub fn implements_name<P>(
+        &self,
+        value: &P,
+        name: &str,
+    ) -> Result<bool, TypeIdDeterminationError>
+    where
+        P: ?Sized + InnermostTypeId,
+    {
+        match self.entry_named(name) {
+            Some(entry) => value.with_innermost_any(|data| entry.implemented_by(self, data)),
+            None => Ok(false),
+        }
+    }
+
+    /// View `value`'s ultimate concrete instance as the target with the given
+    /// `name`, if its type is a registered implementor of that target.
+    pub fn cast_by_name<'a, P>(&self, value: &'a P, name: &str) -> Option<ErasedRef<'a>>
+    where
+        P: ?Sized + InnermostAny,
+    {
+        let view = self.entry_named(name)?.view(self, value.innermost_any())?;
+        // `view` points to the concrete instance, which is borrowed for `'a`
+        Some(unsafe { ErasedRef::new(view) })
+    }
+
+    /// Iterate over the names of every target of which `value`'s ultimate
+    /// concrete type is a registered implementor.
+    #[cfg(feature = "names")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "names")))]
+    pub fn trait_names_of<P>(
+        &self,
+        value: &P,
+    ) -> Result<impl Iterator<Item = &'static str>, TypeIdDeterminationError>
+    where
+        P: ?Sized + InnermostTypeId,
+    {
+        let names = self
+            .traits_of(value)?
+            .map(|target| self.entries[&target].target_name())
+            .collect::<Vec<_>>();
+        Ok(names.into_iter())
+    }
+
+    /// Remove every registration, across all entries, that was made in the
+    /// given `namespace`.
+    pub fn remove_namespace(&mut self, namespace: &str) {
+        nested_span!("remove_namespace", ?namespace);
+        #[cfg(feature = "names")]
+        self.concrete_names.take();
+        self.indexed.take();
+        self.registered_targets.take();
+        for entry in self.entries.values_mut() {
+            entry.remove_namespace(namespace);
+        }
+    }
+
+    /// Iterate over every registration, across all entries, that was made in
+    /// the given `namespace`, yielding the [`TypeId`]s of the target and of the
+    /// concrete implementing type respectively.
+    pub fn iter_namespace<'a>(
+        &'a self,
+        namespace: &'a str,
+    ) -> impl Iterator<Item = (TypeId, TypeId)> + 'a {
+        self.entries.iter().flat_map(move |(&target, entry)| {
+            entry
+                .iter_namespace(namespace)
+                .map(move |type_id| (target, type_id))
+        })
+    }
+}
+
+impl<U> HashMapTypeDatabaseEntry<U>
+where
+    U: ?Sized,
+{
+    /// The approximate number of bytes allocated on the heap by this entry.
+    pub fn memory_footprint(&self) -> usize {
+        self.implementors.heap_footprint() + self.side.heap_footprint()
+    }
+
+    /// The metadata with which `implementor`, of the concrete type with the
+    /// given `type_id`, is registered.
+    #[cfg_attr(feature = "compact-metadata", allow(unused_variables))]
+    fn stored_metadata(&self, type_id: &TypeId, implementor: &Implementor<U>) -> Metadata<U> {
+        #[cfg(feature = "compact-metadata")]
+        // the index was interned on this entry's table
+        unsafe {
+            self.metadata_table.get(&implementor.metadata)
+        }
+        #[cfg(not(feature = "compact-metadata"))]
+        implementor.metadata.unwrap_or_else(|| {
+            // an implementor without metadata was added lazily
+            self.side.lazy[type_id].get()
+        })
+    }
+
+    /// Remove every registration that was made in the given `namespace`.
+    pub fn remove_namespace(&mut self, namespace: &str) {
+        nested_span!("remove_namespace", ?namespace);
+        #[cfg(feature = "names")]
+        self.registered_names.take();
+        let removed: Vec<_> = self.iter_namespace(namespace).collect();
+        for type_id in removed {
+            self.implementors.remove(&type_id);
+            self.side.remove(&type_id);
+        }
+    }
+
+    /// Iterate over every registration in this entry, for example in order to
+    /// install them in another with
+    /// [`register_iter`][TypeDatabaseEntryExt::register_iter].
+    pub fn registrations(&self) -> impl Iterator<Item = Registration<U>> + '_ {
+        self.implementors
+            .iter()
+            .map(move |(&type_id, implementor)| Registration {
+                type_id,
+                metadata: self.stored_metadata(&type_id, implementor),
+                #[cfg(feature = "names")]
+                type_name: implementor.type_name,
+            })
+    }
+
+    /// Iterate over the [`TypeId`]s of every concrete type whose registration
+    /// was made in the given `namespace`.
+    pub fn iter_namespace<'a>(&'a self, namespace: &'a str) -> impl Iterator<Item = TypeId> + 'a {
+        self.side
+            .namespaces
+            .iter()
+            .filter(move |&(_, &registered)| registered == namespace)
+            .map(|(&type_id, _)| type_id)
+    }
+}
+
+impl HashMapTypeDatabase {
+    /// A [`Debug`][fmt::Debug] rendering of this database that names each
+    /// target and its implementors, for example `{dyn Bar: {Quux, Qux}}`.
+    ///
+    /// Implementors that were registered without a name (as are all without
+    /// the `names` feature) are rendered by their [`TypeId`].
+    pub fn fmt_compact(&self) -> impl fmt::Debug + '_ {
+        debug_with(move |f| {
+            f.debug_map()
+                .entries(self.sorted_entries().map(|entry| {
+                    let implementors = debug_with(move |f| {
+                        let mut names: Vec<_> = entry.type_names().collect();
+                        names.sort_unstable_by_key(|&(_, name)| name);
+                        f.debug_set()
+                            .entries(names.into_iter().map(|(id, name)| name_or_id(id, name)))
+                            .finish()
+                    });
+                    (unquoted(entry.target_name()), implementors)
+                }))
+                .finish()
+        })
+    }
+
+    /// A [`Debug`][fmt::Debug] rendering of this database that details each
+    /// registration: its [`TypeId`], namespace and whether it is predicated
+    /// (and, with the `debug-registry` feature, where it was made).
+    pub fn fmt_verbose(&self) -> impl fmt::Debug + '_ {
+        debug_with(move |f| {
+            f.debug_map()
+                .entries(self.sorted_entries().map(|entry| {
+                    (
+                        unquoted(entry.target_name()),
+                        debug_with(move |f| entry.fmt_verbose(f)),
+                    )
+                }))
+                .finish()
+        })
+    }
+
+    /// Every entry, in order of their targets' names.
+    fn sorted_entries(&self) -> impl Iterator<Item = &StoredEntry> {
+        let mut entries: Vec<_> = self.entries.values().collect();
+        entries.sort_unstable_by_key(|entry| entry.target_name());
+        entries.into_iter()
+    }
+}
+
+impl fmt::Debug for HashMapTypeDatabase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "names")]
+        return f
+            .debug_tuple("HashMapTypeDatabase")
+            .field(&self.fmt_compact())
+            .finish();
+
+        #[cfg(not(feature = "names"))]
+        f.debug_tuple("HashMapTypeDatabase")
+            .field(&self.entries)
+            .finish()
+    }
+}
+
+/// A [`Debug`][fmt::Debug] implementation by the closure `fmt`.
+fn debug_with<F>(fmt: F) -> impl fmt::Debug
 where
-    U: ?Sized;
+    F: Fn(&mut fmt::Formatter<'_>) -> fmt::Result,
+{
+    struct DebugWith<F>(F);
+
+    impl<F> fmt::Debug for DebugWith<F>
+    where
+        F: Fn(&mut fmt::Formatter<'_>) -> fmt::Result,
+    {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            (self.0)(f)
+        }
+    }
+
+    DebugWith(fmt)
+}
+
+/// A [`Debug`][fmt::Debug] rendering of `name`, unquoted.
+fn unquoted(name: &'static str) -> impl fmt::Debug {
+    debug_with(move |f| f.write_str(name))
+}
+
+/// A [`Debug`][fmt::Debug] rendering of `name`, unquoted, or failing that of
+/// `type_id`.
+fn name_or_id(type_id: TypeId, name: Option<&'static str>) -> impl fmt::Debug {
+    debug_with(move |f| match name {
+        Some(name) => f.write_str(name),
+        None => fmt::Debug::fmt(&type_id, f),
+    })
+}
 
 impl<U> Default for HashMapTypeDatabaseEntry<U>
 where
     U: ?Sized,
 {
     fn default() -> Self {
-        Self(HashMap::default())
+        Self {
+            implementors: SmallMap::default(),
+            side: SideTables::default(),
+            observer: None,
+            overwrite_policy: OverwritePolicy::default(),
+            #[cfg(feature = "compact-metadata")]
+            metadata_table: Arc::default(),
+            #[cfg(feature = "names")]
+            registered_names: OnceLock::new(),
+        }
     }
 }
 
-impl<U> fmt::Debug for HashMapTypeDatabaseEntry<U> {
+impl<U> Extend<Registration<U>> for HashMapTypeDatabaseEntry<U>
+where
+    U: ?Sized,
+{
+    fn extend<T>(&mut self, registrations: T)
+    where
+        T: IntoIterator<Item = Registration<U>>,
+    {
+        self.register_iter(registrations);
+    }
+}
+
+impl<U> fmt::Debug for HashMapTypeDatabaseEntry<U>
+where
+    U: ?Sized,
+{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "HashMapTypeDatabaseEntry<{}> ", type_name::<U>())?;
-        f.debug_set().entries(self.0.keys()).finish()
+
+        #[cfg(feature = "debug-registry")]
+        return f
+            .debug_map()
+            .entries(
+                self.implementors
+                    .iter()
+                    .map(|(type_id, implementor)| (type_id, implementor.location)),
+            )
+            .finish();
+
+        #[cfg(not(feature = "debug-registry"))]
+        f.debug_set()
+            .entries(self.implementors.iter().map(|(type_id, _)| type_id))
+            .finish()
     }
 }
 
 /// Evaluates to a newly instantiated [`HashMapTypeDatabase`], initialized with
 /// the provided entries.
 #[macro_export]
-#[cfg_attr(doc, doc(cfg(feature = "std")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
 macro_rules! rtti {
-    ($( $trait:path: $( $ty:ty )+, )+) => {{
-        use $crate::db::{TypeDatabase, TypeDatabaseEntryExt};
-        let mut db = $crate::db::hash_map::HashMapTypeDatabase::default();
+    (@register $db:expr; $( $trait:path: $( $ty:ty )+, )+) => {{
+        use $crate::db::TypeDatabaseEntryExt;
         $(
-            let entry = db.get_entry_mut::<dyn $trait>();
-            $(entry.register::<$ty>();)+
+            $crate::db::hash_map::register_target::<dyn $trait, _>(&mut *$db, |entry| {
+                $(entry.register::<$ty>();)+
+            });
         )+
+    }};
+    ($( $token:tt )+) => {{
+        let mut db = $crate::db::hash_map::HashMapTypeDatabase::default();
+        $crate::rtti!(@register &mut db; $($token)+);
         db
     }};
 }
 
+/// Make registrations in the entry of `db` for target `U` with `f`, within a
+/// tracing span for that target: used by [`rtti`].
+#[doc(hidden)]
+pub fn register_target<U, F>(db: &mut HashMapTypeDatabase, f: F)
+where
+    U: 'static + ?Sized,
+    F: FnOnce(&mut HashMapTypeDatabaseEntry<U>),
+{
+    nested_span!("rtti", U = type_name::<U>());
+    f(db.get_entry_mut::<U>());
+}
+
 /// A global, immutable, thread-safe [`HashMapTypeDatabase`] that can be
 /// initialized with [`rtti_global`].
 #[cfg(feature = "global")]
-pub static DB: SyncOnceCell<HashMapTypeDatabase> = SyncOnceCell::new();
+pub static DB: GlobalCell<HashMapTypeDatabase> = GlobalCell::new();
+
+/// The cell in which the global [`DB`] is stored, which can be written only
+/// once.
+#[cfg(all(
+    feature = "global",
+    not(all(target_arch = "wasm32", not(target_feature = "atomics")))
+))]
+pub type GlobalCell<T> = OnceLock<T>;
+
+#[cfg(all(
+    feature = "global",
+    target_arch = "wasm32",
+    not(target_feature = "atomics")
+))]
+pub use super::single_threaded::GlobalCell;
+
+/// Registrations staged by [`defer_registration`], pending [`seal`] of the
+/// global [`DB`]; taken (leaving `None`) while the global [`DB`] is sealed.
+#[cfg(feature = "global")]
+static STAGED: LazyLock<Mutex<Option<HashMapTypeDatabase>>> =
+    LazyLock::new(|| Mutex::new(Some(Default::default())));
+
+/// Register concrete type `I` as an implementor of `U` in the global [`DB`],
+/// once it is initialized by [`seal`] or [`rtti_global`].
+///
+/// This can be called before the global [`DB`] exists (for example, during the
+/// initialization of a library), and so decouples registration from the order
+/// in which an application is initialized.  Fails if the global [`DB`] has
+/// already been initialized or is being sealed (for example, if called from
+/// within the closure passed to [`seal_with`]).
+#[cfg(feature = "global")]
+#[cfg_attr(feature = "debug-registry", track_caller)]
+pub fn defer_registration<U, I>() -> Result<(), DatabaseError<U>>
+where
+    U: 'static + ?Sized,
+    I: 'static + Unsize<U>,
+{
+    nested_span!(
+        "defer_registration",
+        U = type_name::<U>(),
+        I = type_name::<I>()
+    );
+    let mut staged = STAGED.lock().unwrap_or_else(PoisonError::into_inner);
+    match &mut *staged {
+        Some(staged) if !is_sealed() => {
+            staged.get_entry_mut::<U>().register::<I>();
+            Ok(())
+        }
+        _ => Err(DatabaseError::Sealed),
+    }
+}
+
+/// Initializes the global [`DB`] with the registrations deferred by
+/// [`defer_registration`], after which it can no longer be mutated and is read
+/// without locking.
+///
+/// # Panics
+/// Panics if the global [`DB`] has already been initialized.
+#[cfg(feature = "global")]
+pub fn seal() {
+    seal_with(|_| {});
+}
+
+/// Returns the staged registrations to [`STAGED`] if sealing unwinds.
+#[cfg(feature = "global")]
+struct Unseal(Option<HashMapTypeDatabase>);
+
+#[cfg(feature = "global")]
+impl Drop for Unseal {
+    fn drop(&mut self) {
+        if let Some(db) = self.0.take() {
+            *STAGED.lock().unwrap_or_else(PoisonError::into_inner) = Some(db);
+        }
+    }
+}
+
+/// Initializes the global [`DB`] with the registrations deferred by
+/// [`defer_registration`] together with any made by `f`, after which it can no
+/// longer be mutated and is read without locking.
+///
+/// Registrations cannot be deferred while `f` runs.  If `f` panics, the
+/// deferred registrations (together with any that `f` had made) remain staged
+/// for a later attempt to seal.
+///
+/// # Panics
+/// Panics if the global [`DB`] has already been initialized or is being sealed
+/// by another thread.
+#[cfg(feature = "global")]
+pub fn seal_with<F>(f: F)
+where
+    F: FnOnce(&mut HashMapTypeDatabase),
+{
+    nested_span!("seal_with");
+    let mut unseal = {
+        let mut staged = STAGED.lock().unwrap_or_else(PoisonError::into_inner);
+        if is_sealed() {
+            panic!("database already initialized");
+        }
+        Unseal(Some(staged.take().expect("database already being sealed")))
+    };
+    let db = unseal.0.as_mut().unwrap();
+    f(db);
+    db.index_traits();
+    diagnostic!(
+        info,
+        "sealed global database with {} targets",
+        db.entries.len()
+    );
+    let db = unseal.0.take().unwrap();
+    let _staged = STAGED.lock().unwrap_or_else(PoisonError::into_inner);
+    DB.set(db).expect("database already initialized");
+}
+
+/// Whether the global [`DB`] has been sealed (or otherwise initialized).
+#[cfg(feature = "global")]
+pub fn is_sealed() -> bool {
+    DB.get().is_some()
+}
 
-/// Instantiates the global [`DB`] with the provided entries.
+/// Instantiates the global [`DB`] with the provided entries, together with any
+/// registrations deferred by [`defer_registration`].
 #[macro_export]
 #[cfg(feature = "global")]
 macro_rules! rtti_global {
     ($( $token:tt )+) => {{
-        $crate::db::hash_map::DB
-            .set($crate::rtti!($($token)+))
-            .ok()
-            .expect("database already initialized");
+        $crate::db::hash_map::seal_with(|db| $crate::rtti!(@register db; $($token)+));
     }};
 }
 
@@ -72,50 +1371,270 @@ unsafe impl<U> TypeDatabaseEntry<U> for HashMapTypeDatabaseEntry<U>
 where
     U: ?Sized,
 {
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self, metadata)))]
     unsafe fn add(&mut self, type_id: TypeId, metadata: Metadata<U>) {
-        let _ = self.0.insert(type_id, metadata);
+        nested_span!("add", ?type_id);
+        #[cfg(feature = "names")]
+        self.registered_names.take();
+        #[cfg(feature = "compact-metadata")]
+        // the table is shared only with the other entries of this entry's
+        // database, to all of which `&mut self` implies exclusive access
+        let metadata = self.metadata_table.intern::<U>(metadata);
+        #[cfg(not(feature = "compact-metadata"))]
+        let metadata = Some(metadata);
+        let _ = self.implementors.insert(type_id, metadata.into());
+        self.side.remove(&type_id);
+    }
+
+    #[cfg(not(feature = "compact-metadata"))]
+    unsafe fn add_lazy(&mut self, type_id: TypeId, provider: fn() -> Metadata<U>) {
+        nested_span!("add_lazy", ?type_id);
+        #[cfg(feature = "names")]
+        self.registered_names.take();
+        let _ = self.implementors.insert(type_id, None.into());
+        self.side.remove(&type_id);
+        self.side.lazy.insert(type_id, LazyMetadata::new(provider));
+    }
+
+    fn set_predicate(&mut self, type_id: TypeId, predicate: Predicate) {
+        nested_span!("set_predicate", ?type_id);
+        if self.implementors.contains_key(&type_id) {
+            self.side.predicates.insert(type_id, predicate);
+        }
+    }
+
+    fn remove(&mut self, type_id: TypeId) -> bool {
+        nested_span!("remove", ?type_id);
+        #[cfg(feature = "names")]
+        self.registered_names.take();
+        self.side.remove(&type_id);
+        self.implementors.remove(&type_id).is_some()
+    }
+
+    fn set_detail(&mut self, type_id: TypeId, detail: Detail<U>) {
+        nested_span!("set_detail", ?type_id, kind = ?detail.kind());
+        if !self.implementors.contains_key(&type_id) {
+            return;
+        }
+        match detail {
+            Detail::Namespace(namespace) => {
+                self.side.namespaces.insert(type_id, namespace);
+            }
+            #[cfg(feature = "names")]
+            Detail::TypeName(type_name) => {
+                self.registered_names.take();
+                if let Some(implementor) = self.implementors.get_mut(&type_id) {
+                    implementor.type_name = Some(type_name);
+                }
+            }
+            #[cfg(feature = "debug-registry")]
+            Detail::Location(location) => {
+                if let Some(implementor) = self.implementors.get_mut(&type_id) {
+                    implementor.location = Some(location);
+                }
+            }
+            Detail::Cloner(cloner) => {
+                self.side.cloners.insert(type_id, cloner);
+            }
+            Detail::Constructor(constructor) => {
+                self.side.constructors.insert(type_id, constructor);
+            }
+            Detail::Equality(equality) => {
+                self.side.equalities.insert(type_id, equality);
+            }
+            Detail::Hashing(hashing) => {
+                self.side.hashings.insert(type_id, hashing);
+            }
+            #[cfg(feature = "serde")]
+            Detail::Tag(tag) => {
+                if let Some(previous) = self.side.tags.insert(type_id, tag) {
+                    self.side.forget_tag_name(previous.name, &type_id);
+                }
+                self.side.tag_names.insert(tag.name, type_id);
+            }
+        }
     }
 
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self)))]
     fn contains(&self, type_id: TypeId) -> bool {
-        self.0.contains_key(&type_id)
+        nested_span!("contains", ?type_id);
+        self.implementors.contains_key(&type_id)
+    }
+
+    fn predicate(&self, type_id: TypeId) -> Option<Predicate> {
+        nested_span!("predicate", ?type_id);
+        self.side.predicates.get(&type_id).copied()
+    }
+
+    fn detail(&self, type_id: TypeId, kind: DetailKind) -> Option<Detail<U>> {
+        nested_span!("detail", ?type_id, ?kind);
+        match kind {
+            DetailKind::Namespace => self
+                .side
+                .namespaces
+                .get(&type_id)
+                .copied()
+                .map(Detail::Namespace),
+            #[cfg(feature = "names")]
+            DetailKind::TypeName => self
+                .implementors
+                .get(&type_id)?
+                .type_name
+                .map(Detail::TypeName),
+            #[cfg(feature = "debug-registry")]
+            DetailKind::Location => self
+                .implementors
+                .get(&type_id)?
+                .location
+                .map(Detail::Location),
+            DetailKind::Cloner => self.side.cloners.get(&type_id).copied().map(Detail::Cloner),
+            DetailKind::Constructor => self
+                .side
+                .constructors
+                .get(&type_id)
+                .copied()
+                .map(Detail::Constructor),
+            DetailKind::Equality => self
+                .side
+                .equalities
+                .get(&type_id)
+                .copied()
+                .map(Detail::Equality),
+            DetailKind::Hashing => self
+                .side
+                .hashings
+                .get(&type_id)
+                .copied()
+                .map(Detail::Hashing),
+            #[cfg(feature = "serde")]
+            DetailKind::Tag => self.side.tags.get(&type_id).copied().map(Detail::Tag),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn tag_named(&self, name: &str) -> Option<Tag<U>> {
+        nested_span!("tag_named", ?name);
+        let type_id = self.side.tag_names.get(name)?;
+        self.side.tags.get(type_id).copied()
+    }
+
+    fn metadata(&self, type_id: TypeId) -> Option<Metadata<U>> {
+        nested_span!("metadata", ?type_id);
+        self.implementors
+            .get(&type_id)
+            .map(|implementor| self.stored_metadata(&type_id, implementor))
+    }
+
+    fn observer(&self) -> Option<&dyn Observer> {
+        self.observer.as_deref()
+    }
+
+    fn overwrite_policy(&self) -> OverwritePolicy {
+        self.overwrite_policy
     }
 
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self)))]
-    fn metadata(&self, type_id: TypeId) -> Option<&Metadata<U>> {
-        self.0.get(&type_id)
+    #[cfg(feature = "names")]
+    fn registered_names(&self) -> Arc<[&'static str]> {
+        self.registered_names
+            .get_or_init(|| {
+                let mut names: Vec<_> = self
+                    .implementors
+                    .iter()
+                    .filter_map(|(_, implementor)| implementor.type_name)
+                    .collect();
+                names.sort_unstable();
+                names.into()
+            })
+            .clone()
     }
 }
 
 unsafe impl TypeDatabase for HashMapTypeDatabase {
     type Entry<U: ?Sized> = HashMapTypeDatabaseEntry<U>;
 
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
-        U = type_name::<U>(),
-    )))]
-    fn get_entry_mut<U>(&mut self) -> &mut Self::Entry<U>
+    fn get_entry<U>(&self) -> Option<&Self::Entry<U>>
     where
         U: 'static + ?Sized,
     {
-        unsafe {
-            self.0
-                .entry(TypeId::of::<U>())
-                .or_insert_with(|| Box::new(Self::Entry::<U>::default()))
-                .downcast_mut()
-                .unwrap_unchecked()
-        }
+        nested_span!("get_entry", U = type_name::<U>());
+        self.entries
+            .get(&TypeId::of::<U>())
+            // entries are keyed by the `TypeId` of their target
+            .map(|entry| unsafe { entry.get() })
     }
 
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
-        U = type_name::<U>(),
-    )))]
-    fn get_entry<U>(&self) -> Option<&Self::Entry<U>>
+    fn observer(&self) -> Option<&dyn Observer> {
+        self.observer.as_deref()
+    }
+
+    #[cfg(feature = "names")]
+    fn concrete_type_name(&self, type_id: TypeId) -> Option<&'static str> {
+        self.concrete_names
+            .get_or_init(|| {
+                self.entries
+                    .values()
+                    .flat_map(|entry| entry.type_names())
+                    .filter_map(|(type_id, type_name)| Some((type_id, type_name?)))
+                    .collect()
+            })
+            .get(&type_id)
+            .copied()
+    }
+
+    fn cached_trait_index(&self) -> Option<&TraitIndex> {
+        self.indexed.as_ref()
+    }
+
+    fn fallback_metadata<U>(&self, type_id: TypeId) -> Option<(Metadata<U>, Option<Predicate>)>
     where
         U: 'static + ?Sized,
     {
-        self.0
-            .get(&TypeId::of::<U>())
-            .and_then(|t| t.downcast_ref())
+        nested_span!("fallback_metadata", ?type_id, U = type_name::<U>());
+        self.subtraits
+            .get(&TypeId::of::<U>())?
+            .iter()
+            // fallbacks are stored under the `TypeId` of their target
+            .find_map(|fallback| unsafe { fallback.typed::<U>() }(self, type_id))
+    }
+}
+
+unsafe impl TypeDatabaseMut for HashMapTypeDatabase {
+    fn get_entry_mut<U>(&mut self) -> &mut Self::Entry<U>
+    where
+        U: 'static + ?Sized,
+    {
+        nested_span!("get_entry_mut", U = type_name::<U>());
+        let Self {
+            entries,
+            arena,
+            observer,
+            overwrite_policy,
+            #[cfg(feature = "compact-metadata")]
+            metadata_table,
+            #[cfg(feature = "names")]
+            concrete_names,
+            indexed,
+            registered_targets,
+            ..
+        } = self;
+        #[cfg(feature = "names")]
+        concrete_names.take();
+        indexed.take();
+        registered_targets.take();
+        let entry = entries.entry(TypeId::of::<U>()).or_insert_with(|| {
+            StoredEntry::new(
+                arena,
+                Self::Entry::<U> {
+                    implementors: SmallMap::default(),
+                    side: SideTables::default(),
+                    observer: observer.clone(),
+                    overwrite_policy: *overwrite_policy,
+                    #[cfg(feature = "compact-metadata")]
+                    metadata_table: metadata_table.clone(),
+                    #[cfg(feature = "names")]
+                    registered_names: OnceLock::new(),
+                },
+            )
+        });
+        // entries are keyed by the `TypeId` of their target
+        unsafe { entry.get_mut() }
     }
 }
@@ -0,0 +1,52 @@
+//! Lists of implementors, which can be registered together.
+
+use super::TypeDatabaseEntryExt;
+use core::marker::Unsize;
+
+/// A list of concrete implementors of `U`, expressed as a tuple of those
+/// types: for example, `(i32, String, MyType)`.
+///
+/// Implemented for tuples of up to twelve implementors.
+pub trait Implementors<U>
+where
+    U: ?Sized,
+{
+    /// [`register`][TypeDatabaseEntryExt::register] every implementor in this
+    /// list in `entry`.
+    fn register_all<E>(entry: &mut E)
+    where
+        E: ?Sized + TypeDatabaseEntryExt<U>;
+}
+
+macro_rules! implementors {
+    ($( ($( $i:ident ),+) )+) => {$(
+        impl<U, $( $i, )+> Implementors<U> for ($( $i, )+)
+        where
+            U: 'static + ?Sized,
+            $( $i: 'static + Unsize<U>, )+
+        {
+            #[cfg_attr(feature = "debug-registry", track_caller)]
+            fn register_all<E>(entry: &mut E)
+            where
+                E: ?Sized + TypeDatabaseEntryExt<U>,
+            {
+                $( entry.register::<$i>(); )+
+            }
+        }
+    )+};
+}
+
+implementors! {
+    (I1)
+    (I1, I2)
+    (I1, I2, I3)
+    (I1, I2, I3, I4)
+    (I1, I2, I3, I4, I5)
+    (I1, I2, I3, I4, I5, I6)
+    (I1, I2, I3, I4, I5, I6, I7)
+    (I1, I2, I3, I4, I5, I6, I7, I8)
+    (I1, I2, I3, I4, I5, I6, I7, I8, I9)
+    (I1, I2, I3, I4, I5, I6, I7, I8, I9, I10)
+    (I1, I2, I3, I4, I5, I6, I7, I8, I9, I10, I11)
+    (I1, I2, I3, I4, I5, I6, I7, I8, I9, I10, I11, I12)
+}
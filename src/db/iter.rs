@@ -0,0 +1,266 @@
+//! Iterators that cast many pointers with a single entry lookup.
+//!
+//! Only the registrations in the looked up entry are consulted: unlike
+//! [`dyn_cast`][crate::DynCast::dyn_cast], these iterators do not fall back to
+//! anything else that the database knows (such as declared subtraits).
+
+use super::{
+    error::{CastError, DatabaseError},
+    TypeDatabaseEntry, TypeDatabaseEntryExt, TypeDatabaseExt,
+};
+use crate::container::{Coerced, Coercible, InnermostTypeId, Metadata, Pointer};
+use core::{any::type_name, fmt, marker::PhantomData, ptr};
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+
+/// An iterator that casts each pointer yielded by `I` to `U`, using an entry
+/// `E` that was resolved only once.
+pub struct CastIter<'a, U, E, I>
+where
+    U: ?Sized,
+    E: ?Sized,
+{
+    entry: Option<&'a E>,
+    pointers: I,
+    target: PhantomData<fn(&U)>,
+}
+
+impl<'a, U, E, I> CastIter<'a, U, E, I>
+where
+    U: ?Sized,
+    E: ?Sized,
+{
+    /// Cast each pointer yielded by `pointers` using `entry`, or fail to cast
+    /// them all if there is no such entry.
+    pub fn new(entry: Option<&'a E>, pointers: I) -> Self {
+        Self {
+            entry,
+            pointers,
+            target: PhantomData,
+        }
+    }
+}
+
+/// Cast `pointer` to `U` using `entry`, or fail if there is no such entry.
+#[cfg_attr(feature = "debug-casts", track_caller)]
+pub(super) fn cast_with<U, E, P>(
+    entry: Option<&E>,
+    pointer: P,
+) -> Result<P::Coerced<U>, CastError<U, P>>
+where
+    U: 'static + ?Sized,
+    E: TypeDatabaseEntry<U>,
+    P: Pointer + InnermostTypeId,
+    P::Coerced<U>: Sized,
+    P::Inner: Coercible,
+    Coerced<P::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+{
+    match entry {
+        Some(entry) => entry.cast(pointer),
+        None => Err(CastError::new(
+            DatabaseError::RequestedTypeNotInDatabase {
+                requested_type: PhantomData,
+            }
+            .into(),
+            pointer,
+        )),
+    }
+}
+
+impl<U, E, I> Iterator for CastIter<'_, U, E, I>
+where
+    U: 'static + ?Sized,
+    E: TypeDatabaseEntry<U>,
+    I: Iterator,
+    I::Item: Pointer + InnermostTypeId,
+    <I::Item as Coercible>::Coerced<U>: Sized,
+    <I::Item as Coercible>::Inner: Coercible,
+    Coerced<<I::Item as Coercible>::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+{
+    type Item = Result<<I::Item as Coercible>::Coerced<U>, CastError<U, I::Item>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(cast_with(self.entry, self.pointers.next()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.pointers.size_hint()
+    }
+}
+
+impl<U, E, I> fmt::Debug for CastIter<'_, U, E, I>
+where
+    U: ?Sized,
+    E: ?Sized + fmt::Debug,
+    I: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CastIter<{}>", type_name::<U>())?;
+        f.debug_struct("")
+            .field("entry", &self.entry)
+            .field("pointers", &self.pointers)
+            .finish()
+    }
+}
+
+/// An iterator that yields only those items of `I` that are registered as
+/// implementors of `U` in an entry `E` that was resolved only once.
+pub struct FilterImplements<'a, U, E, I>
+where
+    U: ?Sized,
+    E: ?Sized,
+{
+    entry: Option<&'a E>,
+    items: I,
+    target: PhantomData<fn(&U)>,
+}
+
+impl<U, E, I> Iterator for FilterImplements<'_, U, E, I>
+where
+    U: 'static + ?Sized,
+    E: TypeDatabaseEntry<U>,
+    I: Iterator,
+    I::Item: InnermostTypeId,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entry?;
+        self.items
+            .find(|item| matches!(entry.implements(item), Ok(true)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.entry {
+            Some(_) => (0, self.items.size_hint().1),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+impl<U, E, I> fmt::Debug for FilterImplements<'_, U, E, I>
+where
+    U: ?Sized,
+    E: ?Sized + fmt::Debug,
+    I: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FilterImplements<{}>", type_name::<U>())?;
+        f.debug_struct("")
+            .field("entry", &self.entry)
+            .field("items", &self.items)
+            .finish()
+    }
+}
+
+/// An iterator that casts each pointer yielded by `I` to `U`, using an entry
+/// `E` that was resolved only once, and yields only those casts that succeed.
+pub struct CastFilterMap<'a, U, E, I>(CastIter<'a, U, E, I>)
+where
+    U: ?Sized,
+    E: ?Sized;
+
+impl<U, E, I> Iterator for CastFilterMap<'_, U, E, I>
+where
+    U: 'static + ?Sized,
+    E: TypeDatabaseEntry<U>,
+    I: Iterator,
+    I::Item: Pointer + InnermostTypeId,
+    <I::Item as Coercible>::Coerced<U>: Sized,
+    <I::Item as Coercible>::Inner: Coercible,
+    Coerced<<I::Item as Coercible>::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+{
+    type Item = <I::Item as Coercible>::Coerced<U>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.find_map(Result::ok)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.0.size_hint().1)
+    }
+}
+
+impl<U, E, I> fmt::Debug for CastFilterMap<'_, U, E, I>
+where
+    U: ?Sized,
+    E: ?Sized + fmt::Debug,
+    I: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CastFilterMap<{}>", type_name::<U>())?;
+        f.debug_struct("")
+            .field("entry", &self.0.entry)
+            .field("pointers", &self.0.pointers)
+            .finish()
+    }
+}
+
+/// Adapters that scan an iterator of heterogeneous items for implementors of
+/// some target, looking up that target in the database only once.
+pub trait DynIterator<DB>
+where
+    Self: Iterator + Sized,
+    DB: TypeDatabaseExt,
+{
+    /// Yield only those items that are registered as implementors of `U` in
+    /// `db`'s entry for `U` (without database fallbacks).
+    fn filter_implements<U>(self, db: &DB) -> FilterImplements<'_, U, DB::Entry<U>, Self>
+    where
+        U: 'static + ?Sized,
+        Self::Item: InnermostTypeId,
+    {
+        FilterImplements {
+            entry: db.get_entry(),
+            items: self,
+            target: PhantomData,
+        }
+    }
+
+    /// Cast each item to `U`, yielding only those that are registered as
+    /// implementors of `U` in `db`'s entry for `U` (without database
+    /// fallbacks).
+    fn cast_filter_map<U>(self, db: &DB) -> CastFilterMap<'_, U, DB::Entry<U>, Self>
+    where
+        U: 'static + ?Sized,
+        Self::Item: Pointer + InnermostTypeId,
+    {
+        CastFilterMap(CastIter::new(db.get_entry(), self))
+    }
+
+    /// Split the items into those that are registered as implementors of `U`
+    /// in `db`'s entry for `U` (without database fallbacks), cast to `U`, and
+    /// the rest.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    #[allow(clippy::type_complexity)]
+    fn dyn_partition<U>(
+        self,
+        db: &DB,
+    ) -> (Vec<<Self::Item as Coercible>::Coerced<U>>, Vec<Self::Item>)
+    where
+        U: 'static + ?Sized,
+        Self::Item: Pointer + InnermostTypeId,
+        <Self::Item as Coercible>::Coerced<U>: Sized,
+        <Self::Item as Coercible>::Inner: Coercible,
+        Coerced<<Self::Item as Coercible>::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        let mut implementors = Vec::new();
+        let mut rest = Vec::new();
+        for result in CastIter::new(db.get_entry(), self) {
+            match result {
+                Ok(implementor) => implementors.push(implementor),
+                Err(error) => rest.push(error.pointer),
+            }
+        }
+        (implementors, rest)
+    }
+}
+
+impl<DB, I> DynIterator<DB> for I
+where
+    I: Iterator,
+    DB: TypeDatabaseExt,
+{
+}
@@ -1,6 +1,14 @@
 //! A database for runtime type information.
 
 pub mod error;
+pub mod implementors;
+pub mod iter;
+pub mod observer;
+pub mod raw;
+pub mod std_fmt;
+pub mod targets;
+pub mod token;
+pub mod view;
 
 #[cfg(all(test, feature = "std"))]
 mod tests;
@@ -8,16 +16,320 @@ mod tests;
 #[cfg(feature = "std")]
 pub mod hash_map;
 
-use crate::container::{Coerced, Coercible, InnermostTypeId, Metadata, Pointer};
+#[cfg(feature = "std")]
+pub mod vtable;
+
+#[cfg(feature = "std")]
+mod arena;
+
+#[cfg(feature = "std")]
+mod small_map;
+
+#[cfg(feature = "compact-metadata")]
+mod compact;
+
+#[cfg(all(
+    feature = "global",
+    target_arch = "wasm32",
+    not(target_feature = "atomics")
+))]
+mod single_threaded;
+
+#[cfg(feature = "std")]
+pub mod trait_index;
+
+#[cfg(feature = "erased-serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "erased-serde")))]
+pub mod serialize;
+
+#[cfg(feature = "futures")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures")))]
+pub mod stream;
+
+#[cfg(feature = "rayon")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+pub mod parallel;
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod tagged;
+
+use crate::container::{
+    metadata_of, Coerced, Coercible, InnermostTypeId, Metadata, Pointer, TypeIdDeterminationError,
+};
 use core::{
-    any::TypeId,
+    any::{type_name, Any, TypeId},
+    fmt,
+    hash::{Hash, Hasher},
     marker::{PhantomData, Unsize},
     ptr,
 };
-use error::{CastError, DatabaseEntryError, DatabaseError};
+use error::{
+    Candidates, CastError, DatabaseEntryError, DatabaseError, ErrorKind, PreviousRegistration,
+    RegistrationError,
+};
+use implementors::Implementors;
+use iter::CastIter;
+use observer::Observer;
+use raw::ErasedFatPtr;
+use token::{Registration, RegistrationGuard, RegistrationToken};
+use view::DatabaseView;
+
+#[cfg(feature = "erased-serde")]
+use serialize::{Serializable, SerializableSeq};
+#[cfg(feature = "serde")]
+use tagged::Tag;
+#[cfg(feature = "std")]
+use trait_index::TraitIndex;
+
+#[cfg(all(feature = "alloc", not(feature = "std"), target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::{boxed::Box, rc::Rc};
 
-#[cfg(feature = "tracing")]
-use core::any::type_name;
+#[cfg(feature = "debug-registry")]
+use core::panic::Location;
+
+#[cfg(feature = "std")]
+use std::{boxed::Box, rc::Rc, sync::Arc};
+
+/// A predicate that instances of a registered concrete type must additionally
+/// satisfy in order to be deemed implementors.
+pub type Predicate = fn(&dyn Any) -> bool;
+
+/// How to handle the registration of a concrete type as an implementor of a
+/// target for which it is already registered.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OverwritePolicy {
+    /// The new registration replaces the existing one.
+    #[default]
+    LastWins,
+    /// The new registration is discarded.
+    FirstWins,
+    /// The new registration panics.
+    Panic,
+    /// The new registration is discarded, and the resulting
+    /// [`RegistrationError`] is emitted as a warning diagnostic and notified to
+    /// the [`Observer`], if any.
+    ///
+    /// As [`register`][TypeDatabaseEntryExt::register] does not return the
+    /// error, callers that must handle it should instead use
+    /// [`try_register`][TypeDatabaseEntryExt::try_register].
+    Error,
+}
+
+/// A function that clones the instance of a registered concrete type to which
+/// its argument points into a new [`Box<U>`].
+///
+/// # Safety
+/// The argument must point to an instance of the concrete type for which the
+/// function was registered.
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub type Cloner<U> = unsafe fn(*const ()) -> Box<U>;
+
+/// A function that constructs a new instance of a registered concrete type
+/// into a [`Box<U>`].
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub type Constructor<U> = fn() -> Box<U>;
+
+/// A function that compares the instances of a registered concrete type to
+/// which its arguments point for equality.
+///
+/// # Safety
+/// Both arguments must point to instances of the concrete type for which the
+/// function was registered.
+pub type Equality = unsafe fn(*const (), *const ()) -> bool;
+
+/// A function that feeds the instance of a registered concrete type to which
+/// its first argument points into the given [`Hasher`].
+///
+/// # Safety
+/// The first argument must point to an instance of the concrete type for which
+/// the function was registered.
+pub type Hashing = unsafe fn(*const (), &mut dyn Hasher);
+
+/// A detail, beyond its metadata, of the registration of a concrete type as an
+/// implementor of `U`.
+#[non_exhaustive]
+pub enum Detail<U>
+where
+    U: ?Sized,
+{
+    /// The namespace in which the registration was made.
+    Namespace(&'static str),
+    /// The name of the concrete type.
+    #[cfg(feature = "names")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "names")))]
+    TypeName(&'static str),
+    /// The call site at which the registration was made.
+    #[cfg(feature = "debug-registry")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "debug-registry")))]
+    Location(&'static Location<'static>),
+    /// The function with which instances of the concrete type are cloned.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    Cloner(Cloner<U>),
+    /// The function with which new instances of the concrete type are created.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    Constructor(Constructor<U>),
+    /// The function with which instances of the concrete type are compared.
+    Equality(Equality),
+    /// The function with which instances of the concrete type are hashed.
+    Hashing(Hashing),
+    /// The stable name and serialization functions of the concrete type.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    Tag(Tag<U>),
+    /// Uninhabited: mentions `U` when no other variant does.
+    #[doc(hidden)]
+    #[cfg(not(feature = "alloc"))]
+    Target(core::convert::Infallible, PhantomData<fn() -> *const U>),
+}
+
+impl<U> Detail<U>
+where
+    U: ?Sized,
+{
+    /// The kind of this detail.
+    pub fn kind(&self) -> DetailKind {
+        match self {
+            Self::Namespace(_) => DetailKind::Namespace,
+            #[cfg(feature = "names")]
+            Self::TypeName(_) => DetailKind::TypeName,
+            #[cfg(feature = "debug-registry")]
+            Self::Location(_) => DetailKind::Location,
+            #[cfg(feature = "alloc")]
+            Self::Cloner(_) => DetailKind::Cloner,
+            #[cfg(feature = "alloc")]
+            Self::Constructor(_) => DetailKind::Constructor,
+            Self::Equality(_) => DetailKind::Equality,
+            Self::Hashing(_) => DetailKind::Hashing,
+            #[cfg(feature = "serde")]
+            Self::Tag(_) => DetailKind::Tag,
+            #[cfg(not(feature = "alloc"))]
+            Self::Target(never, _) => match *never {},
+        }
+    }
+}
+
+impl<U> Clone for Detail<U>
+where
+    U: ?Sized,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<U> Copy for Detail<U> where U: ?Sized {}
+
+/// The kind of a [`Detail`], by which it is keyed in a [`TypeDatabaseEntry`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum DetailKind {
+    /// A [`Detail::Namespace`].
+    Namespace,
+    /// A [`Detail::TypeName`].
+    #[cfg(feature = "names")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "names")))]
+    TypeName,
+    /// A [`Detail::Location`].
+    #[cfg(feature = "debug-registry")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "debug-registry")))]
+    Location,
+    /// A [`Detail::Cloner`].
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    Cloner,
+    /// A [`Detail::Constructor`].
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    Constructor,
+    /// A [`Detail::Equality`].
+    Equality,
+    /// A [`Detail::Hashing`].
+    Hashing,
+    /// A [`Detail::Tag`].
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    Tag,
+}
+
+impl DetailKind {
+    /// Every kind of detail.
+    pub const ALL: &'static [Self] = &[
+        Self::Namespace,
+        #[cfg(feature = "names")]
+        Self::TypeName,
+        #[cfg(feature = "debug-registry")]
+        Self::Location,
+        #[cfg(feature = "alloc")]
+        Self::Cloner,
+        #[cfg(feature = "alloc")]
+        Self::Constructor,
+        Self::Equality,
+        Self::Hashing,
+        #[cfg(feature = "serde")]
+        Self::Tag,
+    ];
+}
+
+/// An [`Equality`] for concrete type `I`.
+unsafe fn eq_erased<I>(a: *const (), b: *const ()) -> bool
+where
+    I: PartialEq,
+{
+    *a.cast::<I>() == *b.cast::<I>()
+}
+
+/// A [`Hashing`] for concrete type `I`.
+unsafe fn hash_erased<I>(data: *const (), mut state: &mut dyn Hasher)
+where
+    I: Hash,
+{
+    (*data.cast::<I>()).hash(&mut state);
+}
+
+/// The address of the concrete value to which `data` refers.
+fn address_of(data: &dyn Any) -> *const () {
+    (data as *const dyn Any).cast()
+}
+
+/// A [`Constructor`] of concrete type `I`'s default value.
+#[cfg(feature = "alloc")]
+fn construct_default<U, I>() -> Box<U>
+where
+    U: ?Sized,
+    I: Default + Unsize<U>,
+{
+    let value: Box<I> = Box::default();
+    value
+}
+
+/// A [`Cloner`] for concrete type `I`.
+#[cfg(feature = "alloc")]
+unsafe fn clone_boxed<U, I>(data: *const ()) -> Box<U>
+where
+    U: ?Sized,
+    I: Clone + Unsize<U>,
+{
+    let clone: Box<I> = Box::new((*data.cast::<I>()).clone());
+    clone
+}
+
+/// The [`TypeId`] of `value`'s concrete type, together with the
+/// [`Metadata<U>`] of `value` itself: suitable for
+/// [`register_metadata`][TypeDatabaseExt::register_metadata] in another
+/// database.
+pub fn capture_metadata<U>(value: &U) -> Result<(TypeId, Metadata<U>), TypeIdDeterminationError>
+where
+    U: ?Sized + InnermostTypeId,
+{
+    Ok((value.innermost_type_id()?, ptr::metadata(value)))
+}
 
 /// A key-value store, where the key is the [`TypeId`] of a concrete Rust type
 /// and the value is that type's [`Metadata<U>`].
@@ -25,10 +337,18 @@ use core::any::type_name;
 /// `U` will typically be `dyn Trait` such that the value stored for a given
 /// type's `TypeId` is its vtable for `Trait`.
 ///
+/// Alongside its metadata, each registration may carry a
+/// [predicate][TypeDatabaseEntry::set_predicate] and any number of
+/// [details][TypeDatabaseEntry::set_detail].  These have no effect unless
+/// metadata has already been added for the `type_id` in question, and any
+/// subsequent [`add`][TypeDatabaseEntry::add] or
+/// [`remove`][TypeDatabaseEntry::remove] for the same `type_id` discards them.
+///
 /// # Safety
-/// [`metadata`][TypeDatabaseEntry::metadata] must only ever return `Some(&m)`
+/// [`metadata`][TypeDatabaseEntry::metadata] must only ever return `Some(m)`
 /// if `m` was previously [`add`][TypeDatabaseEntry::add]ed for the given
-/// `type_id`.
+/// `type_id`, or was returned by a provider that was previously
+/// [`add_lazy`][TypeDatabaseEntry::add_lazy]ed for the given `type_id`.
 pub unsafe trait TypeDatabaseEntry<U>
 where
     U: ?Sized,
@@ -40,64 +360,621 @@ where
     /// represented by `type_id`.
     unsafe fn add(&mut self, type_id: TypeId, metadata: Metadata<U>);
 
+    /// Add a `provider` of the metadata for the given `type_id`, to be
+    /// evaluated no earlier than the first lookup of that metadata.
+    ///
+    /// The default implementation evaluates `provider` immediately and
+    /// [`add`][TypeDatabaseEntry::add]s the result.
+    ///
+    /// # Safety
+    /// `provider` must return the correct [`Metadata<U>`] for the concrete type
+    /// represented by `type_id`.
+    unsafe fn add_lazy(&mut self, type_id: TypeId, provider: fn() -> Metadata<U>) {
+        self.add(type_id, provider());
+    }
+
+    /// Remove any metadata for the given `type_id`, returning whether there was
+    /// any.
+    ///
+    /// The default implementation removes nothing, so registrations in stores
+    /// that do not override it cannot be revoked.
+    fn remove(&mut self, type_id: TypeId) -> bool {
+        let _ = type_id;
+        false
+    }
+
+    /// Restrict the instances of the concrete type represented by `type_id`
+    /// that are deemed to implement `U` to those that satisfy `predicate`.
+    fn set_predicate(&mut self, type_id: TypeId, predicate: Predicate);
+
+    /// Record `detail` of the registration of the concrete type represented
+    /// by `type_id`, replacing any previous detail of the same
+    /// [`kind`][Detail::kind].
+    ///
+    /// The default implementation does nothing.
+    fn set_detail(&mut self, type_id: TypeId, detail: Detail<U>) {
+        let _ = (type_id, detail);
+    }
+
     /// Whether this store contains metadata for `type_id`.
     fn contains(&self, type_id: TypeId) -> bool;
 
-    /// A reference to the metadata, if any, previously
-    /// [`add`][TypeDatabaseEntry::add]ed for the given `type_id`.
-    fn metadata(&self, type_id: TypeId) -> Option<&Metadata<U>>;
+    /// The predicate, if any, previously
+    /// [`set_predicate`][TypeDatabaseEntry::set_predicate] for the given
+    /// `type_id`.
+    fn predicate(&self, type_id: TypeId) -> Option<Predicate>;
+
+    /// The detail of the given `kind`, if any, previously
+    /// [`set_detail`][TypeDatabaseEntry::set_detail] for the given `type_id`.
+    ///
+    /// The default implementation always returns `None`.
+    fn detail(&self, type_id: TypeId, kind: DetailKind) -> Option<Detail<U>> {
+        let _ = (type_id, kind);
+        None
+    }
+
+    /// The [`Detail::Tag`], if any, previously
+    /// [`set_detail`][TypeDatabaseEntry::set_detail] with the given `name`.
+    ///
+    /// The default implementation always returns `None`.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    fn tag_named(&self, name: &str) -> Option<Tag<U>> {
+        let _ = name;
+        None
+    }
+
+    /// The [`Observer`], if any, to be notified of activity on this store.
+    fn observer(&self) -> Option<&dyn Observer> {
+        None
+    }
+
+    /// How to handle the [`register`][TypeDatabaseEntryExt::register]ing of a
+    /// concrete type that is already registered in this store.
+    fn overwrite_policy(&self) -> OverwritePolicy {
+        OverwritePolicy::LastWins
+    }
+
+    /// The sorted names, as recorded by [`Detail::TypeName`], of every concrete
+    /// type in this store.
+    ///
+    /// This is consulted upon every failed lookup, so implementations should
+    /// cache it rather than collect it afresh.
+    #[cfg(feature = "names")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "names")))]
+    fn registered_names(&self) -> Arc<[&'static str]> {
+        Arc::default()
+    }
+
+    /// The metadata, if any, previously [`add`][TypeDatabaseEntry::add]ed (or
+    /// [`add_lazy`][TypeDatabaseEntry::add_lazy]ed) for the given `type_id`.
+    ///
+    /// Metadata is returned by value so that implementations are free to
+    /// compute or decompress it on demand, rather than storing it materialized.
+    fn metadata(&self, type_id: TypeId) -> Option<Metadata<U>>;
 }
 
 /// The consumer interface for a [`TypeDatabaseEntry<U>`].
+///
+/// Lookups through this interface consult only the entry's own registrations,
+/// and not any that its database knows through
+/// [`fallback_metadata`][TypeDatabase::fallback_metadata] (such as those of
+/// declared subtraits): for those, cast through the database instead, for
+/// example with [`dyn_cast`][crate::DynCast::dyn_cast].
 pub trait TypeDatabaseEntryExt<U>
 where
     Self: TypeDatabaseEntry<U>,
     U: ?Sized,
 {
     /// Register concrete type `I` as an implementor of `U`.
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
-        U = type_name::<U>(),
-        I = type_name::<I>(),
-    )))]
+    ///
+    /// If `I` is already so registered, the store's
+    /// [`overwrite_policy`][TypeDatabaseEntry::overwrite_policy] applies.
+    #[cfg_attr(feature = "debug-registry", track_caller)]
     fn register<I>(&mut self)
     where
         I: 'static + Unsize<U>,
     {
+        nested_span!("register", U = type_name::<U>(), I = type_name::<I>());
+        if overwrite::<U, Self, I>(self) {
+            install::<U, Self, I>(self);
+        }
+    }
+
+    /// Register concrete type `I` as an implementor of `U`, unless it is already
+    /// so registered (irrespective of the store's
+    /// [`overwrite_policy`][TypeDatabaseEntry::overwrite_policy]).
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn try_register<I>(&mut self) -> Result<(), RegistrationError<U>>
+    where
+        I: 'static + Unsize<U>,
+    {
+        nested_span!("try_register", U = type_name::<U>(), I = type_name::<I>());
+        match existing_registration::<U, Self, I>(self) {
+            Some(error) => Err(error),
+            None => {
+                self.register::<I>();
+                Ok(())
+            }
+        }
+    }
+
+    /// Register concrete type `I` as an implementor of `U`, but only for those
+    /// instances that satisfy `predicate`.
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_if<I>(&mut self, predicate: Predicate)
+    where
+        I: 'static + Unsize<U>,
+    {
+        nested_span!("register_if", U = type_name::<U>(), I = type_name::<I>());
+        if !overwrite::<U, Self, I>(self) {
+            return;
+        }
+        self.register::<I>();
+        self.set_predicate(TypeId::of::<I>(), predicate);
+    }
+
+    /// Register concrete type `I` as an implementor of `U` (unless it is already
+    /// so registered), instances of which can be cloned into a [`Box<U>`] with
+    /// [`dyn_clone`][TypeDatabaseExt::dyn_clone].
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_clone<I>(&mut self)
+    where
+        I: 'static + Clone + Unsize<U>,
+    {
+        nested_span!("register_clone", U = type_name::<U>(), I = type_name::<I>());
+        if !self.contains(TypeId::of::<I>()) {
+            self.register::<I>();
+        }
+        self.set_detail(TypeId::of::<I>(), Detail::Cloner(clone_boxed::<U, I>));
+    }
+
+    /// Register concrete type `I` as an implementor of `U` (unless it is already
+    /// so registered), new instances of which can be created with
+    /// [`create`][TypeDatabaseExt::create] by calling `constructor`.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_constructor<I>(&mut self, constructor: Constructor<U>)
+    where
+        I: 'static + Unsize<U>,
+    {
+        nested_span!(
+            "register_constructor",
+            U = type_name::<U>(),
+            I = type_name::<I>()
+        );
+        if !self.contains(TypeId::of::<I>()) {
+            self.register::<I>();
+        }
+        self.set_detail(TypeId::of::<I>(), Detail::Constructor(constructor));
+    }
+
+    /// Register concrete type `I` as an implementor of `U` (unless it is already
+    /// so registered), whose [`Default`] value can be created with
+    /// [`create`][TypeDatabaseExt::create].
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_default<I>(&mut self)
+    where
+        I: 'static + Default + Unsize<U>,
+    {
+        nested_span!(
+            "register_default",
+            U = type_name::<U>(),
+            I = type_name::<I>()
+        );
+        self.register_constructor::<I>(construct_default::<U, I>);
+    }
+
+    /// Register concrete type `I` as an implementor of `U` (unless it is already
+    /// so registered), instances of which can be compared with
+    /// [`dyn_eq`][TypeDatabaseExt::dyn_eq].
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_eq<I>(&mut self)
+    where
+        I: 'static + PartialEq + Unsize<U>,
+    {
+        nested_span!("register_eq", U = type_name::<U>(), I = type_name::<I>());
+        if !self.contains(TypeId::of::<I>()) {
+            self.register::<I>();
+        }
+        self.set_detail(TypeId::of::<I>(), Detail::Equality(eq_erased::<I>));
+    }
+
+    /// Register concrete type `I` as an implementor of `U` (unless it is already
+    /// so registered), instances of which can be hashed with
+    /// [`dyn_hash`][TypeDatabaseExt::dyn_hash].
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_hash<I>(&mut self)
+    where
+        I: 'static + Hash + Unsize<U>,
+    {
+        nested_span!("register_hash", U = type_name::<U>(), I = type_name::<I>());
+        if !self.contains(TypeId::of::<I>()) {
+            self.register::<I>();
+        }
+        self.set_detail(TypeId::of::<I>(), Detail::Hashing(hash_erased::<I>));
+    }
+
+    /// Register concrete type `I` as an implementor of `U` (unless it is already
+    /// so registered), instances of which can be serialized with
+    /// [`serialize_dyn`][TypeDatabaseExt::serialize_dyn] and deserialized with
+    /// [`deserialize_dyn`][TypeDatabaseExt::deserialize_dyn] under the given
+    /// stable `name`.
+    ///
+    /// Fails, without registering `I`, if another concrete type is already
+    /// tagged with `name` in this store.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_tagged<I>(&mut self, name: &'static str) -> Result<(), RegistrationError<U>>
+    where
+        I: 'static + serde::Serialize + serde::de::DeserializeOwned + Unsize<U>,
+    {
+        nested_span!(
+            "register_tagged",
+            U = type_name::<U>(),
+            I = type_name::<I>()
+        );
+        let type_id = TypeId::of::<I>();
+        let retagged = self.tag(type_id).is_some_and(|tag| tag.name == name);
+        if !retagged && self.tag_named(name).is_some() {
+            return Err(RegistrationError::DuplicateTag {
+                type_id,
+                name,
+                target: PhantomData,
+            });
+        }
+        if !self.contains(type_id) {
+            self.register::<I>();
+        }
+        self.set_detail(type_id, Detail::Tag(Tag::of::<I>(name)));
+        Ok(())
+    }
+
+    /// Register concrete type `I` as an implementor of `U` in the given
+    /// `namespace`.
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_in<I>(&mut self, namespace: &'static str)
+    where
+        I: 'static + Unsize<U>,
+    {
+        nested_span!("register_in", U = type_name::<U>(), I = type_name::<I>());
+        if !overwrite::<U, Self, I>(self) {
+            return;
+        }
+        self.register::<I>();
+        self.set_detail(TypeId::of::<I>(), Detail::Namespace(namespace));
+    }
+
+    /// Register every concrete type in the tuple `T` as an implementor of `U`:
+    /// for example, `entry.register_many::<(i32, String)>()`.
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_many<T>(&mut self)
+    where
+        T: Implementors<U>,
+    {
+        nested_span!("register_many", U = type_name::<U>(), T = type_name::<T>());
+        T::register_all(self);
+    }
+
+    /// Register concrete type `I` as an implementor of `U`, deferring
+    /// computation of its metadata until first lookup.
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_lazy<I>(&mut self)
+    where
+        I: 'static + Unsize<U>,
+    {
+        nested_span!("register_lazy", U = type_name::<U>(), I = type_name::<I>());
+        if !overwrite::<U, Self, I>(self) {
+            return;
+        }
         unsafe {
             let type_id = TypeId::of::<I>();
-            let metadata = ptr::metadata::<U>(ptr::null::<I>());
-            self.add(type_id, metadata);
+            self.add_lazy(type_id, metadata_of::<U, I>);
+            record_registration(self, type_id, Some(type_name::<I>()));
+        }
+    }
+
+    /// Register `U` itself, which must be a concrete type, so that pointers to
+    /// its instances can be cast (i.e. downcast) to pointers to `U`.
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_concrete(&mut self)
+    where
+        U: 'static + Sized,
+    {
+        nested_span!("register_concrete", U = type_name::<U>());
+        unsafe {
+            let type_id = TypeId::of::<U>();
+            // the metadata of a sized type is `()`
+            self.add(type_id, ());
+            record_registration(self, type_id, Some(type_name::<U>()));
+        }
+    }
+
+    /// Register the concrete type of `example`, which was obtained by ordinary
+    /// unsizing, as an implementor of `U`: this is useful where that type
+    /// cannot be named, returning its [`TypeId`].
+    ///
+    /// Like [`register_metadata`][TypeDatabaseExt::register_metadata], the
+    /// registration is made irrespective of the entry's
+    /// [`overwrite_policy`][TypeDatabaseEntry::overwrite_policy] and records
+    /// no type name.
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_example(&mut self, example: &U) -> Result<TypeId, TypeIdDeterminationError>
+    where
+        U: InnermostTypeId,
+    {
+        nested_span!("register_example", U = type_name::<U>());
+        let (type_id, metadata) = capture_metadata(example)?;
+        // the metadata of a reference to a value of the concrete type is
+        // necessarily correct for that type
+        unsafe { self.add(type_id, metadata) };
+        record_registration(self, type_id, None);
+        Ok(type_id)
+    }
+
+    /// Register concrete type `I` as an implementor of `U`, returning a token
+    /// with which the registration can later be revoked.
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_tracked<I>(&mut self) -> RegistrationToken<U>
+    where
+        I: 'static + Unsize<U>,
+    {
+        nested_span!(
+            "register_tracked",
+            U = type_name::<U>(),
+            I = type_name::<I>()
+        );
+        let installed = overwrite::<U, Self, I>(self);
+        let token = RegistrationToken::new(self, TypeId::of::<I>(), installed);
+        if installed {
+            install::<U, Self, I>(self);
+        }
+        token
+    }
+
+    /// Install each of the given `registrations`, for example as obtained from
+    /// another database, irrespective of this store's
+    /// [`overwrite_policy`][TypeDatabaseEntry::overwrite_policy].
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_iter<T>(&mut self, registrations: T)
+    where
+        T: IntoIterator<Item = Registration<U>>,
+    {
+        nested_span!("register_iter", U = type_name::<U>());
+        for registration in registrations {
+            let type_id = registration.type_id;
+            unsafe { self.add(type_id, registration.metadata) };
+            #[cfg(feature = "names")]
+            let name = registration.type_name;
+            #[cfg(not(feature = "names"))]
+            let name = None;
+            record_registration(self, type_id, name);
+        }
+    }
+
+    /// Install the [`Metadata<U>`] yielded for each [`TypeId`] by
+    /// `registrations`, as though by
+    /// [`register_iter`][TypeDatabaseEntryExt::register_iter].
+    ///
+    /// # Safety
+    /// Each yielded metadata must be the correct [`Metadata<U>`] for the
+    /// concrete type represented by the accompanying `TypeId`.
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    unsafe fn register_raw_iter<T>(&mut self, registrations: T)
+    where
+        T: IntoIterator<Item = (TypeId, Metadata<U>)>,
+    {
+        nested_span!("register_raw_iter", U = type_name::<U>());
+        self.register_iter(
+            registrations
+                .into_iter()
+                .map(|(type_id, metadata)| Registration::from_raw_parts(type_id, metadata)),
+        );
+    }
+
+    /// Revoke the registration represented by `token`, reinstating any
+    /// registration that it replaced, and returning whether it was still
+    /// present.
+    ///
+    /// Tokens for registrations that were never installed (because the store's
+    /// [`overwrite_policy`][TypeDatabaseEntry::overwrite_policy] kept an
+    /// existing registration instead) revoke nothing.
+    fn unregister(&mut self, token: RegistrationToken<U>) -> bool {
+        nested_span!("unregister", U = type_name::<U>());
+        token.revoke(self)
+    }
+
+    /// Cast each pointer yielded by `pointers` to `U`, yielding the result of
+    /// each cast in turn.
+    ///
+    /// Only this entry's own registrations are consulted.
+    fn cast_all<I>(&self, pointers: I) -> CastIter<'_, U, Self, I::IntoIter>
+    where
+        I: IntoIterator,
+    {
+        CastIter::new(Some(self), pointers.into_iter())
+    }
+
+    /// Cast `data`, a pointer to an instance of the concrete type with the given
+    /// `type_id`, to a type-erased pointer to `U`; or `None` if that concrete
+    /// type is not registered as an implementor of `U`.
+    ///
+    /// As no instance is available against which to evaluate them,
+    /// registrations subject to a predicate are never satisfied.  Only this
+    /// entry's own registrations are consulted.
+    ///
+    /// # Safety
+    /// `data` must point to an instance of the concrete type with the given
+    /// `type_id`.
+    unsafe fn cast_raw(&self, type_id: TypeId, data: *const ()) -> Option<ErasedFatPtr>
+    where
+        U: 'static,
+    {
+        nested_span!("cast_raw", U = type_name::<U>());
+        let metadata = self.metadata(type_id);
+        observe_lookup(
+            self.observer(),
+            type_name::<U>(),
+            type_id,
+            metadata.is_some(),
+        );
+        match self.predicate(type_id) {
+            Some(_) => None,
+            None => {
+                metadata.map(|metadata| ErasedFatPtr::new::<U>(ptr::from_raw_parts(data, metadata)))
+            }
         }
     }
 
     /// Attempt to determine the concrete type of the given `data`.
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     fn concrete_type_id<P>(&self, data: &P) -> Result<TypeId, DatabaseEntryError<U, P>>
     where
         P: ?Sized + InnermostTypeId,
     {
+        nested_span!("concrete_type_id");
         Ok(data.innermost_type_id()?)
     }
 
+    /// The metadata, if any, with which concrete type `I` is registered as an
+    /// implementor of `U`: from which, for example, a fat pointer to an
+    /// instance of `I` can be constructed with [`ptr::from_raw_parts`].
+    fn metadata_for<I>(&self) -> Option<Metadata<U>>
+    where
+        I: 'static + Unsize<U>,
+    {
+        nested_span!("metadata_for", U = type_name::<U>(), I = type_name::<I>());
+        self.metadata(TypeId::of::<I>())
+    }
+
+    /// Whether the concrete type with the given `type_id` is registered as an
+    /// implementor of `U`.
+    ///
+    /// Any predicate with which the type was registered is not evaluated, as
+    /// there is no instance against which to do so.
+    fn contains_type(&self, type_id: TypeId) -> bool {
+        nested_span!("contains_type", ?type_id);
+        let found = self.contains(type_id);
+        observe_lookup(self.observer(), type_name::<U>(), type_id, found);
+        found
+    }
+
     /// Whether `data` is registered as an implementor of `U`.
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
-        P = type_name::<P>(),
-        U = type_name::<U>(),
-    )))]
     fn implements<P>(&self, data: &P) -> Result<bool, DatabaseEntryError<U, P>>
     where
         P: ?Sized + InnermostTypeId,
     {
-        self.concrete_type_id(data)
-            .map(|type_id| self.contains(type_id))
+        nested_span!("implements", P = type_name::<P>(), U = type_name::<U>());
+        let type_id = self.concrete_type_id(data)?;
+        Ok(self.implements_type_id(type_id, data)?)
+    }
+
+    /// Whether `data`, whose concrete type has the given `type_id`, is
+    /// registered as an implementor of `U`.
+    fn implements_type_id<P>(
+        &self,
+        type_id: TypeId,
+        data: &P,
+    ) -> Result<bool, TypeIdDeterminationError>
+    where
+        P: ?Sized + InnermostTypeId,
+    {
+        nested_span!("implements_type_id", ?type_id);
+        let found = self.contains(type_id);
+        observe_lookup(self.observer(), type_name::<U>(), type_id, found);
+        Ok(found && satisfies(self.predicate(type_id), data)?)
+    }
+
+    /// The [`Detail::Namespace`], if any, of the concrete type represented by
+    /// `type_id`.
+    fn namespace(&self, type_id: TypeId) -> Option<&'static str> {
+        match self.detail(type_id, DetailKind::Namespace)? {
+            Detail::Namespace(namespace) => Some(namespace),
+            _ => None,
+        }
+    }
+
+    /// The [`Detail::TypeName`], if any, of the concrete type represented by
+    /// `type_id`.
+    #[cfg(feature = "names")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "names")))]
+    fn type_name(&self, type_id: TypeId) -> Option<&'static str> {
+        match self.detail(type_id, DetailKind::TypeName)? {
+            Detail::TypeName(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The [`Detail::Location`], if any, of the concrete type represented by
+    /// `type_id`.
+    #[cfg(feature = "debug-registry")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "debug-registry")))]
+    fn location(&self, type_id: TypeId) -> Option<&'static Location<'static>> {
+        match self.detail(type_id, DetailKind::Location)? {
+            Detail::Location(location) => Some(location),
+            _ => None,
+        }
+    }
+
+    /// The [`Detail::Cloner`], if any, of the concrete type represented by
+    /// `type_id`.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    fn cloner(&self, type_id: TypeId) -> Option<Cloner<U>> {
+        match self.detail(type_id, DetailKind::Cloner)? {
+            Detail::Cloner(cloner) => Some(cloner),
+            _ => None,
+        }
+    }
+
+    /// The [`Detail::Constructor`], if any, of the concrete type represented by
+    /// `type_id`.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    fn constructor(&self, type_id: TypeId) -> Option<Constructor<U>> {
+        match self.detail(type_id, DetailKind::Constructor)? {
+            Detail::Constructor(constructor) => Some(constructor),
+            _ => None,
+        }
+    }
+
+    /// The [`Detail::Equality`], if any, of the concrete type represented by
+    /// `type_id`.
+    fn equality(&self, type_id: TypeId) -> Option<Equality> {
+        match self.detail(type_id, DetailKind::Equality)? {
+            Detail::Equality(equality) => Some(equality),
+            _ => None,
+        }
+    }
+
+    /// The [`Detail::Hashing`], if any, of the concrete type represented by
+    /// `type_id`.
+    fn hashing(&self, type_id: TypeId) -> Option<Hashing> {
+        match self.detail(type_id, DetailKind::Hashing)? {
+            Detail::Hashing(hashing) => Some(hashing),
+            _ => None,
+        }
+    }
+
+    /// The [`Detail::Tag`], if any, of the concrete type represented by
+    /// `type_id`.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    fn tag(&self, type_id: TypeId) -> Option<Tag<U>> {
+        match self.detail(type_id, DetailKind::Tag)? {
+            Detail::Tag(tag) => Some(tag),
+            _ => None,
+        }
     }
 
     /// Cast `pointer` to `P::Coerced<U>`, if registered as an implementor of
     /// `U`.
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
-        P = type_name::<P>(),
-        U = type_name::<U>(),
-    )))]
+    #[cfg_attr(feature = "debug-casts", track_caller)]
     fn cast<P>(&self, pointer: P) -> Result<P::Coerced<U>, CastError<U, P>>
     where
         P: Pointer + InnermostTypeId,
@@ -105,26 +982,375 @@ where
         P::Inner: Coercible,
         Coerced<P::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
     {
-        unsafe {
-            match self.concrete_type_id(&pointer).and_then(|type_id| {
-                self.metadata(type_id).ok_or(
-                    DatabaseEntryError::ConcreteTypeNotRegisteredForTarget {
-                        type_id,
-                        requested_type: PhantomData,
-                        instance_type: PhantomData,
-                    },
-                )
-            }) {
-                Ok(&metadata) => Ok(pointer.coerce(metadata)),
-                Err(source) => Err(CastError { source, pointer }),
+        nested_span!("cast", P = type_name::<P>(), U = type_name::<U>());
+        cast_resolved(
+            Ok(self),
+            self.observer(),
+            pointer,
+            |_| None,
+            #[cfg(feature = "names")]
+            |type_id| self.type_name(type_id),
+            #[cfg(not(feature = "names"))]
+            |_| None,
+        )
+    }
+}
+
+/// The error, if any, that arises from registering concrete type `I` in
+/// `entry`, because it is already registered there.
+fn existing_registration<U, E, I>(entry: &E) -> Option<RegistrationError<U>>
+where
+    U: ?Sized,
+    E: ?Sized + TypeDatabaseEntry<U>,
+    I: 'static + Unsize<U>,
+{
+    let type_id = TypeId::of::<I>();
+    let metadata = entry.metadata(type_id)?;
+    let previous = PreviousRegistration {
+        #[cfg(feature = "names")]
+        type_name: entry.type_name(type_id),
+        #[cfg(not(feature = "names"))]
+        type_name: None,
+        #[cfg(feature = "debug-registry")]
+        location: entry.location(type_id),
+    };
+    let target = PhantomData;
+    Some(if metadata == metadata_of::<U, I>() {
+        RegistrationError::AlreadyRegistered {
+            type_id,
+            previous,
+            target,
+        }
+    } else {
+        RegistrationError::ConflictingRegistration {
+            type_id,
+            previous,
+            target,
+        }
+    })
+}
+
+/// Register concrete type `I` in `entry`, irrespective of its
+/// [`OverwritePolicy`].
+#[cfg_attr(feature = "debug-registry", track_caller)]
+fn install<U, E, I>(entry: &mut E)
+where
+    U: ?Sized,
+    E: ?Sized + TypeDatabaseEntry<U>,
+    I: 'static + Unsize<U>,
+{
+    unsafe {
+        let type_id = TypeId::of::<I>();
+        let metadata = metadata_of::<U, I>();
+        entry.add(type_id, metadata);
+        #[cfg(feature = "validate")]
+        assert!(
+            entry.metadata(type_id) == Some(metadata),
+            "entry for <{}> did not retain the metadata added for <{}>",
+            type_name::<U>(),
+            type_name::<I>(),
+        );
+        record_registration(entry, type_id, Some(type_name::<I>()));
+    }
+}
+
+/// Record the details of the concrete type with the given `type_id` and `name`
+/// (if known), just added to `entry`, and notify its observer.
+#[cfg_attr(feature = "debug-registry", track_caller)]
+fn record_registration<U, E>(entry: &mut E, type_id: TypeId, name: Option<&'static str>)
+where
+    U: ?Sized,
+    E: ?Sized + TypeDatabaseEntry<U>,
+{
+    #[cfg(feature = "names")]
+    if let Some(name) = name {
+        entry.set_detail(type_id, Detail::TypeName(name));
+    }
+    #[cfg(feature = "debug-registry")]
+    entry.set_detail(type_id, Detail::Location(Location::caller()));
+    observe_registration(
+        entry.observer(),
+        type_name::<U>(),
+        type_id,
+        name.unwrap_or("?"),
+    );
+}
+
+/// Whether registering concrete type `I` in `entry` should proceed, according
+/// to its [`OverwritePolicy`].
+#[cfg_attr(feature = "debug-registry", track_caller)]
+fn overwrite<U, E, I>(entry: &E) -> bool
+where
+    U: ?Sized,
+    E: ?Sized + TypeDatabaseEntry<U>,
+    I: 'static + Unsize<U>,
+{
+    let policy = entry.overwrite_policy();
+    if policy == OverwritePolicy::LastWins || !entry.contains(TypeId::of::<I>()) {
+        return true;
+    }
+    if let Some(error) = existing_registration::<U, E, I>(entry) {
+        match policy {
+            OverwritePolicy::Panic => panic!("{:?}", error),
+            OverwritePolicy::Error => {
+                diagnostic!(warn, "registration rejected: {}", error);
+                #[cfg(feature = "defmt")]
+                defmt::warn!("registration rejected: {}", error);
+                if let Some(observer) = entry.observer() {
+                    observer.on_registration_rejected(type_name::<U>(), &error);
+                }
             }
+            OverwritePolicy::LastWins | OverwritePolicy::FirstWins => {}
+        }
+    }
+    false
+}
+
+/// Notify `observer`, if any, and any diagnostic logger of the outcome of
+/// looking up `type_id` in the entry for `target`.
+fn observe_lookup(
+    observer: Option<&dyn Observer>,
+    target: &'static str,
+    type_id: TypeId,
+    found: bool,
+) {
+    diagnostic!(
+        trace for target,
+        "lookup of {:?} in <{}>: {}",
+        type_id,
+        target,
+        if found { "hit" } else { "miss" },
+    );
+    #[cfg(feature = "defmt")]
+    defmt::trace!("lookup in <{=str}>: {=bool}", target, found);
+    if let Some(observer) = observer {
+        if found {
+            observer.on_lookup_hit(target, type_id);
+        } else {
+            observer.on_lookup_miss(target, type_id);
         }
     }
 }
 
+/// Notify `observer`, if any, and any diagnostic logger of the registration
+/// of the concrete type named `implementor` as an implementor of `target`.
+fn observe_registration(
+    observer: Option<&dyn Observer>,
+    target: &'static str,
+    type_id: TypeId,
+    implementor: &'static str,
+) {
+    diagnostic!(
+        debug for target,
+        "registered <{}> as implementor of <{}>",
+        implementor,
+        target
+    );
+    #[cfg(feature = "metrics")]
+    metrics::counter!("rattish_registrations_total", "target" => target).increment(1);
+    if let Some(observer) = observer {
+        observer.on_register(target, type_id, implementor);
+    }
+}
+
+/// Count, with the `metrics` feature, an attempted cast to `target` and
+/// whether it `succeeded`.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_cast(target: &'static str, succeeded: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!("rattish_casts_attempted_total", "target" => target).increment(1);
+        if succeeded {
+            metrics::counter!("rattish_casts_succeeded_total", "target" => target).increment(1);
+        } else {
+            metrics::counter!("rattish_casts_failed_total", "target" => target).increment(1);
+        }
+    }
+}
+
+/// Notify `observer`, if any, and any diagnostic logger that a cast to `target`
+/// of a `pointer` to the concrete type with the given `type_id` (and, if known,
+/// `type_name`) failed with `error`, of the given `kind`.
+///
+/// With the `cast-failure-events` feature, a single structured `tracing` event
+/// is also emitted (at `WARN` level, with target `rattish::cast_failure`) even
+/// if spans are not: failures can thereby be alerted upon without
+/// instrumenting every cast.
+#[cfg_attr(not(feature = "cast-failure-events"), allow(unused_variables))]
+pub(crate) fn observe_cast_failure(
+    observer: Option<&dyn Observer>,
+    target: &'static str,
+    pointer: &'static str,
+    kind: ErrorKind,
+    type_id: Option<TypeId>,
+    type_name: Option<&'static str>,
+    error: &dyn fmt::Debug,
+) {
+    diagnostic!(debug for failed target, "cast to <{}> failed: {:?}", target, error);
+    // not subject to any filter
+    #[cfg(feature = "cast-failure-events")]
+    tracing::warn!(
+        target: "rattish::cast_failure",
+        target_type = target,
+        pointer_type = pointer,
+        concrete_type_id = type_id.map(tracing::field::debug),
+        concrete_type_name = type_name,
+        kind = ?kind,
+        "cast failed",
+    );
+    record_cast(target, false);
+    #[cfg(feature = "defmt")]
+    defmt::debug!("cast to <{=str}> failed: {}", target, kind);
+    if let Some(observer) = observer {
+        observer.on_cast_failure(target, error);
+    }
+}
+
+/// The metadata and predicate, if any, with which the concrete type with the
+/// given `type_id` is registered in `entry`; or otherwise the candidates that
+/// are registered instead.
+///
+/// Being independent of the pointer type, this is monomorphized only once per
+/// entry rather than for every type of pointer that is cast.
+fn registration<U, E>(entry: &E, type_id: TypeId) -> Option<(Metadata<U>, Option<Predicate>)>
+where
+    U: ?Sized,
+    E: ?Sized + TypeDatabaseEntry<U>,
+{
+    let metadata = entry.metadata(type_id);
+    observe_lookup(
+        entry.observer(),
+        type_name::<U>(),
+        type_id,
+        metadata.is_some(),
+    );
+    Some((metadata?, entry.predicate(type_id)))
+}
+
+/// The metadata of the concrete type of `data`, which has the given `type_id`,
+/// as an implementor of `U`: as registered in `entry` or, failing that, as
+/// found by `fallback`; provided that `data` satisfies any predicate to which
+/// that registration is subject.
+#[cfg_attr(not(feature = "names"), allow(unused_variables))]
+pub(crate) fn resolve<U, E, P, F>(
+    entry: Result<&E, DatabaseError<U>>,
+    type_id: TypeId,
+    data: &P,
+    fallback: F,
+) -> Result<Metadata<U>, DatabaseEntryError<U, P>>
+where
+    U: 'static + ?Sized,
+    E: ?Sized + TypeDatabaseEntry<U>,
+    P: ?Sized + InnermostTypeId,
+    F: FnOnce(TypeId) -> Option<(Metadata<U>, Option<Predicate>)>,
+{
+    let found = match entry {
+        Ok(entry) => registration(entry, type_id),
+        Err(_) => None,
+    };
+    let Some((metadata, predicate)) = found.or_else(|| fallback(type_id)) else {
+        return Err(match entry {
+            Ok(entry) => DatabaseEntryError::ConcreteTypeNotRegisteredForTarget {
+                type_id,
+                requested_type: PhantomData,
+                instance_type: PhantomData,
+                candidates: Candidates {
+                    #[cfg(feature = "names")]
+                    registered: entry.registered_names(),
+                    ..Candidates::default()
+                },
+            },
+            Err(error) => error.into(),
+        });
+    };
+    if satisfies(predicate, data)? {
+        Ok(metadata)
+    } else {
+        Err(DatabaseEntryError::PredicateNotSatisfied {
+            type_id,
+            requested_type: PhantomData,
+            instance_type: PhantomData,
+        })
+    }
+}
+
+/// The metadata of the concrete type of `data`, which has the given `type_id`,
+/// as an implementor of `U` in `db` (see [`resolve`]).
+pub(crate) fn resolve_in<U, DB, P>(
+    db: &DB,
+    type_id: TypeId,
+    data: &P,
+) -> Result<Metadata<U>, DatabaseEntryError<U, P>>
+where
+    U: 'static + ?Sized,
+    DB: ?Sized + TypeDatabase,
+    P: ?Sized + InnermostTypeId,
+{
+    resolve(db.get_db_entry::<U>(), type_id, data, |type_id| {
+        db.fallback_metadata::<U>(type_id)
+    })
+}
+
+/// Cast `pointer` to `U` with the metadata that is [`resolve`]d for its
+/// concrete type, notifying `observer` (and any diagnostic logger) of failure
+/// with the concrete type's name as given by `name`.
+#[cfg_attr(feature = "debug-casts", track_caller)]
+pub(crate) fn cast_resolved<U, E, P, F, N>(
+    entry: Result<&E, DatabaseError<U>>,
+    observer: Option<&dyn Observer>,
+    pointer: P,
+    fallback: F,
+    name: N,
+) -> Result<P::Coerced<U>, CastError<U, P>>
+where
+    U: 'static + ?Sized,
+    E: ?Sized + TypeDatabaseEntry<U>,
+    P: Pointer + InnermostTypeId,
+    P::Coerced<U>: Sized,
+    P::Inner: Coercible,
+    Coerced<P::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    F: FnOnce(TypeId) -> Option<(Metadata<U>, Option<Predicate>)>,
+    N: FnOnce(TypeId) -> Option<&'static str>,
+{
+    let metadata = match pointer.innermost_type_id() {
+        Ok(type_id) => resolve(entry, type_id, &pointer, fallback),
+        Err(reason) => Err(reason.into()),
+    };
+    match metadata {
+        Ok(metadata) => {
+            record_cast(type_name::<U>(), true);
+            Ok(unsafe { pointer.coerce(metadata) })
+        }
+        Err(source) => {
+            let type_id = source.concrete_type_id();
+            observe_cast_failure(
+                observer,
+                type_name::<U>(),
+                type_name::<P>(),
+                source.kind(),
+                type_id,
+                type_id.and_then(name),
+                &source,
+            );
+            Err(CastError::new(source, pointer))
+        }
+    }
+}
+
+/// Whether `data` satisfies `predicate`, if any.
+fn satisfies<P>(predicate: Option<Predicate>, data: &P) -> Result<bool, TypeIdDeterminationError>
+where
+    P: ?Sized + InnermostTypeId,
+{
+    match predicate {
+        Some(predicate) => data.with_innermost_any(predicate),
+        None => Ok(true),
+    }
+}
+
 impl<U, E> TypeDatabaseEntryExt<U> for E
 where
-    Self: TypeDatabaseEntry<U>,
+    E: ?Sized + TypeDatabaseEntry<U>,
     U: ?Sized,
 {
 }
@@ -148,18 +1374,61 @@ pub unsafe trait TypeDatabase {
     /// ```
     type Entry<U: ?Sized>: TypeDatabaseEntry<U>;
 
+    /// Returns a shared/immutable reference to the value of the entry that is
+    /// keyed by `U`.
+    fn get_entry<U>(&self) -> Option<&Self::Entry<U>>
+    where
+        U: 'static + ?Sized;
+
+    /// The [`Observer`], if any, to be notified of activity on this database.
+    fn observer(&self) -> Option<&dyn Observer> {
+        None
+    }
+
+    /// The name, if known, of the concrete type with the given `type_id`.
+    #[cfg(feature = "names")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "names")))]
+    fn concrete_type_name(&self, _type_id: TypeId) -> Option<&'static str> {
+        None
+    }
+
+    /// The [`TraitIndex`], if any, that is kept up to date with this database's
+    /// registrations, by which implementation of several targets can be tested
+    /// together.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    fn cached_trait_index(&self) -> Option<&TraitIndex> {
+        None
+    }
+
+    /// The metadata, if any, for the concrete type with the given `type_id` as
+    /// an implementor of `U` that is not registered in the entry keyed by `U`
+    /// but is nevertheless known to the database (for example, because it is
+    /// registered as an implementor of a subtrait of `U`), together with any
+    /// predicate to which that registration is subject.
+    fn fallback_metadata<U>(&self, _type_id: TypeId) -> Option<(Metadata<U>, Option<Predicate>)>
+    where
+        U: 'static + ?Sized,
+    {
+        None
+    }
+}
+
+/// A [`TypeDatabase`] whose entries can be mutated.
+///
+/// # Safety
+/// Lookups for a given key must always return references to the same value,
+/// which must also be that returned by [`get_entry`][TypeDatabase::get_entry].
+pub unsafe trait TypeDatabaseMut
+where
+    Self: TypeDatabase,
+{
     /// Returns an exclusive/mutable reference to the value of the entry that is
     /// keyed by `U`.  A new entry may be created if one did not previously
     /// exist.
     fn get_entry_mut<U>(&mut self) -> &mut Self::Entry<U>
     where
         U: 'static + ?Sized;
-
-    /// Returns a shared/immutable reference to the value of the entry that is
-    /// keyed by `U`.
-    fn get_entry<U>(&self) -> Option<&Self::Entry<U>>
-    where
-        U: 'static + ?Sized;
 }
 
 /// The consumer interface of a `TypeDatabase`.
@@ -169,18 +1438,356 @@ where
 {
     /// Returns a shared/immutable reference to the value of the entry that is
     /// keyed by `U`.
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
-        U = type_name::<U>(),
-    )))]
     fn get_db_entry<U>(&self) -> Result<&Self::Entry<U>, DatabaseError<U>>
     where
         U: 'static + ?Sized,
     {
+        nested_span!("get_db_entry", U = type_name::<U>());
         self.get_entry()
             .ok_or(DatabaseError::RequestedTypeNotInDatabase {
                 requested_type: PhantomData,
             })
     }
+
+    /// Register concrete type `I` as an implementor of `U`, returning a token
+    /// with which the registration can later be revoked.
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_tracked<U, I>(&mut self) -> RegistrationToken<U>
+    where
+        Self: TypeDatabaseMut,
+        U: 'static + ?Sized,
+        I: 'static + Unsize<U>,
+    {
+        nested_span!(
+            "register_tracked",
+            U = type_name::<U>(),
+            I = type_name::<I>()
+        );
+        self.get_entry_mut::<U>().register_tracked::<I>()
+    }
+
+    /// Register concrete type `I` as an implementor of `U` until the returned
+    /// guard is dropped.
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    fn register_scoped<U, I>(&mut self) -> RegistrationGuard<'_, Self, U>
+    where
+        Self: TypeDatabaseMut,
+        U: 'static + ?Sized,
+        I: 'static + Unsize<U>,
+    {
+        nested_span!(
+            "register_scoped",
+            U = type_name::<U>(),
+            I = type_name::<I>()
+        );
+        let token = self.register_tracked::<U, I>();
+        RegistrationGuard::new(self, token)
+    }
+
+    /// Register `metadata` as that of the concrete type with the given
+    /// `type_id` as an implementor of `U`, for example where both were obtained
+    /// from another image through [`capture_metadata`].
+    ///
+    /// Unlike [`register`][TypeDatabaseEntryExt::register], the registration
+    /// is made irrespective of the entry's
+    /// [`overwrite_policy`][TypeDatabaseEntry::overwrite_policy] and records
+    /// no type name.
+    ///
+    /// # Safety
+    /// `metadata` must be the correct [`Metadata<U>`] for the concrete type
+    /// represented by `type_id`.
+    #[cfg_attr(feature = "debug-registry", track_caller)]
+    unsafe fn register_metadata<U>(&mut self, type_id: TypeId, metadata: Metadata<U>)
+    where
+        Self: TypeDatabaseMut,
+        U: 'static + ?Sized,
+    {
+        nested_span!("register_metadata", ?type_id, U = type_name::<U>());
+        let entry = self.get_entry_mut::<U>();
+        entry.add(type_id, metadata);
+        record_registration(entry, type_id, None);
+    }
+
+    /// Revoke the registration represented by `token`, reinstating any
+    /// registration that it replaced, and returning whether it was still
+    /// present (see [`unregister`][TypeDatabaseEntryExt::unregister]).
+    fn unregister<U>(&mut self, token: RegistrationToken<U>) -> bool
+    where
+        Self: TypeDatabaseMut,
+        U: 'static + ?Sized,
+    {
+        nested_span!("unregister", U = type_name::<U>());
+        self.get_entry_mut::<U>().unregister(token)
+    }
+
+    /// Whether the concrete type with the given `type_id` is registered as an
+    /// implementor of `U` (see
+    /// [`contains_type`][TypeDatabaseEntryExt::contains_type]).
+    fn is_implementor_id<U>(&self, type_id: TypeId) -> bool
+    where
+        U: 'static + ?Sized,
+    {
+        nested_span!("is_implementor_id", ?type_id, U = type_name::<U>());
+        matches!(self.get_entry::<U>(), Some(entry) if entry.contains_type(type_id))
+    }
+
+    /// Whether concrete type `I` is registered as an implementor of `U`.
+    fn is_registered<U, I>(&self) -> bool
+    where
+        U: 'static + ?Sized,
+        I: 'static + Unsize<U>,
+    {
+        nested_span!("is_registered", U = type_name::<U>(), I = type_name::<I>());
+        self.is_implementor_id::<U>(TypeId::of::<I>())
+    }
+
+    /// Clone `value`'s ultimate concrete instance into a [`Box<U>`], if its
+    /// type was registered as a clonable implementor of `U` (for example, with
+    /// [`register_clone`][TypeDatabaseEntryExt::register_clone]).
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    fn dyn_clone<U, P>(&self, value: &P) -> Option<Box<U>>
+    where
+        U: 'static + ?Sized,
+        P: ?Sized + InnermostTypeId,
+    {
+        nested_span!("dyn_clone", U = type_name::<U>());
+        let entry = self.get_entry::<U>()?;
+        value
+            .with_innermost_any(|data| {
+                let cloner = entry.cloner(data.type_id())?;
+                Some(unsafe { cloner(address_of(data)) })
+            })
+            .ok()
+            .flatten()
+    }
+
+    /// Serialize `value`'s ultimate concrete instance, tagged with the name
+    /// under which its type was registered as an implementor of `U` (for
+    /// example, with [`register_tagged`][TypeDatabaseEntryExt::register_tagged]).
+    ///
+    /// See the [`tagged`] module for the representation.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    fn serialize_dyn<U, P, S>(&self, value: &P, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        U: 'static + ?Sized,
+        P: ?Sized + InnermostTypeId,
+        S: serde::Serializer,
+    {
+        nested_span!("serialize_dyn", U = type_name::<U>());
+        use serde::ser::Error;
+
+        let entry = self.get_entry::<U>();
+        value
+            .with_innermost_any(
+                |data| match entry.and_then(|entry| entry.tag(data.type_id())) {
+                    Some(tag) => unsafe { tagged::serialize(tag, address_of(data), serializer) },
+                    None => Err(S::Error::custom(format_args!(
+                        "concrete type is not a tagged implementor of {}",
+                        type_name::<U>(),
+                    ))),
+                },
+            )
+            .unwrap_or_else(|err| Err(S::Error::custom(err)))
+    }
+
+    /// Deserialize a [`Box<U>`] from a value tagged with the name under which
+    /// its concrete type was registered as an implementor of `U` (for example,
+    /// with [`register_tagged`][TypeDatabaseEntryExt::register_tagged]).
+    ///
+    /// See the [`tagged`] module for the representation.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    fn deserialize_dyn<'de, U, D>(&self, deserializer: D) -> Result<Box<U>, D::Error>
+    where
+        U: 'static + ?Sized,
+        D: serde::Deserializer<'de>,
+    {
+        nested_span!("deserialize_dyn", U = type_name::<U>());
+        deserializer.deserialize_map(tagged::TaggedVisitor {
+            entry: self.get_entry::<U>(),
+            target: PhantomData,
+        })
+    }
+
+    /// Create a new instance of the concrete type with the given `type_id`, if
+    /// it was registered as a constructible implementor of `U` (for example,
+    /// with [`register_default`][TypeDatabaseEntryExt::register_default]).
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    fn create<U>(&self, type_id: TypeId) -> Option<Box<U>>
+    where
+        U: 'static + ?Sized,
+    {
+        nested_span!("create", ?type_id, U = type_name::<U>());
+        let constructor = self.get_entry::<U>()?.constructor(type_id)?;
+        Some(constructor())
+    }
+
+    /// Compare the ultimate concrete instances of `a` and `b` for equality, if
+    /// the type of `a` was registered as a comparable implementor of `U` (for
+    /// example, with [`register_eq`][TypeDatabaseEntryExt::register_eq]).
+    ///
+    /// Instances of different concrete types are never equal.
+    fn dyn_eq<U, A, B>(&self, a: &A, b: &B) -> Option<bool>
+    where
+        U: 'static + ?Sized,
+        A: ?Sized + InnermostTypeId,
+        B: ?Sized + InnermostTypeId,
+    {
+        nested_span!("dyn_eq", U = type_name::<U>());
+        let entry = self.get_entry::<U>()?;
+        a.with_innermost_any(|a| {
+            let type_id = a.type_id();
+            let equality = entry.equality(type_id)?;
+            b.with_innermost_any(|b| {
+                type_id == b.type_id() && unsafe { equality(address_of(a), address_of(b)) }
+            })
+            .ok()
+        })
+        .ok()
+        .flatten()
+    }
+
+    /// Feed the ultimate concrete instance of `value`, together with its type,
+    /// into `state`, if that type was registered as a hashable implementor of
+    /// `U` (for example, with
+    /// [`register_hash`][TypeDatabaseEntryExt::register_hash]); returns whether
+    /// it was so registered.
+    fn dyn_hash<U, P, H>(&self, value: &P, state: &mut H) -> bool
+    where
+        U: 'static + ?Sized,
+        P: ?Sized + InnermostTypeId,
+        H: Hasher,
+    {
+        nested_span!("dyn_hash", U = type_name::<U>());
+        let entry = match self.get_entry::<U>() {
+            Some(entry) => entry,
+            None => return false,
+        };
+        value
+            .with_innermost_any(|data| {
+                let type_id = data.type_id();
+                match entry.hashing(type_id) {
+                    Some(hashing) => {
+                        type_id.hash(state);
+                        unsafe { hashing(address_of(data), state) };
+                        true
+                    }
+                    None => false,
+                }
+            })
+            .unwrap_or(false)
+    }
+
+    /// Wraps `value` so that it serializes as its ultimate concrete instance,
+    /// which must be registered in this database as an implementor of
+    /// [`dyn erased_serde::Serialize`][erased_serde::Serialize] (for example,
+    /// with [`register_prelude`][serialize::register_prelude]).
+    #[cfg(feature = "erased-serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "erased-serde")))]
+    fn serializable<'a, P>(&'a self, value: &'a P) -> Serializable<'a, Self, P>
+    where
+        P: ?Sized + InnermostTypeId,
+    {
+        Serializable { db: self, value }
+    }
+
+    /// Wraps `values` so that they serialize as a sequence of their ultimate
+    /// concrete instances, as though each were
+    /// [`serializable`][TypeDatabaseExt::serializable].
+    #[cfg(feature = "erased-serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "erased-serde")))]
+    fn serializable_seq<'a, T>(&'a self, values: &'a [T]) -> SerializableSeq<'a, Self, T>
+    where
+        T: InnermostTypeId,
+    {
+        SerializableSeq { db: self, values }
+    }
+
+    /// Returns a read-only view of this database.
+    fn view(&self) -> DatabaseView<'_, Self> {
+        DatabaseView::new(self)
+    }
 }
 
 impl<DB> TypeDatabaseExt for DB where Self: TypeDatabase {}
+
+macro_rules! forward_type_database {
+    ($( $(#[$feature:literal])? $ty:ty, )+) => {$(
+        $( #[cfg(feature = $feature)] )?
+        unsafe impl<DB> TypeDatabase for $ty
+        where
+            DB: ?Sized + TypeDatabase,
+        {
+            type Entry<U: ?Sized> = DB::Entry<U>;
+
+            fn get_entry<U>(&self) -> Option<&Self::Entry<U>>
+            where
+                U: 'static + ?Sized,
+            {
+                (**self).get_entry()
+            }
+
+            fn observer(&self) -> Option<&dyn Observer> {
+                (**self).observer()
+            }
+
+            #[cfg(feature = "names")]
+            fn concrete_type_name(&self, type_id: TypeId) -> Option<&'static str> {
+                (**self).concrete_type_name(type_id)
+            }
+
+            #[cfg(feature = "std")]
+            fn cached_trait_index(&self) -> Option<&TraitIndex> {
+                (**self).cached_trait_index()
+            }
+
+            fn fallback_metadata<U>(
+                &self,
+                type_id: TypeId,
+            ) -> Option<(Metadata<U>, Option<Predicate>)>
+            where
+                U: 'static + ?Sized,
+            {
+                (**self).fallback_metadata::<U>(type_id)
+            }
+        }
+    )+};
+    (mut $( $(#[$feature:literal])? $ty:ty, )+) => {
+        forward_type_database! { $( $(#[$feature])? $ty, )+ }
+
+        $(
+            $( #[cfg(feature = $feature)] )?
+            unsafe impl<DB> TypeDatabaseMut for $ty
+            where
+                DB: ?Sized + TypeDatabaseMut,
+            {
+                fn get_entry_mut<U>(&mut self) -> &mut Self::Entry<U>
+                where
+                    U: 'static + ?Sized,
+                {
+                    (**self).get_entry_mut()
+                }
+            }
+        )+
+    };
+}
+
+forward_type_database! {
+    &DB,
+    #["alloc"] Rc<DB>,
+}
+
+// `alloc::sync` is only available on targets with atomic pointers
+#[cfg(target_has_atomic = "ptr")]
+forward_type_database! {
+    #["alloc"] Arc<DB>,
+}
+
+forward_type_database! {
+    mut
+    &mut DB,
+    #["alloc"] Box<DB>,
+}
@@ -0,0 +1,32 @@
+//! Observation of database activity.
+
+use core::{any::TypeId, fmt};
+
+/// An observer of activity on a [`TypeDatabase`][super::TypeDatabase] and its
+/// entries, with which a database may be constructed.
+///
+/// Targets are identified by their [`type_name`][core::any::type_name].  All
+/// methods do nothing by default.
+pub trait Observer
+where
+    Self: Send + Sync,
+{
+    /// Called after the concrete type `type_name`, with the given `type_id`, is
+    /// registered as an implementor of `target`.
+    fn on_register(&self, _target: &'static str, _type_id: TypeId, _type_name: &'static str) {}
+
+    /// Called when the concrete type with the given `type_id` is found to be
+    /// registered as an implementor of `target`.
+    fn on_lookup_hit(&self, _target: &'static str, _type_id: TypeId) {}
+
+    /// Called when the concrete type with the given `type_id` is found not to
+    /// be registered as an implementor of `target`.
+    fn on_lookup_miss(&self, _target: &'static str, _type_id: TypeId) {}
+
+    /// Called when an attempt to cast to `target` fails with `error`.
+    fn on_cast_failure(&self, _target: &'static str, _error: &dyn fmt::Debug) {}
+
+    /// Called when a registration for `target` is rejected with `error`, under
+    /// [`OverwritePolicy::Error`][super::OverwritePolicy::Error].
+    fn on_registration_rejected(&self, _target: &'static str, _error: &dyn fmt::Debug) {}
+}
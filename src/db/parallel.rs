@@ -0,0 +1,153 @@
+//! Parallel iterators that cast many pointers with a single entry lookup,
+//! shared across threads.
+//!
+//! As with the [iterators][super::iter], only the registrations in the looked
+//! up entry are consulted, and not any database fallbacks.
+
+use super::{
+    error::CastError, iter::cast_with, TypeDatabaseEntry, TypeDatabaseEntryExt, TypeDatabaseExt,
+};
+use crate::container::{Coerced, Coercible, InnermostTypeId, Metadata, Pointer};
+use core::{any::type_name, fmt, marker::PhantomData, ptr};
+use rayon::iter::{plumbing::UnindexedConsumer, ParallelIterator};
+
+/// A parallel iterator that casts each pointer yielded by `I` to `U`, using an
+/// entry `E` that was resolved only once.
+pub struct ParCastAll<'a, U, E, I>
+where
+    U: ?Sized,
+    E: ?Sized,
+{
+    entry: Option<&'a E>,
+    pointers: I,
+    target: PhantomData<fn(&U)>,
+}
+
+impl<U, E, I> ParallelIterator for ParCastAll<'_, U, E, I>
+where
+    U: 'static + ?Sized,
+    E: TypeDatabaseEntry<U> + Sync,
+    I: ParallelIterator,
+    I::Item: Pointer + InnermostTypeId,
+    <I::Item as Coercible>::Coerced<U>: Sized + Send,
+    <I::Item as Coercible>::Inner: Coercible,
+    Coerced<<I::Item as Coercible>::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    CastError<U, I::Item>: Send,
+{
+    type Item = Result<<I::Item as Coercible>::Coerced<U>, CastError<U, I::Item>>;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let entry = self.entry;
+        self.pointers
+            .map(move |pointer| cast_with(entry, pointer))
+            .drive_unindexed(consumer)
+    }
+}
+
+impl<U, E, I> fmt::Debug for ParCastAll<'_, U, E, I>
+where
+    U: ?Sized,
+    E: ?Sized + fmt::Debug,
+    I: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ParCastAll<{}>", type_name::<U>())?;
+        f.debug_struct("")
+            .field("entry", &self.entry)
+            .field("pointers", &self.pointers)
+            .finish()
+    }
+}
+
+/// A parallel iterator that yields only those items of `I` that are registered
+/// as implementors of `U` in an entry `E` that was resolved only once.
+pub struct ParFilterImplements<'a, U, E, I>
+where
+    U: ?Sized,
+    E: ?Sized,
+{
+    entry: Option<&'a E>,
+    items: I,
+    target: PhantomData<fn(&U)>,
+}
+
+impl<U, E, I> ParallelIterator for ParFilterImplements<'_, U, E, I>
+where
+    U: 'static + ?Sized,
+    E: TypeDatabaseEntry<U> + Sync,
+    I: ParallelIterator,
+    I::Item: InnermostTypeId,
+{
+    type Item = I::Item;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let entry = self.entry;
+        self.items
+            .filter(move |item| matches!(entry.map(|entry| entry.implements(item)), Some(Ok(true))))
+            .drive_unindexed(consumer)
+    }
+}
+
+impl<U, E, I> fmt::Debug for ParFilterImplements<'_, U, E, I>
+where
+    U: ?Sized,
+    E: ?Sized + fmt::Debug,
+    I: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ParFilterImplements<{}>", type_name::<U>())?;
+        f.debug_struct("")
+            .field("entry", &self.entry)
+            .field("items", &self.items)
+            .finish()
+    }
+}
+
+/// Adapters that cast the items of a parallel iterator, looking up the target
+/// in the database only once and sharing its entry across threads.
+pub trait DynParallelIterator<DB>
+where
+    Self: ParallelIterator,
+    DB: TypeDatabaseExt,
+{
+    /// Cast each item to `U` with `db`'s entry for `U` (without database
+    /// fallbacks), yielding the result of each cast.
+    fn par_cast_all<U>(self, db: &DB) -> ParCastAll<'_, U, DB::Entry<U>, Self>
+    where
+        U: 'static + ?Sized,
+        Self::Item: Pointer + InnermostTypeId,
+    {
+        ParCastAll {
+            entry: db.get_entry(),
+            pointers: self,
+            target: PhantomData,
+        }
+    }
+
+    /// Yield only those items that are registered as implementors of `U` in
+    /// `db`'s entry for `U` (without database fallbacks).
+    fn par_filter_implements<U>(self, db: &DB) -> ParFilterImplements<'_, U, DB::Entry<U>, Self>
+    where
+        U: 'static + ?Sized,
+        Self::Item: InnermostTypeId,
+    {
+        ParFilterImplements {
+            entry: db.get_entry(),
+            items: self,
+            target: PhantomData,
+        }
+    }
+}
+
+impl<DB, I> DynParallelIterator<DB> for I
+where
+    I: ParallelIterator,
+    DB: TypeDatabaseExt,
+{
+}
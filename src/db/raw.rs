@@ -0,0 +1,136 @@
+//! Fully type-erased pointers, for consumers that only know types at runtime.
+
+use crate::container::Metadata;
+use core::{
+    any::{type_name, TypeId},
+    fmt,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ptr,
+};
+
+/// Storage for type-erased [`Metadata`], which is assumed to be no larger (and
+/// no more strictly aligned) than a pair of `usize`s.
+type ErasedMetadata = MaybeUninit<[usize; 2]>;
+
+/// A type-erased pointer to an instance of some target type, as produced by a
+/// raw cast.
+#[derive(Clone, Copy)]
+pub struct ErasedFatPtr {
+    target: TypeId,
+    data: *const (),
+    metadata: ErasedMetadata,
+}
+
+impl ErasedFatPtr {
+    /// Erase the type of `pointer`.
+    ///
+    /// # Panics
+    /// Panics if `U`'s [`Metadata`] is larger, or more strictly aligned, than a
+    /// pair of `usize`s.
+    pub fn new<U>(pointer: *const U) -> Self
+    where
+        U: 'static + ?Sized,
+    {
+        assert!(
+            mem::size_of::<Metadata<U>>() <= mem::size_of::<ErasedMetadata>()
+                && mem::align_of::<Metadata<U>>() <= mem::align_of::<ErasedMetadata>(),
+            "metadata of <{}> cannot be erased",
+            type_name::<U>(),
+        );
+
+        let (data, metadata) = pointer.to_raw_parts();
+        let mut erased = ErasedMetadata::uninit();
+        unsafe { erased.as_mut_ptr().cast::<Metadata<U>>().write(metadata) };
+
+        Self {
+            target: TypeId::of::<U>(),
+            data,
+            metadata: erased,
+        }
+    }
+
+    /// The [`TypeId`] of the type to which this pointer points.
+    pub fn target(&self) -> TypeId {
+        self.target
+    }
+
+    /// The address of the data to which this pointer points, without its
+    /// metadata.
+    pub fn data(&self) -> *const () {
+        self.data
+    }
+
+    /// Recover the typed pointer, if `U` is the type to which this pointer
+    /// points.
+    pub fn downcast<U>(&self) -> Option<*const U>
+    where
+        U: 'static + ?Sized,
+    {
+        (self.target == TypeId::of::<U>()).then(|| {
+            // the metadata was written as a `Metadata<U>` by `new`
+            let metadata = unsafe { self.metadata.as_ptr().cast::<Metadata<U>>().read() };
+            ptr::from_raw_parts(self.data, metadata)
+        })
+    }
+}
+
+impl fmt::Debug for ErasedFatPtr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErasedFatPtr")
+            .field("target", &self.target)
+            .field("data", &self.data)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A type-erased shared reference to an instance of some target type.
+#[derive(Clone, Copy, Debug)]
+pub struct ErasedRef<'a> {
+    pointer: ErasedFatPtr,
+    lifetime: PhantomData<&'a ()>,
+}
+
+impl<'a> ErasedRef<'a> {
+    /// Wrap `pointer` as a reference.
+    ///
+    /// # Safety
+    /// `pointer` must be valid for shared reads for `'a`.
+    pub unsafe fn new(pointer: ErasedFatPtr) -> Self {
+        Self {
+            pointer,
+            lifetime: PhantomData,
+        }
+    }
+
+    /// The [`TypeId`] of the type to which this reference refers.
+    pub fn target(&self) -> TypeId {
+        self.pointer.target()
+    }
+
+    /// Recover the typed reference, if `U` is the type to which this reference
+    /// refers.
+    pub fn downcast<U>(&self) -> Option<&'a U>
+    where
+        U: 'static + ?Sized,
+    {
+        self.pointer.downcast().map(|pointer| unsafe { &*pointer })
+    }
+}
+
+/// A visitor of the views through which an object can be seen, being a
+/// reference to that object as each of the targets that it implements.
+pub trait ViewVisitor {
+    /// Visit `view`, whose target can be determined from
+    /// [`ErasedRef::target`] and recovered with [`ErasedRef::downcast`].
+    fn visit(&mut self, view: ErasedRef<'_>);
+}
+
+impl<F> ViewVisitor for F
+where
+    F: FnMut(ErasedRef<'_>),
+{
+    fn visit(&mut self, view: ErasedRef<'_>) {
+        self(view);
+    }
+}
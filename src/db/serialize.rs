@@ -0,0 +1,125 @@
+//! Serialization of type-erased values through their registration as
+//! implementors of [`erased_serde::Serialize`].
+
+use super::{TypeDatabaseEntryExt, TypeDatabaseExt, TypeDatabaseMut};
+use crate::{container::InnermostTypeId, DynCast};
+use core::{any::type_name, fmt};
+use serde::ser::{self, Serialize, SerializeSeq};
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::string::String;
+
+#[cfg(feature = "std")]
+use std::string::String;
+
+/// Register the standard library's scalar and string types as implementors of
+/// [`dyn erased_serde::Serialize`][erased_serde::Serialize] in `db`.
+pub fn register_prelude<DB>(db: &mut DB)
+where
+    DB: TypeDatabaseMut,
+{
+    macro_rules! register {
+        ($( $ty:ty )+) => {{
+            let entry = db.get_entry_mut::<dyn erased_serde::Serialize>();
+            $( entry.register::<$ty>(); )+
+        }};
+    }
+
+    register!(
+        () bool char
+        i8 i16 i32 i64 i128 isize
+        u8 u16 u32 u64 u128 usize
+        f32 f64
+        &'static str String
+    );
+}
+
+/// A value that serializes as its ultimate concrete instance, which must be
+/// registered in `db` as an implementor of
+/// [`dyn erased_serde::Serialize`][erased_serde::Serialize].
+pub struct Serializable<'a, DB, P>
+where
+    DB: ?Sized,
+    P: ?Sized,
+{
+    pub(super) db: &'a DB,
+    pub(super) value: &'a P,
+}
+
+impl<DB, P> Serialize for Serializable<'_, DB, P>
+where
+    DB: TypeDatabaseExt,
+    P: ?Sized + InnermostTypeId,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        self.value
+            .with_innermost_any(|data| {
+                data.dyn_cast::<dyn erased_serde::Serialize>(self.db)
+                    .ok()
+                    .map(|value| erased_serde::serialize(value, serializer))
+            })
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| {
+                Err(ser::Error::custom(format_args!(
+                    "concrete type of {} is not a registered implementor of erased_serde::Serialize",
+                    type_name::<P>(),
+                )))
+            })
+    }
+}
+
+impl<DB, P> fmt::Debug for Serializable<'_, DB, P>
+where
+    DB: ?Sized,
+    P: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Serializable")
+            .field("P", &type_name::<P>())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A slice of values that serializes as a sequence of their ultimate concrete
+/// instances, each of which must be registered in `db` as an implementor of
+/// [`dyn erased_serde::Serialize`][erased_serde::Serialize].
+pub struct SerializableSeq<'a, DB, T>
+where
+    DB: ?Sized,
+{
+    pub(super) db: &'a DB,
+    pub(super) values: &'a [T],
+}
+
+impl<DB, T> Serialize for SerializableSeq<'_, DB, T>
+where
+    DB: TypeDatabaseExt,
+    T: InnermostTypeId,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.values.len()))?;
+        for value in self.values {
+            seq.serialize_element(&Serializable { db: self.db, value })?;
+        }
+        seq.end()
+    }
+}
+
+impl<DB, T> fmt::Debug for SerializableSeq<'_, DB, T>
+where
+    DB: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SerializableSeq")
+            .field("T", &type_name::<T>())
+            .field("len", &self.values.len())
+            .finish_non_exhaustive()
+    }
+}
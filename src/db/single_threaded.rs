@@ -0,0 +1,38 @@
+//! Storage for the global database on targets that have only one thread.
+//!
+//! Without the `atomics` target feature, wasm32 (for example, in a browser)
+//! cannot spawn threads, so the global database needs none of the
+//! synchronization of a [`OnceLock`][std::sync::OnceLock].
+
+use core::cell::OnceCell;
+
+/// The cell in which the global [`DB`][super::hash_map::DB] is stored, which
+/// can be written only once.
+pub struct GlobalCell<T>(OnceCell<T>);
+
+// there are no other threads with which the cell could be shared
+unsafe impl<T> Sync for GlobalCell<T> {}
+
+impl<T> GlobalCell<T> {
+    /// An empty cell.
+    pub const fn new() -> Self {
+        Self(OnceCell::new())
+    }
+
+    /// The value of the cell, if it has been written.
+    pub fn get(&self) -> Option<&T> {
+        self.0.get()
+    }
+
+    /// Write `value` to the cell, unless it has already been written, in which
+    /// case `value` is returned.
+    pub fn set(&self, value: T) -> Result<(), T> {
+        self.0.set(value)
+    }
+}
+
+impl<T> Default for GlobalCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
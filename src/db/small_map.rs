@@ -0,0 +1,218 @@
+//! Maps keyed by [`TypeId`].
+//!
+//! A [`TypeId`] is itself already a hash of its type, so rehashing it with
+//! SipHash on every lookup is wasted effort: [`TypeIdMap`] instead just mixes
+//! it with a single multiplication.  Furthermore, most targets have only a
+//! handful of implementors, for which a linear scan is both smaller and faster
+//! than any hashing at all: [`SmallMap`] stores a few entries inline, and only
+//! spills to a [`TypeIdMap`] beyond that.
+//!
+//! With the `bloom-filter` feature, a spilled map is also accompanied by a
+//! [`Bloom`] filter of its keys, so that lookups which miss (as most do, when
+//! probing many values for targets that few of them implement) can usually be
+//! answered without touching the map.
+
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hasher},
+    mem,
+};
+
+#[cfg(feature = "bloom-filter")]
+use std::hash::Hash;
+
+/// The number of entries that are stored inline before spilling.
+const INLINE: usize = 4;
+
+/// A [`HashMap`] keyed by [`TypeId`], which uses [`TypeIdHasher`].
+pub(super) type TypeIdMap<V> = HashMap<TypeId, V, BuildHasherDefault<TypeIdHasher>>;
+
+/// A [`Hasher`] for [`TypeId`]s, which are already hashes and so need only be
+/// spread across the hash's bits.
+#[derive(Default)]
+pub(super) struct TypeIdHasher(u64);
+
+impl Hasher for TypeIdHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        // only reached if `TypeId` hashes other than as a single integer
+        for &byte in bytes {
+            self.write_u64(u64::from(byte));
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.0 = (self.0.rotate_left(5) ^ value).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A bloom filter of [`TypeId`]s, which may report false positives but never
+/// false negatives.
+#[cfg(feature = "bloom-filter")]
+#[derive(Default)]
+pub(super) struct Bloom([u64; 4]);
+
+#[cfg(feature = "bloom-filter")]
+impl Bloom {
+    /// The two bits, of 256, that represent `key`.
+    fn bits(key: &TypeId) -> [u8; 2] {
+        let mut hasher = TypeIdHasher::default();
+        key.hash(&mut hasher);
+        let hash = hasher.finish();
+        // the high bits of a multiplicative hash are the best mixed
+        [(hash >> 56) as u8, (hash >> 48) as u8]
+    }
+
+    /// Add `key` to the filter.
+    fn insert(&mut self, key: &TypeId) {
+        for bit in Self::bits(key) {
+            self.0[usize::from(bit / 64)] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Whether `key` may have been added to the filter.
+    fn may_contain(&self, key: &TypeId) -> bool {
+        Self::bits(key)
+            .iter()
+            .all(|&bit| self.0[usize::from(bit / 64)] & (1 << (bit % 64)) != 0)
+    }
+}
+
+/// A map from [`TypeId`] to `V`, stored inline until it exceeds [`INLINE`]
+/// entries.
+pub(super) enum SmallMap<V> {
+    Inline([Option<(TypeId, V)>; INLINE]),
+    Spilled(TypeIdMap<V>, #[cfg(feature = "bloom-filter")] Bloom),
+}
+
+impl<V> SmallMap<V> {
+    /// Insert `value` for `key`, returning any value that it replaced.
+    pub(super) fn insert(&mut self, key: TypeId, value: V) -> Option<V> {
+        let slots = match self {
+            Self::Inline(slots) => slots,
+            #[cfg(feature = "bloom-filter")]
+            Self::Spilled(map, bloom) => {
+                bloom.insert(&key);
+                return map.insert(key, value);
+            }
+            #[cfg(not(feature = "bloom-filter"))]
+            Self::Spilled(map) => return map.insert(key, value),
+        };
+
+        if let Some(existing) = slots.iter_mut().flatten().find(|(k, _)| *k == key) {
+            return Some(mem::replace(&mut existing.1, value));
+        }
+        if let Some(slot) = slots.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some((key, value));
+            return None;
+        }
+
+        let mut map = TypeIdMap::with_capacity_and_hasher(INLINE * 2, Default::default());
+        map.extend(slots.iter_mut().filter_map(Option::take));
+        map.insert(key, value);
+        *self = Self::spilled(map);
+        None
+    }
+
+    /// A spilled map of the entries in `map`.
+    fn spilled(map: TypeIdMap<V>) -> Self {
+        #[cfg(feature = "bloom-filter")]
+        {
+            let mut bloom = Bloom::default();
+            map.keys().for_each(|key| bloom.insert(key));
+            Self::Spilled(map, bloom)
+        }
+        #[cfg(not(feature = "bloom-filter"))]
+        Self::Spilled(map)
+    }
+
+    /// Whether there may be a value for `key`: always `true`, unless a bloom
+    /// filter rules it out.
+    #[cfg_attr(not(feature = "bloom-filter"), allow(unused_variables))]
+    fn may_contain(&self, key: &TypeId) -> bool {
+        match self {
+            #[cfg(feature = "bloom-filter")]
+            Self::Spilled(_, bloom) => bloom.may_contain(key),
+            _ => true,
+        }
+    }
+
+    /// Remove and return the value for `key`, if any.
+    pub(super) fn remove(&mut self, key: &TypeId) -> Option<V> {
+        match self {
+            Self::Inline(slots) => slots
+                .iter_mut()
+                .find(|slot| matches!(slot, Some((k, _)) if k == key))?
+                .take()
+                .map(|(_, value)| value),
+            Self::Spilled(map, ..) => map.remove(key),
+        }
+    }
+
+    /// The value for `key`, if any.
+    pub(super) fn get(&self, key: &TypeId) -> Option<&V> {
+        if !self.may_contain(key) {
+            return None;
+        }
+        match self {
+            Self::Inline(slots) => slots
+                .iter()
+                .flatten()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value),
+            Self::Spilled(map, ..) => map.get(key),
+        }
+    }
+
+    /// The value for `key`, if any, mutably.
+    #[cfg(any(feature = "names", feature = "debug-registry"))]
+    pub(super) fn get_mut(&mut self, key: &TypeId) -> Option<&mut V> {
+        if !self.may_contain(key) {
+            return None;
+        }
+        match self {
+            Self::Inline(slots) => slots
+                .iter_mut()
+                .flatten()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value),
+            Self::Spilled(map, ..) => map.get_mut(key),
+        }
+    }
+
+    /// Whether there is a value for `key`.
+    pub(super) fn contains_key(&self, key: &TypeId) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Iterate over every entry.
+    pub(super) fn iter(&self) -> impl Iterator<Item = (&TypeId, &V)> {
+        let (inline, spilled) = match self {
+            Self::Inline(slots) => (Some(slots.iter().flatten()), None),
+            Self::Spilled(map, ..) => (None, Some(map.iter())),
+        };
+        inline
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (key, value))
+            .chain(spilled.into_iter().flatten())
+    }
+
+    /// The number of bytes allocated on the heap, which is none until spilled.
+    pub(super) fn heap_footprint(&self) -> usize {
+        match self {
+            Self::Inline(_) => 0,
+            Self::Spilled(map, ..) => map.capacity() * (mem::size_of::<(TypeId, V)>() + 1),
+        }
+    }
+}
+
+impl<V> Default for SmallMap<V> {
+    fn default() -> Self {
+        Self::Inline([(); INLINE].map(|()| None))
+    }
+}
@@ -0,0 +1,57 @@
+//! Registrations of the standard library's types for its formatting traits.
+
+use super::{TypeDatabaseEntryExt, TypeDatabaseMut};
+use core::{fmt, time::Duration};
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::string::String;
+
+#[cfg(feature = "std")]
+use std::string::String;
+
+/// Register the standard library's primitive, string and other common types
+/// in `db` as implementors of each of the [`fmt`] traits that they implement.
+pub fn register_std_fmt<DB>(db: &mut DB)
+where
+    DB: TypeDatabaseMut,
+{
+    macro_rules! register {
+        ($( $trait:path: $( $ty:ty )+, )+) => {$({
+            let entry = db.get_entry_mut::<dyn $trait>();
+            $( entry.register::<$ty>(); )+
+        })+};
+    }
+
+    register! {
+        fmt::Debug:
+            () bool char
+            i8 i16 i32 i64 i128 isize
+            u8 u16 u32 u64 u128 usize
+            f32 f64
+            &'static str Duration,
+        fmt::Display:
+            bool char
+            i8 i16 i32 i64 i128 isize
+            u8 u16 u32 u64 u128 usize
+            f32 f64
+            &'static str,
+        fmt::LowerExp:
+            i8 i16 i32 i64 i128 isize
+            u8 u16 u32 u64 u128 usize
+            f32 f64,
+        fmt::UpperExp:
+            i8 i16 i32 i64 i128 isize
+            u8 u16 u32 u64 u128 usize
+            f32 f64,
+        fmt::LowerHex: i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize,
+        fmt::UpperHex: i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize,
+        fmt::Octal: i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize,
+        fmt::Binary: i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize,
+    }
+
+    #[cfg(feature = "alloc")]
+    register! {
+        fmt::Debug: String,
+        fmt::Display: String,
+    }
+}
@@ -0,0 +1,177 @@
+//! Streams that cast many pointers with a single entry lookup.
+//!
+//! As with the [iterators][super::iter], only the registrations in the looked
+//! up entry are consulted, and not any database fallbacks.
+
+use super::{error::CastError, iter::cast_with, TypeDatabaseEntry, TypeDatabaseExt};
+use crate::container::{Coerced, Coercible, InnermostTypeId, Metadata, Pointer};
+use core::{
+    any::type_name,
+    fmt,
+    marker::PhantomData,
+    pin::Pin,
+    ptr,
+    task::{Context, Poll},
+};
+use futures_core::Stream;
+
+/// A stream that casts each pointer yielded by `S` to `U`, using an entry `E`
+/// that was resolved only once.
+pub struct CastStream<'a, U, E, S>
+where
+    U: ?Sized,
+    E: ?Sized,
+{
+    entry: Option<&'a E>,
+    pointers: S,
+    target: PhantomData<fn(&U)>,
+}
+
+impl<U, E, S> CastStream<'_, U, E, S>
+where
+    U: ?Sized,
+    E: ?Sized,
+{
+    /// The pinned `pointers` stream, and the entry with which to cast its
+    /// items.
+    fn project(self: Pin<&mut Self>) -> (Pin<&mut S>, Option<&E>) {
+        // SAFETY: `pointers` is structurally pinned, and is never moved out of
+        // `self`
+        unsafe {
+            let this = self.get_unchecked_mut();
+            (Pin::new_unchecked(&mut this.pointers), this.entry)
+        }
+    }
+}
+
+impl<U, E, S> Stream for CastStream<'_, U, E, S>
+where
+    U: 'static + ?Sized,
+    E: TypeDatabaseEntry<U>,
+    S: Stream,
+    S::Item: Pointer + InnermostTypeId,
+    <S::Item as Coercible>::Coerced<U>: Sized,
+    <S::Item as Coercible>::Inner: Coercible,
+    Coerced<<S::Item as Coercible>::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+{
+    type Item = Result<<S::Item as Coercible>::Coerced<U>, CastError<U, S::Item>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let (pointers, entry) = self.project();
+        pointers
+            .poll_next(cx)
+            .map(|pointer| Some(cast_with(entry, pointer?)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.pointers.size_hint()
+    }
+}
+
+impl<U, E, S> fmt::Debug for CastStream<'_, U, E, S>
+where
+    U: ?Sized,
+    E: ?Sized + fmt::Debug,
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CastStream<{}>", type_name::<U>())?;
+        f.debug_struct("")
+            .field("entry", &self.entry)
+            .field("pointers", &self.pointers)
+            .finish()
+    }
+}
+
+/// A stream that casts each pointer yielded by `S` to `U`, using an entry `E`
+/// that was resolved only once, and yields only those casts that succeed.
+pub struct CastFilterStream<'a, U, E, S>(CastStream<'a, U, E, S>)
+where
+    U: ?Sized,
+    E: ?Sized;
+
+impl<U, E, S> Stream for CastFilterStream<'_, U, E, S>
+where
+    U: 'static + ?Sized,
+    E: TypeDatabaseEntry<U>,
+    S: Stream,
+    S::Item: Pointer + InnermostTypeId,
+    <S::Item as Coercible>::Coerced<U>: Sized,
+    <S::Item as Coercible>::Inner: Coercible,
+    Coerced<<S::Item as Coercible>::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+{
+    type Item = <S::Item as Coercible>::Coerced<U>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // SAFETY: the inner stream is structurally pinned, and is never moved
+        // out of `self`
+        let mut inner = unsafe { self.map_unchecked_mut(|this| &mut this.0) };
+        loop {
+            match inner.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Err(_))) => continue,
+                Poll::Ready(Some(Ok(casted))) => return Poll::Ready(Some(casted)),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.0.size_hint().1)
+    }
+}
+
+impl<U, E, S> fmt::Debug for CastFilterStream<'_, U, E, S>
+where
+    U: ?Sized,
+    E: ?Sized + fmt::Debug,
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CastFilterStream<{}>", type_name::<U>())?;
+        f.debug_struct("")
+            .field("entry", &self.0.entry)
+            .field("pointers", &self.0.pointers)
+            .finish()
+    }
+}
+
+/// Adapters that cast the items of a stream, looking up the target in the
+/// database only once.
+pub trait DynStream<DB>
+where
+    Self: Stream + Sized,
+    DB: TypeDatabaseExt,
+{
+    /// Cast each item to `U` with `db`'s entry for `U` (without database
+    /// fallbacks), yielding the result of each cast in turn.
+    fn cast_items<U>(self, db: &DB) -> CastStream<'_, U, DB::Entry<U>, Self>
+    where
+        U: 'static + ?Sized,
+        Self::Item: Pointer + InnermostTypeId,
+    {
+        CastStream {
+            entry: db.get_entry(),
+            pointers: self,
+            target: PhantomData,
+        }
+    }
+
+    /// Cast each item to `U`, yielding only those that are registered as
+    /// implementors of `U` in `db`'s entry for `U` (without database
+    /// fallbacks).
+    fn filter_cast_items<U>(self, db: &DB) -> CastFilterStream<'_, U, DB::Entry<U>, Self>
+    where
+        U: 'static + ?Sized,
+        Self::Item: Pointer + InnermostTypeId,
+    {
+        CastFilterStream(self.cast_items(db))
+    }
+}
+
+impl<DB, S> DynStream<DB> for S
+where
+    S: Stream,
+    DB: TypeDatabaseExt,
+{
+}
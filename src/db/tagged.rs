@@ -0,0 +1,192 @@
+//! Polymorphic serialization of registered implementors, tagged with a stable
+//! name for their concrete type.
+//!
+//! Values are represented externally tagged, that is as a single-entry map
+//! from the name with which their concrete type was registered to the
+//! serialization of the concrete value itself: for example, `{"Circle": 1.5}`.
+//! Internally tagged representations, in which the name is a field of the
+//! value's own serialization (for example, `{"type": "Circle", "radius": 1.5}`),
+//! are not supported.
+
+use super::TypeDatabaseEntry;
+use serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, MapAccess, Visitor},
+    ser::{self, SerializeMap},
+};
+use std::{
+    any::type_name,
+    borrow::Cow,
+    fmt,
+    marker::{PhantomData, Unsize},
+};
+
+/// A function that views the instance of a registered concrete type to which
+/// its argument points as [`erased_serde::Serialize`].
+///
+/// # Safety
+/// The argument must point to an instance of the concrete type for which the
+/// function was registered.
+pub type AsSerialize = unsafe fn(*const ()) -> *const dyn erased_serde::Serialize;
+
+/// A function that deserializes an instance of a registered concrete type into
+/// a new [`Box<U>`].
+pub type Deserialize<U> =
+    fn(&mut dyn erased_serde::Deserializer<'_>) -> Result<Box<U>, erased_serde::Error>;
+
+/// The stable name and serialization functions with which a concrete type was
+/// registered as an implementor of `U`.
+pub struct Tag<U>
+where
+    U: ?Sized,
+{
+    /// The name that identifies the concrete type in serialized data.
+    pub name: &'static str,
+    /// Views instances of the concrete type as serializable.
+    pub serialize: AsSerialize,
+    /// Deserializes instances of the concrete type.
+    pub deserialize: Deserialize<U>,
+}
+
+impl<U> Tag<U>
+where
+    U: ?Sized,
+{
+    /// The tag for concrete type `I`, identified by `name`.
+    pub fn of<I>(name: &'static str) -> Self
+    where
+        I: 'static + erased_serde::Serialize + DeserializeOwned + Unsize<U>,
+    {
+        Self {
+            name,
+            serialize: as_serialize::<I>,
+            deserialize: deserialize_boxed::<U, I>,
+        }
+    }
+}
+
+impl<U> Clone for Tag<U>
+where
+    U: ?Sized,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<U> Copy for Tag<U> where U: ?Sized {}
+
+impl<U> fmt::Debug for Tag<U>
+where
+    U: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Tag").field(&self.name).finish()
+    }
+}
+
+/// An [`AsSerialize`] for concrete type `I`.
+unsafe fn as_serialize<I>(data: *const ()) -> *const dyn erased_serde::Serialize
+where
+    I: 'static + erased_serde::Serialize,
+{
+    data.cast::<I>()
+}
+
+/// A [`Deserialize`] for concrete type `I`.
+fn deserialize_boxed<U, I>(
+    deserializer: &mut dyn erased_serde::Deserializer<'_>,
+) -> Result<Box<U>, erased_serde::Error>
+where
+    U: ?Sized,
+    I: DeserializeOwned + Unsize<U>,
+{
+    let value: Box<I> = Box::new(erased_serde::deserialize(deserializer)?);
+    Ok(value)
+}
+
+/// Serialize the concrete value at `data` as tagged by `tag`.
+///
+/// # Safety
+/// `data` must point to an instance of the concrete type for which `tag` was
+/// registered.
+pub(super) unsafe fn serialize<U, S>(
+    tag: Tag<U>,
+    data: *const (),
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    U: ?Sized,
+    S: ser::Serializer,
+{
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry(tag.name, &*(tag.serialize)(data))?;
+    map.end()
+}
+
+/// Deserializes an externally tagged implementor of `U` whose concrete type is
+/// registered in `entry`.
+pub(super) struct TaggedVisitor<'a, U, E>
+where
+    U: ?Sized,
+    E: ?Sized,
+{
+    pub(super) entry: Option<&'a E>,
+    pub(super) target: PhantomData<fn() -> Box<U>>,
+}
+
+impl<'a, 'de, U, E> Visitor<'de> for TaggedVisitor<'a, U, E>
+where
+    U: 'static + ?Sized,
+    E: ?Sized + TypeDatabaseEntry<U>,
+{
+    type Value = Box<U>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a tagged implementor of {}", type_name::<U>())
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let name = map
+            .next_key::<Cow<'de, str>>()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let tag = self
+            .entry
+            .and_then(|entry| entry.tag_named(&name))
+            .ok_or_else(|| {
+                de::Error::custom(format_args!(
+                    "unknown implementor {:?} of {}",
+                    name,
+                    type_name::<U>(),
+                ))
+            })?;
+        let value = map.next_value_seed(TaggedSeed(tag))?;
+        if map.next_key::<de::IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(2, &self));
+        }
+        Ok(value)
+    }
+}
+
+/// Deserializes the untagged value of the concrete type for which the given
+/// [`Tag`] was registered.
+struct TaggedSeed<U>(Tag<U>)
+where
+    U: ?Sized;
+
+impl<'de, U> DeserializeSeed<'de> for TaggedSeed<U>
+where
+    U: ?Sized,
+{
+    type Value = Box<U>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let mut deserializer = <dyn erased_serde::Deserializer<'_>>::erase(deserializer);
+        (self.0.deserialize)(&mut deserializer).map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,167 @@
+//! Lists of targets, against which implementation can be checked together.
+
+use super::{
+    address_of,
+    error::{DatabaseEntryError, MultiCastError},
+    resolve_in, TypeDatabase,
+};
+use crate::container::{InnermostTypeId, TypeIdDeterminationError};
+use core::{
+    any::{Any, TypeId},
+    ptr,
+};
+
+/// A list of targets, expressed as a tuple of references to each target type:
+/// for example, `(&dyn Bar, &dyn Baz, &dyn Debug)`.
+///
+/// Implemented for tuples of up to twelve targets.
+pub trait Targets
+where
+    Self: 'static,
+{
+    /// Whether `data`, whose concrete type has the given `type_id`, is known to
+    /// `db` to implement every target in this list.
+    fn implemented_by_all<DB, P>(
+        db: &DB,
+        type_id: TypeId,
+        data: &P,
+    ) -> Result<bool, TypeIdDeterminationError>
+    where
+        DB: ?Sized + TypeDatabase,
+        P: ?Sized + InnermostTypeId;
+
+    /// Whether `data`, whose concrete type has the given `type_id`, is known to
+    /// `db` to implement any target in this list.
+    fn implemented_by_any<DB, P>(
+        db: &DB,
+        type_id: TypeId,
+        data: &P,
+    ) -> Result<bool, TypeIdDeterminationError>
+    where
+        DB: ?Sized + TypeDatabase,
+        P: ?Sized + InnermostTypeId;
+
+    /// A tuple of references, with lifetime `'a`, to each target in this list.
+    ///
+    /// Note that this is a generic associated type, parameters of which are not
+    /// presently rendered by Rustdoc.  Its full declaration is:
+    ///
+    /// ```ignore
+    /// type Views<'a>
+    /// ```
+    type Views<'a>;
+
+    /// Views of `data`, an instance of `P`, as every target in this list if its
+    /// concrete type is known to `db` to implement them all; otherwise the
+    /// error that arose on the first target that it does not.
+    fn view_all<'a, DB, P>(
+        db: &DB,
+        data: &'a dyn Any,
+    ) -> Result<Self::Views<'a>, MultiCastError<Self, P>>
+    where
+        DB: ?Sized + TypeDatabase,
+        P: ?Sized;
+
+    /// Call `f` with the [`TypeId`] of each target in this list, in order.
+    fn for_each_type_id(f: &mut dyn FnMut(TypeId));
+}
+
+/// Whether `data`, whose concrete type has the given `type_id`, is known to
+/// `db` to implement `U`.
+fn implemented_by<U, DB, P>(
+    db: &DB,
+    type_id: TypeId,
+    data: &P,
+) -> Result<bool, TypeIdDeterminationError>
+where
+    U: 'static + ?Sized,
+    DB: ?Sized + TypeDatabase,
+    P: ?Sized + InnermostTypeId,
+{
+    match resolve_in::<U, _, _>(db, type_id, data) {
+        Ok(_) => Ok(true),
+        Err(DatabaseEntryError::ConcreteTypeDeterminationFailure { reason, .. }) => Err(reason),
+        Err(_) => Ok(false),
+    }
+}
+
+/// A view of `data`, an instance of `P`, as `U` if its concrete type is known
+/// to `db` to implement `U`.
+fn view<'a, U, T, DB, P>(db: &DB, data: &'a dyn Any) -> Result<&'a U, MultiCastError<T, P>>
+where
+    U: 'static + ?Sized,
+    T: 'static + ?Sized,
+    DB: ?Sized + TypeDatabase,
+    P: ?Sized,
+{
+    let metadata = resolve_in::<U, _, _>(db, Any::type_id(data), data)
+        .map_err(|error| MultiCastError::new(error.with_instance_type::<P>()))?;
+    Ok(unsafe { &*ptr::from_raw_parts(address_of(data), metadata) })
+}
+
+macro_rules! targets {
+    ($( ($( $u:ident ),+) )+) => {$(
+        impl<$( $u, )+> Targets for ($( &'static $u, )+)
+        where
+            $( $u: 'static + ?Sized, )+
+        {
+            #[allow(clippy::needless_question_mark)]
+            fn implemented_by_all<DB, P>(
+                db: &DB,
+                type_id: TypeId,
+                data: &P,
+            ) -> Result<bool, TypeIdDeterminationError>
+            where
+                DB: ?Sized + TypeDatabase,
+                P: ?Sized + InnermostTypeId,
+            {
+                Ok($( implemented_by::<$u, _, _>(db, type_id, data)? )&&+)
+            }
+
+            #[allow(clippy::needless_question_mark)]
+            fn implemented_by_any<DB, P>(
+                db: &DB,
+                type_id: TypeId,
+                data: &P,
+            ) -> Result<bool, TypeIdDeterminationError>
+            where
+                DB: ?Sized + TypeDatabase,
+                P: ?Sized + InnermostTypeId,
+            {
+                Ok($( implemented_by::<$u, _, _>(db, type_id, data)? )||+)
+            }
+
+            type Views<'a> = ($( &'a $u, )+);
+
+            fn view_all<'a, DB, P>(
+                db: &DB,
+                data: &'a dyn Any,
+            ) -> Result<Self::Views<'a>, MultiCastError<Self, P>>
+            where
+                DB: ?Sized + TypeDatabase,
+                P: ?Sized,
+            {
+                Ok(($( view::<$u, Self, _, _>(db, data)?, )+))
+            }
+
+            fn for_each_type_id(f: &mut dyn FnMut(TypeId)) {
+                $( f(TypeId::of::<$u>()); )+
+            }
+        }
+    )+};
+}
+
+targets! {
+    (U1)
+    (U1, U2)
+    (U1, U2, U3)
+    (U1, U2, U3, U4)
+    (U1, U2, U3, U4, U5)
+    (U1, U2, U3, U4, U5, U6)
+    (U1, U2, U3, U4, U5, U6, U7)
+    (U1, U2, U3, U4, U5, U6, U7, U8)
+    (U1, U2, U3, U4, U5, U6, U7, U8, U9)
+    (U1, U2, U3, U4, U5, U6, U7, U8, U9, U10)
+    (U1, U2, U3, U4, U5, U6, U7, U8, U9, U10, U11)
+    (U1, U2, U3, U4, U5, U6, U7, U8, U9, U10, U11, U12)
+}
@@ -2,19 +2,40 @@
 
 use super::{
     error::{
-        CastError,
+        CastError, ChainedCastError,
         DatabaseEntryError::{
             ConcreteTypeDeterminationFailure, ConcreteTypeNotRegisteredForTarget,
+            PredicateNotSatisfied,
         },
         DatabaseError::RequestedTypeNotInDatabase,
+        ErrorKind, UpgradeCastError,
     },
     hash_map::HashMapTypeDatabase,
-    TypeDatabaseEntryExt, TypeDatabaseExt,
+    iter::DynIterator,
+    observer::Observer,
+    raw::ErasedRef,
+    token::StaticRegistration,
+    Detail, TypeDatabaseEntry, TypeDatabaseEntryExt, TypeDatabaseExt, TypeDatabaseMut,
 };
-use crate::rtti;
-use std::{any::Any, lazy::SyncLazy, rc};
+use crate::{
+    rtti, AndThenCast, DynCast, DynCastFrom, DynCastMut, DynCastRef, DynCastUpgraded, DynImplements,
+};
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    fmt, ptr, rc,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+#[cfg(not(feature = "compact-metadata"))]
+use super::{hash_map::HashMapTypeDatabaseEntry, Metadata};
+#[cfg(not(feature = "compact-metadata"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-static DB: SyncLazy<HashMapTypeDatabase> = SyncLazy::new(|| {
+static DB: LazyLock<HashMapTypeDatabase> = LazyLock::new(|| {
     rtti! {
         PartialEq<i32>: i32,
         PartialEq<f32>: f32,
@@ -56,6 +77,345 @@ fn targets_cannot_determine_implementation_of_dangling_weak_rc() {
     ));
 }
 
+#[test]
+fn targets_implement_all_or_any_of_registered_types() {
+    let data = &12345i32 as &dyn Any;
+    assert!(data
+        .dyn_implements_all::<(&dyn PartialEq<i32>,)>(&*DB)
+        .unwrap());
+    assert!(!data
+        .dyn_implements_all::<(&dyn PartialEq<i32>, &dyn PartialEq<f32>)>(&*DB)
+        .unwrap());
+    assert!(data
+        .dyn_implements_any::<(&dyn PartialEq<f32>, &dyn PartialEq<i32>)>(&*DB)
+        .unwrap());
+    assert!(!data
+        .dyn_implements_any::<(&dyn PartialEq<f32>, &dyn fmt::Debug)>(&*DB)
+        .unwrap());
+}
+
+#[test]
+fn several_targets_are_viewed_together() {
+    let db = rtti! {
+        Base: i32,
+        PartialEq<i32>: i32,
+    };
+
+    let boxed: Box<dyn Any> = Box::new(12345i32);
+    let (base, eq) = boxed
+        .dyn_cast_multi::<(&dyn Base, &dyn PartialEq<i32>)>(&db)
+        .unwrap();
+    assert_eq!(base.base(), 12345);
+    assert!(eq.eq(&12345));
+
+    let error = boxed
+        .dyn_cast_multi::<(&dyn Base, &dyn PartialEq<f32>)>(&db)
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), ErrorKind::TargetNotRegistered);
+    assert_eq!(error.target, TypeId::of::<dyn PartialEq<f32>>());
+}
+
+#[test]
+fn several_targets_report_the_first_that_fails() {
+    let mut db = rtti! {
+        Base: i32,
+    };
+    db.get_entry_mut::<dyn fmt::Debug>()
+        .register_if::<i32>(|_| false);
+    db.get_entry_mut::<dyn fmt::Display>().register::<u8>();
+
+    let boxed: Box<dyn Any> = Box::new(12345i32);
+
+    let error = boxed
+        .dyn_cast_multi::<(&dyn Base, &dyn fmt::Debug, &dyn fmt::Display)>(&db)
+        .err()
+        .unwrap();
+    assert!(matches!(error.source, PredicateNotSatisfied { .. }));
+    assert_eq!(error.target, TypeId::of::<dyn fmt::Debug>());
+    assert_eq!(error.concrete_type_id(), Some(TypeId::of::<i32>()));
+
+    let error = boxed
+        .dyn_cast_multi::<(&dyn Base, &dyn fmt::Display)>(&db)
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.source,
+        ConcreteTypeNotRegisteredForTarget { .. }
+    ));
+    assert_eq!(error.target, TypeId::of::<dyn fmt::Display>());
+    assert!(error
+        .to_string()
+        .contains(std::any::type_name::<dyn fmt::Display>()));
+}
+
+#[test]
+fn heterogeneous_collections_are_scanned_for_implementors() {
+    let values = || -> Vec<Box<dyn Any>> { vec![Box::new(1i32), Box::new(2f32), Box::new(3i32)] };
+
+    assert_eq!(
+        values()
+            .into_iter()
+            .filter_implements::<dyn PartialEq<i32>>(&*DB)
+            .count(),
+        2
+    );
+    assert_eq!(
+        values()
+            .into_iter()
+            .cast_filter_map::<dyn PartialEq<f32>>(&*DB)
+            .filter(|value| value.eq(&2.0))
+            .count(),
+        1
+    );
+
+    let (implementors, rest) = values()
+        .into_iter()
+        .dyn_partition::<dyn PartialEq<i32>>(&*DB);
+    assert!(implementors[0].eq(&1) && implementors[1].eq(&3));
+    assert!(rest.len() == 1 && rest[0].is::<f32>());
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn stream_items_are_casted() {
+    use super::stream::DynStream;
+    use futures_util::{stream, StreamExt};
+
+    let values = || -> Vec<Box<dyn Any>> { vec![Box::new(1i32), Box::new(2f32), Box::new(3i32)] };
+
+    let results: Vec<_> = futures_executor::block_on(
+        stream::iter(values())
+            .cast_items::<dyn PartialEq<i32>>(&*DB)
+            .map(|result| result.is_ok())
+            .collect(),
+    );
+    assert_eq!(results, [true, false, true]);
+
+    let casted: Vec<_> = futures_executor::block_on(
+        stream::iter(values())
+            .filter_cast_items::<dyn PartialEq<f32>>(&*DB)
+            .collect(),
+    );
+    assert!(casted.len() == 1 && casted[0].eq(&2.0));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_items_are_casted() {
+    use super::parallel::DynParallelIterator;
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+    trait Message: Any + Send + Sync {}
+    crate::coercible_trait!(Message);
+    impl Message for i32 {}
+    impl Message for f32 {}
+
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn PartialEq<i32> + Send + Sync>()
+        .register::<i32>();
+
+    let values = || -> Vec<Box<dyn Message>> {
+        (0..100)
+            .map(|i| -> Box<dyn Message> {
+                if i % 2 == 0 {
+                    Box::new(i)
+                } else {
+                    Box::new(i as f32)
+                }
+            })
+            .collect()
+    };
+
+    let casted = values()
+        .into_par_iter()
+        .par_cast_all::<dyn PartialEq<i32> + Send + Sync>(&db)
+        .filter(Result::is_ok)
+        .count();
+    assert_eq!(casted, 50);
+
+    let implementors = values()
+        .into_par_iter()
+        .par_filter_implements::<dyn PartialEq<i32> + Send + Sync>(&db)
+        .count();
+    assert_eq!(implementors, 50);
+}
+
+#[test]
+fn non_any_trait_objects_are_casted_by_vtable() {
+    use super::vtable::VtableTypeDatabase;
+
+    trait Shape {
+        fn area(&self) -> f64;
+    }
+    #[derive(Debug)]
+    struct Square(f64);
+    impl Shape for Square {
+        fn area(&self) -> f64 {
+            self.0 * self.0
+        }
+    }
+    struct Circle;
+    impl Shape for Circle {
+        fn area(&self) -> f64 {
+            0.0
+        }
+    }
+
+    let square: Box<dyn Shape> = Box::new(Square(2.0));
+
+    // vtables are not unique across codegen units, so register the metadata of
+    // the very pointers that will be cast
+    let mut db = VtableTypeDatabase::default();
+    unsafe {
+        db.get_entry_mut::<dyn Shape, dyn fmt::Debug>().add(
+            ptr::metadata(&*square),
+            ptr::metadata(&Square(0.0) as &dyn fmt::Debug),
+        );
+    }
+
+    assert_eq!(square.area(), 4.0);
+    let debug = db.cast_ref::<_, dyn fmt::Debug>(&*square).unwrap();
+    assert_eq!(format!("{:?}", debug), "Square(2.0)");
+    assert!(db.cast_box::<_, dyn fmt::Debug>(square).is_ok());
+
+    let circle: Box<dyn Shape> = Box::new(Circle);
+    assert_eq!(circle.area(), 0.0);
+    assert!(db.cast_ref::<_, dyn fmt::Debug>(&*circle).is_none());
+}
+
+#[test]
+fn unnameable_types_are_registered_by_example() {
+    fn opaque() -> impl Specific {
+        10_i32
+    }
+
+    let mut db = HashMapTypeDatabase::default();
+    let example = opaque();
+    let type_id = db
+        .get_entry_mut::<dyn Specific>()
+        .register_example(&example)
+        .unwrap();
+    assert_eq!(type_id, TypeId::of::<i32>());
+
+    let any: &dyn Any = &20_i32;
+    assert!(any.dyn_cast::<dyn Specific>(&db).is_ok());
+}
+
+#[test]
+fn missing_entries_do_not_implement() {
+    let db = HashMapTypeDatabase::default();
+    let any: &dyn Any = &12345;
+    assert!(matches!(
+        any.dyn_implements::<dyn fmt::Debug>(&db),
+        Err(super::error::DatabaseEntryError::DatabaseError { .. })
+    ));
+    assert!(!any.dyn_implements_or_false::<dyn fmt::Debug>(&db).unwrap());
+}
+
+#[test]
+fn cast_errors_are_boxed() {
+    fn cast(db: &HashMapTypeDatabase) -> Result<(), Box<dyn std::error::Error>> {
+        let boxed: Box<dyn Any> = Box::new(12345);
+        boxed.dyn_cast::<dyn fmt::Debug>(db)?;
+        Ok(())
+    }
+
+    let error = cast(&HashMapTypeDatabase::default()).unwrap_err();
+    let expected = "requested type <dyn core::fmt::Debug> not registered in database";
+    #[cfg(not(feature = "debug-casts"))]
+    assert_eq!(error.to_string(), expected);
+    #[cfg(feature = "debug-casts")]
+    assert!(error.to_string().starts_with(expected));
+}
+
+#[cfg(feature = "debug-casts")]
+#[test]
+fn cast_errors_record_caller_location() {
+    let any: &dyn Any = &12345;
+    let error = any
+        .dyn_cast::<dyn fmt::Debug>(&HashMapTypeDatabase::default())
+        .err()
+        .unwrap();
+    let line = line!() - 3;
+
+    assert_eq!(error.location().file(), file!());
+    assert_eq!(error.location().line(), line);
+    assert!(error
+        .to_string()
+        .ends_with(&format!("(cast at {})", error.location())));
+}
+
+// wasm32 cannot spawn threads
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn owned_cast_errors_cross_threads() {
+    let rc: rc::Rc<dyn Any> = rc::Rc::new(12345);
+    let error = rc
+        .dyn_cast::<dyn PartialEq<f32>>(&*DB)
+        .err()
+        .unwrap()
+        .into_owned();
+
+    let error = std::thread::spawn(move || error).join().unwrap();
+    assert_eq!(error.kind, ErrorKind::TypeNotRegisteredForTarget);
+    assert_eq!(error.concrete_type_id, Some(TypeId::of::<i32>()));
+    assert!(error.to_string().starts_with(
+        "unable to cast <alloc::rc::Rc<dyn core::any::Any>> to <dyn core::cmp::PartialEq<f32>>: "
+    ));
+}
+
+#[cfg(feature = "anyhow")]
+#[test]
+fn cast_errors_convert_into_anyhow() {
+    let any: &dyn Any = &12345;
+    let error = any
+        .dyn_cast::<dyn fmt::Debug>(&HashMapTypeDatabase::default())
+        .map_err(CastError::into_anyhow)
+        .unwrap_err();
+    assert_eq!(
+        format!("{:#}", error),
+        "unable to cast <&dyn core::any::Any> to <dyn core::fmt::Debug>: \
+         requested type <dyn core::fmt::Debug> not registered in database",
+    );
+}
+
+#[test]
+fn error_kinds_are_independent_of_type_parameters() {
+    let db = HashMapTypeDatabase::default();
+    let any: &dyn Any = &12345;
+    let error = any.dyn_cast::<dyn fmt::Debug>(&db).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::TargetNotRegistered);
+
+    let db = rtti! {
+        fmt::Debug: f32,
+    };
+    let error = any.dyn_cast::<dyn fmt::Debug>(&db).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::TypeNotRegisteredForTarget);
+}
+
+#[test]
+fn failed_casts_are_inspected_and_retried() {
+    let boxed: Box<dyn Any> = Box::new(12345);
+    let mut db = rtti! {
+        fmt::Debug: f32,
+    };
+    let error = boxed.dyn_cast::<dyn fmt::Display>(&db).err().unwrap();
+    assert_eq!(error.concrete_type_id(), None);
+    assert_eq!(error.target_type_name(), "dyn core::fmt::Display");
+
+    let error = error
+        .into_pointer()
+        .dyn_cast::<dyn fmt::Debug>(&db)
+        .unwrap_err()
+        .map_pointer(rc::Rc::<dyn Any>::from);
+    assert_eq!(error.concrete_type_id(), Some(TypeId::of::<i32>()));
+
+    db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+    let debug = error.retry_with(&db).unwrap();
+    assert_eq!(format!("{:?}", debug), "12345");
+}
+
 #[test]
 fn registered_type_is_casted() {
     let target = DB.get_db_entry::<dyn PartialEq<i32>>().unwrap();
@@ -92,3 +452,1616 @@ fn cannot_cast_dangling_weak_rc() {
         })
     ));
 }
+
+#[test]
+fn optional_cast_is_some_only_if_registered() {
+    let boxed: Box<dyn Any> = Box::new(12345i32);
+    assert!(boxed
+        .dyn_cast_opt::<dyn PartialEq<i32>>(&*DB)
+        .unwrap()
+        .eq(&12345));
+
+    let boxed: Box<dyn Any> = Box::new(12345i32);
+    assert!(boxed.dyn_cast_opt::<dyn PartialEq<f32>>(&*DB).is_none());
+}
+
+#[test]
+fn failed_cast_falls_back() {
+    let boxed: Box<dyn Any> = Box::new(12345i32);
+    let fallback: Box<dyn PartialEq<f32>> = Box::new(1.5f32);
+    assert!(boxed
+        .dyn_cast_or::<dyn PartialEq<f32>>(&*DB, fallback)
+        .eq(&1.5));
+
+    let boxed: Box<dyn Any> = Box::new(12345i32);
+    let recovered = boxed.dyn_cast_or_else::<dyn PartialEq<f32>, _>(&*DB, |boxed| {
+        Box::new(*boxed.downcast::<i32>().unwrap() as f32)
+    });
+    assert!(recovered.eq(&12345.0));
+}
+
+#[test]
+fn first_registered_target_is_casted() {
+    fn describe(boxed: Box<dyn Any>) -> String {
+        crate::dyn_cast_first!(boxed, &*DB;
+            dyn PartialEq<f32> => |_| "f32".into(),
+            dyn PartialEq<i32> => |_| "i32".into(),
+            else |boxed| format!("{:?}", (*boxed).type_id() == TypeId::of::<u8>()),
+        )
+    }
+
+    assert_eq!(describe(Box::new(1i32)), "i32");
+    assert_eq!(describe(Box::new(1f32)), "f32");
+    assert_eq!(describe(Box::new(1u8)), "true");
+}
+
+#[test]
+fn registered_type_is_casted_with_resolved_entry() {
+    let target = DB.get_db_entry::<dyn PartialEq<i32>>().unwrap();
+    let any: &dyn Any = &12345i32;
+    let casted = DynCast::<HashMapTypeDatabase>::dyn_cast_with(any, target).unwrap();
+    assert!(casted.eq(&12345));
+}
+
+#[test]
+fn verified_type_is_casted_unchecked() {
+    let any: &dyn Any = &12345i32;
+    assert!(any.dyn_implements::<dyn PartialEq<i32>>(&*DB).unwrap());
+    let casted = unsafe { any.dyn_cast_unchecked::<dyn PartialEq<i32>>(&*DB) };
+    assert!(casted.eq(&12345));
+}
+
+#[test]
+fn pointers_are_casted_in_bulk() {
+    let boxes: Vec<Box<dyn Any>> = vec![Box::new(1i32), Box::new(2f32), Box::new(3i32)];
+    let casted: Vec<_> = DynCast::dyn_cast_iter::<dyn PartialEq<i32>, _>(boxes, &*DB)
+        .map(|result| result.map(|eq| eq.eq(&3)).ok())
+        .collect();
+    assert_eq!(casted, [Some(false), None, Some(true)]);
+
+    let target = DB.get_db_entry::<dyn PartialEq<f32>>().unwrap();
+    let casted = target.cast_all([&1i32 as &dyn Any, &2f32]);
+    assert_eq!(casted.filter(Result::is_ok).count(), 1);
+}
+
+#[test]
+fn registered_type_is_casted_by_reference() {
+    let rc: rc::Rc<dyn Any> = rc::Rc::new(12345i32);
+    let casted = rc.dyn_cast_ref::<dyn PartialEq<i32>>(&*DB).unwrap();
+    assert!(casted.eq(&12345));
+
+    let casted = rc.dyn_cast_ref::<dyn PartialEq<f32>>(&*DB);
+    assert!(matches!(
+        casted,
+        Err(CastError {
+            source: ConcreteTypeNotRegisteredForTarget { .. },
+            ..
+        })
+    ));
+    assert_eq!(rc::Rc::strong_count(&rc), 1);
+}
+
+#[test]
+fn registered_interface_is_queried() {
+    let any: &dyn Any = &12345i32;
+    assert!(any
+        .dyn_query::<dyn PartialEq<i32>>(&*DB)
+        .unwrap()
+        .eq(&12345));
+    assert!(any.dyn_query::<dyn PartialEq<f32>>(&*DB).is_none());
+}
+
+#[test]
+fn registered_type_is_casted_by_exclusive_reference() {
+    let db = rtti! {
+        fmt::Write: String,
+    };
+
+    let mut boxed: Box<dyn Any> = Box::new(String::new());
+    fmt::Write::write_str(boxed.dyn_cast_mut::<dyn fmt::Write>(&db).unwrap(), "hello").unwrap();
+    assert_eq!(boxed.downcast_ref::<String>().unwrap(), "hello");
+}
+
+#[test]
+fn concrete_type_is_downcasted_through_wrappers() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<i32>().register_concrete();
+
+    let any: rc::Rc<RefCell<dyn Any>> = rc::Rc::new(RefCell::new(12345i32));
+    let concrete: rc::Rc<RefCell<i32>> = any.dyn_cast(&db).unwrap();
+    assert_eq!(*concrete.borrow(), 12345);
+
+    let any: rc::Rc<RefCell<dyn Any>> = rc::Rc::new(RefCell::new(12345f32));
+    assert!(any.dyn_cast::<i32>(&db).is_err());
+}
+
+trait Base {
+    fn base(&self) -> i32;
+}
+
+trait Specific: Base + Any {}
+crate::coercible_trait!(Specific);
+
+impl Base for i32 {
+    fn base(&self) -> i32 {
+        *self
+    }
+}
+
+impl Specific for i32 {}
+
+#[test]
+fn trait_object_is_upcasted_to_supertrait() {
+    let db = rtti! {
+        Base: i32,
+    };
+
+    let specific: rc::Rc<dyn Specific> = rc::Rc::new(12345i32);
+    let base: rc::Rc<dyn Base> = specific.dyn_upcast(&db).unwrap();
+    assert_eq!(base.base(), 12345);
+}
+
+#[test]
+fn subtrait_registrations_are_found_for_supertrait() {
+    let mut db = rtti! {
+        Specific: i32,
+    };
+    db.declare_subtrait::<dyn Specific, dyn Base>();
+
+    let any: rc::Rc<dyn Any> = rc::Rc::new(12345i32);
+    assert!(any.dyn_implements::<dyn Base>(&db).unwrap());
+    let base: rc::Rc<dyn Base> = any.dyn_cast(&db).unwrap();
+    assert_eq!(base.base(), 12345);
+
+    let any: rc::Rc<dyn Any> = rc::Rc::new(12345f32);
+    assert!(!matches!(any.dyn_implements::<dyn Base>(&db), Ok(true)));
+
+    let any: &dyn Any = &12345i32;
+    assert!(db
+        .traits_of(any)
+        .unwrap()
+        .any(|target| target == TypeId::of::<dyn Base>()));
+}
+
+#[test]
+fn subtrait_predicates_apply_to_supertrait() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn Specific>()
+        .register_if::<i32>(|data| data.downcast_ref::<i32>() == Some(&1));
+    db.declare_subtrait::<dyn Specific, dyn Base>();
+
+    let any: rc::Rc<dyn Any> = rc::Rc::new(1i32);
+    assert!(any.dyn_implements::<dyn Base>(&db).unwrap());
+    assert!(any.dyn_cast::<dyn Base>(&db).is_ok());
+
+    let any: rc::Rc<dyn Any> = rc::Rc::new(2i32);
+    assert!(!any.dyn_implements::<dyn Base>(&db).unwrap());
+    assert_eq!(
+        any.dyn_cast::<dyn Base>(&db).err().unwrap().kind(),
+        ErrorKind::PredicateNotSatisfied,
+    );
+}
+
+#[test]
+fn casts_are_chained() {
+    let db = rtti! {
+        Specific: i32,
+        Base: i32,
+    };
+
+    let any: rc::Rc<dyn Any> = rc::Rc::new(12345i32);
+    let base = Ok::<_, ()>(any)
+        .and_then_cast::<dyn Specific>(&db)
+        .and_then_cast::<dyn Base>(&db)
+        .unwrap();
+    assert_eq!(base.base(), 12345);
+
+    let any: rc::Rc<dyn Any> = rc::Rc::new(12345f32);
+    let result = Ok::<_, ()>(any)
+        .and_then_cast::<dyn Specific>(&db)
+        .and_then_cast::<dyn Base>(&db);
+    assert!(matches!(
+        result,
+        Err(ChainedCastError::Previous(ChainedCastError::Cast(CastError { pointer, .. })))
+            if pointer.is::<f32>()
+    ));
+}
+
+#[test]
+fn shared_pointers_are_casted_by_reference() {
+    let db = rtti! {
+        Base: i32,
+    };
+
+    let any: Arc<dyn Any> = Arc::new(12345i32);
+    let base = any.dyn_cast_cloned::<dyn Base>(&db).ok().unwrap();
+    assert_eq!(base.base(), 12345);
+    assert_eq!(Arc::strong_count(&any), 2);
+
+    let any: Arc<dyn Any> = Arc::new(12345f32);
+    assert!(any.dyn_cast_cloned::<dyn Base>(&db).is_err());
+    assert_eq!(Arc::strong_count(&any), 1);
+}
+
+#[test]
+fn weak_references_are_upgraded_and_casted() {
+    let db = rtti! {
+        Base: i32,
+    };
+
+    let any: rc::Rc<dyn Any> = rc::Rc::new(12345i32);
+    let weak = rc::Rc::downgrade(&any);
+    let base = weak.dyn_cast_upgraded::<dyn Base>(&db).ok().unwrap();
+    assert_eq!(base.base(), 12345);
+
+    drop((any, base));
+    assert!(matches!(
+        weak.dyn_cast_upgraded::<dyn Base>(&db),
+        Err(UpgradeCastError::Dangling { .. })
+    ));
+
+    let any: Arc<dyn Any> = Arc::new(12345f32);
+    assert!(matches!(
+        Arc::downgrade(&any).dyn_cast_upgraded::<dyn Base>(&db),
+        Err(UpgradeCastError::Cast(CastError { pointer, .. })) if pointer.is::<f32>()
+    ));
+}
+
+trait Leaf: Specific {}
+
+impl Leaf for i32 {}
+
+#[test]
+fn subtrait_declarations_are_followed_transitively() {
+    let mut db = rtti! {
+        Leaf: i32,
+    };
+    db.declare_subtrait::<dyn Leaf, dyn Specific>();
+    db.declare_subtrait::<dyn Specific, dyn Base>();
+
+    let any: rc::Rc<dyn Any> = rc::Rc::new(12345i32);
+    let base: rc::Rc<dyn Base> = any.dyn_cast(&db).unwrap();
+    assert_eq!(base.base(), 12345);
+}
+
+impl Base for u8 {
+    fn base(&self) -> i32 {
+        (*self).into()
+    }
+}
+
+#[test]
+fn auto_trait_markers_are_stripped_and_added() {
+    let mut db = rtti! {
+        Base: i32,
+    };
+    db.get_entry_mut::<dyn Base + Send>().register::<u8>();
+    unsafe { db.declare_marker_variant::<dyn Base, dyn Base + Send>() };
+
+    let any: rc::Rc<dyn Any> = rc::Rc::new(12345i32);
+    let base: rc::Rc<dyn Base + Send> = any.dyn_cast(&db).unwrap();
+    assert_eq!(base.base(), 12345);
+
+    let any: rc::Rc<dyn Any> = rc::Rc::new(123u8);
+    let base: rc::Rc<dyn Base> = any.dyn_cast(&db).unwrap();
+    assert_eq!(base.base(), 123);
+
+    let any: rc::Rc<dyn Any> = rc::Rc::new(12345f32);
+    assert!(any.dyn_cast::<dyn Base + Send>(&db).is_err());
+}
+
+#[test]
+fn destination_type_drives_cast() {
+    let db = rtti! {
+        Specific: i32,
+    };
+
+    let any: rc::Rc<RefCell<dyn Any>> = rc::Rc::new(RefCell::new(12345i32));
+    let specific = <rc::Rc<RefCell<dyn Specific>>>::dyn_cast_from(any, &db).unwrap();
+    assert_eq!(specific.borrow().base(), 12345);
+}
+
+struct Concrete(i32);
+crate::coercible_type!(Concrete);
+
+impl Base for Concrete {
+    fn base(&self) -> i32 {
+        self.0
+    }
+}
+
+#[test]
+fn concrete_type_is_casted_without_erasure() {
+    let db = rtti! {
+        Base: Concrete,
+    };
+
+    let concrete = rc::Rc::new(RefCell::new(Concrete(12345)));
+    let base: rc::Rc<RefCell<dyn Base>> = concrete.dyn_cast(&db).unwrap();
+    assert_eq!(base.borrow().base(), 12345);
+}
+
+#[test]
+fn lazily_registered_type_is_casted() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn PartialEq<i32>>()
+        .register_lazy::<i32>();
+
+    let target = db.get_db_entry::<dyn PartialEq<i32>>().unwrap();
+    let casted = target.cast(&12345i32 as &dyn Any).unwrap();
+
+    assert!(casted.eq(&12345));
+}
+
+#[test]
+#[cfg(not(feature = "compact-metadata"))]
+fn lazy_metadata_is_provided_once_on_first_lookup() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn provider() -> Metadata<dyn PartialEq<i32>> {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        ptr::metadata::<dyn PartialEq<i32>>(ptr::null::<i32>())
+    }
+
+    let mut entry = HashMapTypeDatabaseEntry::<dyn PartialEq<i32>>::default();
+    unsafe { entry.add_lazy(TypeId::of::<i32>(), provider) };
+
+    assert!(entry.contains(TypeId::of::<i32>()));
+    assert_eq!(CALLS.load(Ordering::SeqCst), 0);
+
+    let expected = ptr::metadata::<dyn PartialEq<i32>>(ptr::null::<i32>());
+    assert_eq!(entry.metadata(TypeId::of::<i32>()), Some(expected));
+    assert_eq!(entry.metadata(TypeId::of::<i32>()), Some(expected));
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+
+    // an eager registration supersedes the lazy one
+    unsafe { entry.add(TypeId::of::<i32>(), expected) };
+    unsafe { entry.add_lazy(TypeId::of::<u8>(), || unreachable!()) };
+    assert!(entry.remove(TypeId::of::<u8>()));
+    assert_eq!(entry.metadata(TypeId::of::<i32>()), Some(expected));
+    assert_eq!(entry.metadata(TypeId::of::<u8>()), None);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+}
+
+#[derive(Debug)]
+struct Flag(bool);
+
+fn is_set(flag: &dyn Any) -> bool {
+    matches!(flag.downcast_ref(), Some(Flag(true)))
+}
+
+#[test]
+fn predicated_type_implements_only_if_satisfied() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn fmt::Debug>()
+        .register_if::<Flag>(is_set);
+
+    let target = db.get_db_entry::<dyn fmt::Debug>().unwrap();
+
+    assert!(target.implements(&Flag(true) as &dyn Any).unwrap());
+    assert!(!target.implements(&Flag(false) as &dyn Any).unwrap());
+}
+
+#[test]
+fn predicated_type_is_casted_only_if_satisfied() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn fmt::Debug>()
+        .register_if::<Flag>(is_set);
+
+    let target = db.get_db_entry::<dyn fmt::Debug>().unwrap();
+    let casted = target.cast(&Flag(true) as &dyn Any).unwrap();
+    assert_eq!(format!("{:?}", casted), "Flag(true)");
+
+    let casted = target.cast(&Flag(false) as &dyn Any);
+    assert!(matches!(
+        casted,
+        Err(CastError {
+            source: PredicateNotSatisfied { .. },
+            ..
+        })
+    ));
+}
+
+#[derive(Default)]
+struct Recorder(Mutex<Vec<String>>);
+
+impl Observer for Recorder {
+    fn on_register(&self, target: &'static str, _type_id: TypeId, type_name: &'static str) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("register {} for {}", type_name, target));
+    }
+
+    fn on_lookup_hit(&self, target: &'static str, _type_id: TypeId) {
+        self.0.lock().unwrap().push(format!("hit {}", target));
+    }
+
+    fn on_lookup_miss(&self, target: &'static str, _type_id: TypeId) {
+        self.0.lock().unwrap().push(format!("miss {}", target));
+    }
+
+    fn on_cast_failure(&self, target: &'static str, _error: &dyn fmt::Debug) {
+        self.0.lock().unwrap().push(format!("failure {}", target));
+    }
+}
+
+#[test]
+fn observer_is_notified_of_activity() {
+    let recorder = Arc::new(Recorder::default());
+    let mut db = HashMapTypeDatabase::with_observer(recorder.clone());
+    db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+
+    let target = db.get_db_entry::<dyn fmt::Debug>().unwrap();
+    assert!(target.cast(&12345i32 as &dyn Any).is_ok());
+    assert!(target.cast(&12345f32 as &dyn Any).is_err());
+
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        [
+            "register i32 for dyn core::fmt::Debug",
+            "hit dyn core::fmt::Debug",
+            "miss dyn core::fmt::Debug",
+            "failure dyn core::fmt::Debug",
+        ],
+    );
+}
+
+#[test]
+fn observer_is_notified_of_unnamed_registrations() {
+    use super::token::Registration;
+    use crate::container::metadata_of;
+
+    let recorder = Arc::new(Recorder::default());
+    let mut db = HashMapTypeDatabase::with_observer(recorder.clone());
+    let entry = db.get_entry_mut::<dyn Specific>();
+    entry.register_example(&10_i32).unwrap();
+    entry.register_iter([unsafe {
+        Registration::from_raw_parts(TypeId::of::<i32>(), metadata_of::<dyn Specific, i32>())
+    }]);
+    unsafe {
+        db.register_metadata::<dyn Specific>(
+            TypeId::of::<i32>(),
+            metadata_of::<dyn Specific, i32>(),
+        );
+    }
+
+    let expected = format!("register ? for {}", std::any::type_name::<dyn Specific>());
+    assert_eq!(*recorder.0.lock().unwrap(), [expected.as_str(); 3]);
+}
+
+#[test]
+fn view_casts_registered_type() {
+    let view = DB.view();
+    let casted = (&12345i32 as &dyn Any)
+        .dyn_cast::<dyn PartialEq<i32>>(&view)
+        .unwrap();
+
+    assert!(casted.eq(&12345));
+}
+
+#[cfg(feature = "global")]
+#[test]
+fn global_is_initialized_with_deferred_registrations() {
+    use super::{
+        error::DatabaseError::Sealed,
+        hash_map::{is_sealed, seal_with},
+    };
+    use crate::{defer_registration, rtti_global, GlobalDynCast};
+
+    defer_registration::<dyn PartialEq<i32>, i32>().unwrap();
+    assert!(!is_sealed());
+
+    // registrations deferred before a failed attempt to seal are not lost
+    assert!(std::panic::catch_unwind(|| seal_with(|_| panic!("failed to seal"))).is_err());
+    assert!(!is_sealed());
+
+    // registrations cannot be deferred while sealing, but nor do they deadlock
+    seal_with(|db| {
+        assert!(matches!(
+            defer_registration::<dyn PartialEq<u8>, u8>(),
+            Err(Sealed),
+        ));
+        db.get_entry_mut::<dyn PartialEq<f32>>().register::<f32>();
+    });
+    assert!(is_sealed());
+    assert!(std::panic::catch_unwind(|| rtti_global! {
+        PartialEq<u32>: u32,
+    })
+    .is_err());
+    assert!(matches!(
+        defer_registration::<dyn PartialEq<u32>, u32>(),
+        Err(Sealed),
+    ));
+
+    let casted = GlobalDynCast::dyn_cast::<dyn PartialEq<i32>>(&12345i32 as &dyn Any).unwrap();
+    assert!(casted.eq(&12345));
+
+    let casted = GlobalDynCast::dyn_cast::<dyn PartialEq<f32>>(&1.5f32 as &dyn Any).unwrap();
+    assert!(casted.eq(&1.5));
+}
+
+#[test]
+fn namespaced_registrations_are_iterated_and_removed_together() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn fmt::Debug>()
+        .register_in::<i32>("plugin");
+    db.get_entry_mut::<dyn fmt::Debug>().register::<f32>();
+    db.get_entry_mut::<dyn fmt::Display>()
+        .register_in::<i32>("plugin");
+
+    let mut registrations: Vec<_> = db.iter_namespace("plugin").collect();
+    registrations.sort();
+    let mut expected = vec![
+        (TypeId::of::<dyn fmt::Debug>(), TypeId::of::<i32>()),
+        (TypeId::of::<dyn fmt::Display>(), TypeId::of::<i32>()),
+    ];
+    expected.sort();
+    assert_eq!(registrations, expected);
+
+    db.remove_namespace("plugin");
+    assert_eq!(db.iter_namespace("plugin").count(), 0);
+
+    let debug = db.get_db_entry::<dyn fmt::Debug>().unwrap();
+    assert!(!debug.contains(TypeId::of::<i32>()));
+    assert!(debug.contains(TypeId::of::<f32>()));
+}
+
+#[test]
+fn registration_details_do_not_outlive_the_registration() {
+    let mut db = HashMapTypeDatabase::default();
+    let entry = db.get_entry_mut::<dyn fmt::Debug>();
+    entry.register_if::<i32>(|_| false);
+    entry.set_detail(TypeId::of::<i32>(), Detail::Namespace("plugin"));
+    entry.set_detail(TypeId::of::<f32>(), Detail::Namespace("plugin"));
+    assert!(entry.predicate(TypeId::of::<i32>()).is_some());
+    assert_eq!(entry.namespace(TypeId::of::<f32>()), None);
+
+    entry.register::<i32>();
+    assert!(entry.predicate(TypeId::of::<i32>()).is_none());
+    assert_eq!(entry.namespace(TypeId::of::<i32>()), None);
+    assert_eq!(entry.iter_namespace("plugin").count(), 0);
+    assert!((&1i32 as &dyn Any).dyn_cast::<dyn fmt::Debug>(&db).is_ok());
+}
+
+#[cfg(feature = "names")]
+#[test]
+fn fingerprint_depends_only_upon_registrations() {
+    let db = rtti! {
+        fmt::Debug: i32 f32,
+        fmt::Display: i32,
+    };
+    let reordered = rtti! {
+        fmt::Display: i32,
+        fmt::Debug: f32 i32,
+    };
+    let different = rtti! {
+        fmt::Debug: i32 f32,
+        fmt::Display: f32,
+    };
+
+    assert_eq!(db.fingerprint(), reordered.fingerprint());
+    assert_ne!(db.fingerprint(), different.fingerprint());
+}
+
+#[test]
+fn memory_footprint_grows_with_registrations() {
+    let mut db = HashMapTypeDatabase::default();
+    assert_eq!(db.memory_footprint(), 0);
+
+    db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+    let footprint = db.memory_footprint();
+    assert!(footprint > 0);
+
+    db.get_entry_mut::<dyn fmt::Display>().register::<i32>();
+    assert!(db.memory_footprint() > footprint);
+}
+
+#[test]
+fn small_entries_are_stored_inline_until_spilled() {
+    let mut db = HashMapTypeDatabase::default();
+    let entry = db.get_entry_mut::<dyn fmt::Debug>();
+    entry.register::<i8>();
+    entry.register::<i16>();
+    entry.register::<i32>();
+    assert_eq!(entry.memory_footprint(), 0);
+
+    entry.register::<i64>();
+    entry.register::<u8>();
+    entry.register::<u16>();
+    assert!(entry.memory_footprint() > 0);
+
+    assert!(entry.remove(TypeId::of::<i8>()));
+    for value in [&1i16 as &dyn Any, &2i32, &3i64, &4u8, &5u16] {
+        assert!(value.dyn_cast::<dyn fmt::Debug>(&db).is_ok());
+    }
+    assert!((&0i8 as &dyn Any).dyn_cast::<dyn fmt::Debug>(&db).is_err());
+}
+
+#[test]
+fn static_registration_tables_are_installed() {
+    static DEBUG: [StaticRegistration<dyn fmt::Debug>; 2] =
+        crate::static_registrations!(fmt::Debug: i32 String);
+
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn fmt::Debug>()
+        .register_iter(DEBUG.iter().map(StaticRegistration::registration));
+
+    let value = String::from("foo");
+    let casted = (&value as &dyn Any)
+        .dyn_cast::<dyn fmt::Debug>(&db)
+        .unwrap();
+    assert_eq!(format!("{:?}", casted), "\"foo\"");
+    assert!(db
+        .get_db_entry::<dyn fmt::Debug>()
+        .unwrap()
+        .contains(TypeId::of::<i32>()));
+}
+
+#[test]
+fn entries_are_dropped_with_their_database() {
+    let recorder = Arc::new(Recorder::default());
+    let mut db = HashMapTypeDatabase::with_observer(recorder.clone());
+    db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+    db.get_entry_mut::<dyn fmt::Display>().register::<i32>();
+    assert_eq!(Arc::strong_count(&recorder), 4);
+
+    drop(db);
+    assert_eq!(Arc::strong_count(&recorder), 1);
+}
+
+#[test]
+fn type_ids_are_hashed_without_siphash() {
+    use super::small_map::TypeIdHasher;
+    use std::hash::{Hash, Hasher};
+
+    let hash = |type_id: TypeId| {
+        let mut hasher = TypeIdHasher::default();
+        type_id.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    assert_eq!(hash(TypeId::of::<i32>()), hash(TypeId::of::<i32>()));
+    assert_ne!(hash(TypeId::of::<i32>()), hash(TypeId::of::<u32>()));
+}
+
+#[test]
+fn smart_pointers_to_databases_are_databases() {
+    let mut boxed = Box::new(HashMapTypeDatabase::default());
+    boxed
+        .get_entry_mut::<dyn PartialEq<i32>>()
+        .register::<i32>();
+
+    let casted = (&12345i32 as &dyn Any)
+        .dyn_cast::<dyn PartialEq<i32>>(&boxed)
+        .unwrap();
+    assert!(casted.eq(&12345));
+
+    let shared = Arc::new(*boxed);
+    let casted = (&12345i32 as &dyn Any)
+        .dyn_cast::<dyn PartialEq<i32>>(&shared)
+        .unwrap();
+    assert!(casted.eq(&12345));
+}
+
+#[cfg(feature = "names")]
+#[test]
+fn names_and_type_ids_are_mapped() {
+    let db = rtti! {
+        fmt::Debug: i32,
+    };
+
+    assert_eq!(db.name_of(TypeId::of::<i32>()), Some("i32"));
+    assert_eq!(
+        db.name_of(TypeId::of::<dyn fmt::Debug>()),
+        Some("dyn core::fmt::Debug"),
+    );
+    assert_eq!(db.name_of(TypeId::of::<f32>()), None);
+
+    assert_eq!(db.type_id_of("i32"), Some(TypeId::of::<i32>()));
+    assert_eq!(
+        db.type_id_of("dyn core::fmt::Debug"),
+        Some(TypeId::of::<dyn fmt::Debug>()),
+    );
+    assert_eq!(db.type_id_of("f32"), None);
+}
+
+#[cfg(feature = "names")]
+#[test]
+fn unregistered_casts_suggest_candidates() {
+    let db = rtti! {
+        fmt::Debug: i32,
+        fmt::Display: f32 u8,
+    };
+
+    let error = (&1i32 as &dyn Any)
+        .dyn_cast::<dyn fmt::Display>(&db)
+        .err()
+        .unwrap();
+
+    let message = error.to_string();
+    assert!(message.contains("did you forget to register <i32>"));
+    assert!(message.contains("registered types are <f32>, <u8>"));
+}
+
+#[cfg(feature = "names")]
+#[test]
+fn candidates_are_suggested_through_forwarding_databases() {
+    let db = rtti! {
+        fmt::Debug: i32,
+        fmt::Display: u8,
+    };
+    let data: &dyn Any = &1i32;
+    let by_ref = data.dyn_cast::<dyn fmt::Display>(&&db).err().unwrap();
+    let by_rc = data
+        .dyn_cast::<dyn fmt::Display>(&rc::Rc::new(&db))
+        .err()
+        .unwrap();
+    let by_view = data
+        .dyn_cast::<dyn fmt::Display>(&super::view::DatabaseView::new(&db))
+        .err()
+        .unwrap();
+    for error in [by_ref.to_string(), by_rc.to_string(), by_view.to_string()] {
+        assert!(error.contains("did you forget to register <i32>"));
+    }
+}
+
+#[cfg(feature = "names")]
+#[test]
+fn candidates_follow_later_registrations() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn fmt::Display>().register::<u8>();
+    let data: &dyn Any = &1i32;
+    let message = data
+        .dyn_cast::<dyn fmt::Display>(&db)
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(!message.contains("did you forget"));
+    assert!(message.contains("registered types are <u8>"));
+
+    db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+    db.get_entry_mut::<dyn fmt::Display>().register::<f32>();
+    let message = data
+        .dyn_cast::<dyn fmt::Display>(&db)
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(message.contains("did you forget to register <i32>"));
+    assert!(message.contains("registered types are <f32>, <u8>"));
+}
+
+#[cfg(feature = "debug-registry")]
+#[test]
+fn registration_call_sites_are_recorded() {
+    let mut db = HashMapTypeDatabase::default();
+    let line = line!() + 1;
+    db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+
+    let location = db
+        .get_db_entry::<dyn fmt::Debug>()
+        .unwrap()
+        .location(TypeId::of::<i32>())
+        .unwrap();
+    assert_eq!(location.file(), file!());
+    assert_eq!(location.line(), line);
+}
+
+#[test]
+fn tracked_registrations_can_be_revoked() {
+    let mut db = HashMapTypeDatabase::default();
+    let token = db.register_tracked::<dyn fmt::Debug, i32>();
+    let data: Box<dyn Any> = Box::new(1i32);
+    assert!(db
+        .get_db_entry::<dyn fmt::Debug>()
+        .unwrap()
+        .implements(&*data)
+        .unwrap());
+
+    assert!(db.unregister(token));
+    assert!(!db
+        .get_db_entry::<dyn fmt::Debug>()
+        .unwrap()
+        .implements(&*data)
+        .unwrap());
+}
+
+#[test]
+fn scoped_registrations_are_revoked_on_drop() {
+    let mut db = HashMapTypeDatabase::default();
+    let data: Box<dyn Any> = Box::new(1i32);
+    {
+        let guard = db.register_scoped::<dyn fmt::Debug, i32>();
+        assert!(guard
+            .get_db_entry::<dyn fmt::Debug>()
+            .unwrap()
+            .implements(&*data)
+            .unwrap());
+    }
+    assert!(!db
+        .get_db_entry::<dyn fmt::Debug>()
+        .unwrap()
+        .implements(&*data)
+        .unwrap());
+}
+
+#[test]
+fn scoped_reregistrations_restore_the_original_on_drop() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn Base>()
+        .register_in::<i32>("permanent");
+    let data: Box<dyn Any> = Box::new(1i32);
+    {
+        let _guard = db.register_scoped::<dyn Base, i32>();
+    }
+    assert!((&*data).dyn_cast::<dyn Base>(&db).is_ok());
+    assert_eq!(
+        db.get_db_entry::<dyn Base>()
+            .unwrap()
+            .namespace(TypeId::of::<i32>()),
+        Some("permanent"),
+    );
+}
+
+#[test]
+fn discarded_registrations_revoke_nothing() {
+    use super::OverwritePolicy;
+
+    let mut db = HashMapTypeDatabase::default();
+    db.set_overwrite_policy(OverwritePolicy::FirstWins);
+    db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+    let token = db.register_tracked::<dyn fmt::Debug, i32>();
+    assert!(!db.unregister(token));
+
+    let data: Box<dyn Any> = Box::new(1i32);
+    assert!((&*data).dyn_cast::<dyn fmt::Debug>(&db).is_ok());
+}
+
+#[test]
+fn raw_pointers_are_casted_by_type_id() {
+    let data = 12345i32;
+    let erased = unsafe {
+        DB.cast_raw(
+            TypeId::of::<dyn PartialEq<i32>>(),
+            TypeId::of::<i32>(),
+            ptr::addr_of!(data).cast(),
+        )
+    }
+    .unwrap();
+
+    assert!(erased.downcast::<dyn PartialEq<f32>>().is_none());
+    let casted = erased.downcast::<dyn PartialEq<i32>>().unwrap();
+    assert!(unsafe { &*casted }.eq(&12345));
+
+    assert!(unsafe {
+        DB.cast_raw(
+            TypeId::of::<dyn PartialEq<f32>>(),
+            TypeId::of::<i32>(),
+            ptr::addr_of!(data).cast(),
+        )
+    }
+    .is_none());
+}
+
+#[test]
+fn traits_of_value_are_enumerated() {
+    let mut db = rtti! {
+        PartialEq<i32>: i32,
+        fmt::Debug: i32 f32,
+    };
+
+    let mut traits: Vec<_> = db.traits_of(&12345i32 as &dyn Any).unwrap().collect();
+    traits.sort();
+    let mut expected = vec![
+        TypeId::of::<dyn PartialEq<i32>>(),
+        TypeId::of::<dyn fmt::Debug>(),
+    ];
+    expected.sort();
+    assert_eq!(traits, expected);
+
+    assert!(db
+        .get_entry_mut::<dyn fmt::Debug>()
+        .remove(TypeId::of::<i32>()));
+    db.get_entry_mut::<dyn fmt::Display>().register::<i32>();
+    let mut traits: Vec<_> = db.traits_of(&12345i32 as &dyn Any).unwrap().collect();
+    traits.sort();
+    let mut expected = vec![
+        TypeId::of::<dyn PartialEq<i32>>(),
+        TypeId::of::<dyn fmt::Display>(),
+    ];
+    expected.sort();
+    assert_eq!(traits, expected);
+
+    #[cfg(feature = "names")]
+    assert_eq!(
+        db.trait_names_of(&1f32 as &dyn Any)
+            .unwrap()
+            .collect::<Vec<_>>(),
+        [std::any::type_name::<dyn fmt::Debug>()],
+    );
+}
+
+#[test]
+fn views_of_value_are_visited() {
+    let db = rtti! {
+        PartialEq<i32>: i32,
+        fmt::Debug: i32 f32,
+    };
+
+    let mut debugged = Vec::new();
+    let mut equal = false;
+    db.for_each_view(&12345i32 as &dyn Any, &mut |view: ErasedRef<'_>| {
+        if let Some(debug) = view.downcast::<dyn fmt::Debug>() {
+            debugged.push(format!("{:?}", debug));
+        } else if let Some(eq) = view.downcast::<dyn PartialEq<i32>>() {
+            equal = eq.eq(&12345);
+        }
+    })
+    .unwrap();
+
+    assert_eq!(debugged, ["12345"]);
+    assert!(equal);
+}
+
+#[test]
+fn registered_clonable_type_is_cloned() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn fmt::Debug>()
+        .register_clone::<String>();
+    db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+
+    let original: Box<dyn Any> = Box::new(String::from("hello"));
+    let cloned: Box<dyn fmt::Debug> = db.dyn_clone(&*original).unwrap();
+    assert_eq!(format!("{:?}", cloned), "\"hello\"");
+
+    assert!(db
+        .dyn_clone::<dyn fmt::Debug, _>(&12345i32 as &dyn Any)
+        .is_none());
+}
+
+#[test]
+fn registered_types_are_compared_and_hashed() {
+    use std::collections::hash_map::DefaultHasher;
+
+    let mut db = HashMapTypeDatabase::default();
+    let entry = db.get_entry_mut::<dyn fmt::Debug>();
+    entry.register_eq::<i32>();
+    entry.register_hash::<i32>();
+    entry.register_eq::<u32>();
+
+    let (a, b, c, d): (&dyn Any, &dyn Any, &dyn Any, &dyn Any) = (&1i32, &1i32, &2i32, &1u32);
+    assert_eq!(db.dyn_eq::<dyn fmt::Debug, _, _>(a, b), Some(true));
+    assert_eq!(db.dyn_eq::<dyn fmt::Debug, _, _>(a, c), Some(false));
+    assert_eq!(db.dyn_eq::<dyn fmt::Debug, _, _>(a, d), Some(false));
+    assert_eq!(
+        db.dyn_eq::<dyn fmt::Debug, _, _>(&1f32 as &dyn Any, a),
+        None
+    );
+
+    let hash = |value: &dyn Any| {
+        let mut state = DefaultHasher::new();
+        db.dyn_hash::<dyn fmt::Debug, _, _>(value, &mut state)
+            .then(|| std::hash::Hasher::finish(&state))
+    };
+    assert_eq!(hash(a), hash(b));
+    assert_ne!(hash(a), hash(c));
+    assert_eq!(hash(d), None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn tagged_implementors_round_trip() {
+    let mut db = HashMapTypeDatabase::default();
+    let entry = db.get_entry_mut::<dyn Base>();
+    entry.register_tagged::<i32>("int").unwrap();
+
+    let value: &dyn Any = &42i32;
+    let mut json = Vec::new();
+    db.serialize_dyn::<dyn Base, _, _>(value, &mut serde_json::Serializer::new(&mut json))
+        .unwrap();
+    assert_eq!(json, br#"{"int":42}"#);
+
+    let mut deserializer = serde_json::Deserializer::from_slice(&json);
+    let value = db
+        .deserialize_dyn::<dyn Base, _>(&mut deserializer)
+        .unwrap();
+    assert_eq!(value.base(), 42);
+
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{"float":1.5}"#);
+    assert!(db
+        .deserialize_dyn::<dyn Base, _>(&mut deserializer)
+        .is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn duplicate_tags_are_rejected() {
+    let mut db = HashMapTypeDatabase::default();
+    let entry = db.get_entry_mut::<dyn Base>();
+    entry.register_tagged::<i32>("int").unwrap();
+    entry.register_tagged::<i32>("int").unwrap();
+
+    let error = entry.register_tagged::<u8>("int").unwrap_err();
+    assert!(matches!(
+        error,
+        super::error::RegistrationError::DuplicateTag { type_id, name: "int", .. }
+            if type_id == TypeId::of::<u8>()
+    ));
+    assert!(!entry.contains(TypeId::of::<u8>()));
+
+    assert!(entry.remove(TypeId::of::<i32>()));
+    assert!(entry.tag_named("int").is_none());
+    entry.register_tagged::<u8>("int").unwrap();
+    assert_eq!(entry.tag_named("int").unwrap().name, "int");
+    assert!(entry.tag(TypeId::of::<u8>()).is_some());
+}
+
+#[cfg(feature = "erased-serde")]
+#[test]
+fn erased_values_are_serialized() {
+    let mut db = HashMapTypeDatabase::default();
+    super::serialize::register_prelude(&mut db);
+
+    let values: Vec<Box<dyn Any>> =
+        vec![Box::new(1u8), Box::new(String::from("two")), Box::new(true)];
+    let json = serde_json::to_string(&db.serializable_seq(&values)).unwrap();
+    assert_eq!(json, r#"[1,"two",true]"#);
+
+    let unregistered: Box<dyn Any> = Box::new(Concrete(3));
+    assert!(serde_json::to_string(&db.serializable(&unregistered)).is_err());
+}
+
+#[test]
+fn registered_constructors_create_instances() {
+    let mut db = HashMapTypeDatabase::default();
+    let entry = db.get_entry_mut::<dyn Base>();
+    entry.register_default::<i32>();
+    entry.register_constructor::<Concrete>(|| Box::new(Concrete(7)));
+
+    let created = db.create::<dyn Base>(TypeId::of::<i32>()).unwrap();
+    assert_eq!(created.base(), 0);
+    let created = db.create::<dyn Base>(TypeId::of::<Concrete>()).unwrap();
+    assert_eq!(created.base(), 7);
+    assert!(db.create::<dyn Base>(TypeId::of::<u8>()).is_none());
+
+    #[cfg(feature = "names")]
+    assert_eq!(
+        db.create_named::<dyn Base>(std::any::type_name::<Concrete>())
+            .map(|created| created.base()),
+        Some(7)
+    );
+}
+
+#[test]
+fn targets_are_selected_by_name() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn Base>().register::<i32>();
+    db.name_target::<dyn Base>("base");
+
+    let value: &dyn Any = &12;
+    assert_eq!(db.implements_name(value, "base"), Ok(true));
+    assert_eq!(db.implements_name(value, "unknown"), Ok(false));
+    assert_eq!(db.implements_name(&"text" as &dyn Any, "base"), Ok(false));
+
+    let base = db.cast_by_name(value, "base").unwrap();
+    assert_eq!(base.downcast::<dyn Base>().map(Base::base), Some(12));
+    assert!(db
+        .cast_by_name(value, std::any::type_name::<dyn Base>())
+        .is_some());
+}
+
+#[test]
+fn target_aliases_resolve_to_canonical_entry() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn Base>().register::<i32>();
+    assert!(db.alias_target("legacy::Base", std::any::type_name::<dyn Base>()));
+    assert!(!db.alias_target("legacy::Other", "unknown"));
+
+    let value: &dyn Any = &12;
+    assert_eq!(db.implements_name(value, "legacy::Base"), Ok(true));
+    assert_eq!(db.implements_name(value, "legacy::Other"), Ok(false));
+}
+
+#[test]
+fn std_types_are_registered_for_formatting() {
+    let mut db = HashMapTypeDatabase::default();
+    super::std_fmt::register_std_fmt(&mut db);
+
+    let byte: &dyn Any = &255u8;
+    let hex = byte.dyn_cast::<dyn fmt::LowerHex>(&db).ok().unwrap();
+    assert_eq!(format!("{:x}", hex), "ff");
+
+    let text: &dyn Any = &String::from("text");
+    let display = text.dyn_cast::<dyn fmt::Display>(&db).ok().unwrap();
+    assert_eq!(display.to_string(), "text");
+    assert!(text.dyn_cast::<dyn fmt::LowerExp>(&db).is_err());
+}
+
+#[test]
+fn duplicate_registrations_are_rejected() {
+    let mut db = HashMapTypeDatabase::default();
+    let entry = db.get_entry_mut::<dyn Base>();
+    assert!(entry.try_register::<i32>().is_ok());
+
+    let error = entry.try_register::<i32>().unwrap_err();
+    assert!(matches!(
+        error,
+        super::error::RegistrationError::AlreadyRegistered { type_id, .. }
+            if type_id == TypeId::of::<i32>()
+    ));
+    #[cfg(feature = "names")]
+    assert!(matches!(
+        error,
+        super::error::RegistrationError::AlreadyRegistered { previous, .. }
+            if previous.type_name == Some(std::any::type_name::<i32>())
+    ));
+
+    // metadata that differs from that of `i32` is reported as a conflict
+    unsafe {
+        entry.add(
+            TypeId::of::<i32>(),
+            crate::container::metadata_of::<dyn Base, u8>(),
+        );
+    }
+    assert!(matches!(
+        entry.try_register::<i32>().unwrap_err(),
+        super::error::RegistrationError::ConflictingRegistration { type_id, .. }
+            if type_id == TypeId::of::<i32>()
+    ));
+}
+
+// wasm32 cannot unwind from panics
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn overwrite_policy_governs_duplicate_registrations() {
+    use super::OverwritePolicy;
+
+    let mut db = HashMapTypeDatabase::default();
+    db.set_overwrite_policy(OverwritePolicy::FirstWins);
+    let entry = db.get_entry_mut::<dyn Base>();
+    entry.register_in::<i32>("first");
+    entry.register_in::<i32>("second");
+    assert_eq!(entry.namespace(TypeId::of::<i32>()), Some("first"));
+
+    db.set_overwrite_policy(OverwritePolicy::Panic);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        db.get_entry_mut::<dyn Base>().register::<i32>();
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn capability_is_queried_by_type_id() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn Base>()
+        .register_if::<i32>(|data| data.downcast_ref::<i32>() == Some(&1));
+
+    let type_id = TypeId::of::<i32>();
+    assert!(db.is_implementor_id::<dyn Base>(type_id));
+    assert!(!db.is_implementor_id::<dyn Base>(TypeId::of::<u8>()));
+    assert!(!db.is_implementor_id::<dyn fmt::Debug>(type_id));
+
+    assert!(db.is_registered::<dyn Base, i32>());
+    assert!(!db.is_registered::<dyn Base, Concrete>());
+}
+
+#[test]
+fn captured_metadata_is_registered() {
+    let value: &dyn Specific = &7;
+    let (type_id, metadata) = super::capture_metadata(value).unwrap();
+    assert_eq!(type_id, TypeId::of::<i32>());
+
+    let mut db = HashMapTypeDatabase::default();
+    unsafe { db.register_metadata::<dyn Specific>(type_id, metadata) };
+
+    let any: &dyn Any = &9;
+    assert_eq!(
+        any.dyn_cast::<dyn Specific>(&db)
+            .map(|specific| specific.base())
+            .ok(),
+        Some(9)
+    );
+}
+
+#[test]
+fn tuples_of_implementors_are_registered() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn Base>()
+        .register_many::<(i32, Concrete)>();
+
+    assert!(db.is_implementor_id::<dyn Base>(TypeId::of::<i32>()));
+    assert!(db.is_implementor_id::<dyn Base>(TypeId::of::<Concrete>()));
+}
+
+#[test]
+fn registrations_are_installed_in_bulk() {
+    let mut source = HashMapTypeDatabase::default();
+    source
+        .get_entry_mut::<dyn Base>()
+        .register_many::<(i32, Concrete)>();
+
+    let mut db = HashMapTypeDatabase::default();
+    let entry = db.get_entry_mut::<dyn Base>();
+    entry.extend(source.get_db_entry::<dyn Base>().unwrap().registrations());
+    unsafe {
+        entry.register_raw_iter(std::iter::once((
+            TypeId::of::<u8>(),
+            ptr::metadata::<dyn Base>(ptr::null::<i32>()),
+        )));
+    }
+
+    assert!(db.is_implementor_id::<dyn Base>(TypeId::of::<i32>()));
+    assert!(db.is_implementor_id::<dyn Base>(TypeId::of::<Concrete>()));
+    assert!(db.is_implementor_id::<dyn Base>(TypeId::of::<u8>()));
+    #[cfg(feature = "names")]
+    assert_eq!(
+        db.get_db_entry::<dyn Base>()
+            .unwrap()
+            .type_name(TypeId::of::<Concrete>()),
+        Some(std::any::type_name::<Concrete>())
+    );
+}
+
+#[test]
+fn fat_pointers_are_constructed_from_registered_metadata() {
+    let mut db = HashMapTypeDatabase::default();
+    let entry = db.get_entry_mut::<dyn Base>();
+    entry.register::<Concrete>();
+    assert!(entry.metadata_for::<i32>().is_none());
+
+    let concrete = Concrete(7);
+    let metadata = entry.metadata_for::<Concrete>().unwrap();
+    let base: *const dyn Base =
+        ptr::from_raw_parts((&concrete as *const Concrete).cast::<()>(), metadata);
+    assert_eq!(unsafe { &*base }.base(), 7);
+}
+
+#[test]
+fn trait_index_tests_targets_by_mask() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+    db.get_entry_mut::<dyn fmt::Debug>().register::<String>();
+    db.get_entry_mut::<dyn fmt::Display>().register::<String>();
+
+    let index = db.trait_index();
+    assert_eq!(index.len(), 2);
+    assert!(index.index_of::<dyn fmt::Binary>().is_none());
+    let debug = index.index_of::<dyn fmt::Debug>().unwrap();
+    let display = index.index_of::<dyn fmt::Display>().unwrap();
+    assert_ne!(debug, display);
+
+    let mask = index
+        .mask::<(&dyn fmt::Debug, &dyn fmt::Display)>()
+        .unwrap();
+    assert!(index.implements_all(TypeId::of::<String>(), &mask));
+    assert!(!index.implements_all(TypeId::of::<i32>(), &mask));
+    assert!(index.implements_any(TypeId::of::<i32>(), &mask));
+    assert!(!index.implements_any(TypeId::of::<u8>(), &mask));
+    assert!(index
+        .traits_of(TypeId::of::<i32>())
+        .unwrap()
+        .contains(debug));
+    assert!(index
+        .mask::<(&dyn fmt::Debug, &dyn fmt::Binary)>()
+        .is_none());
+}
+
+#[test]
+fn indexed_databases_agree_with_lookups() {
+    type Both = (&'static dyn Base, &'static dyn fmt::Debug);
+
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn Specific>().register::<i32>();
+    db.declare_subtrait::<dyn Specific, dyn Base>();
+    db.get_entry_mut::<dyn fmt::Debug>()
+        .register_if::<i32>(|data| data.downcast_ref() == Some(&1i32));
+    db.get_entry_mut::<dyn fmt::Debug>().register::<u8>();
+    db.index_traits();
+    assert!(super::TypeDatabase::cached_trait_index(&db).is_some());
+
+    assert!((&1i32 as &dyn Any).dyn_implements_all::<Both>(&db).unwrap());
+    assert!(!(&2i32 as &dyn Any).dyn_implements_all::<Both>(&db).unwrap());
+    assert!((&2i32 as &dyn Any).dyn_implements_any::<Both>(&db).unwrap());
+    assert!(!(&2u8 as &dyn Any).dyn_implements_all::<Both>(&db).unwrap());
+    assert!((&2u8 as &dyn Any).dyn_implements_any::<Both>(&db).unwrap());
+    assert!(!(&2u16 as &dyn Any).dyn_implements_any::<Both>(&db).unwrap());
+
+    db.get_entry_mut::<dyn fmt::Debug>().register::<u16>();
+    assert!(super::TypeDatabase::cached_trait_index(&db).is_none());
+    assert!((&2u16 as &dyn Any).dyn_implements_any::<Both>(&db).unwrap());
+}
+
+#[test]
+#[cfg(feature = "bloom-filter")]
+fn spilled_entries_filter_misses() {
+    let mut db = HashMapTypeDatabase::default();
+    let entry = db.get_entry_mut::<dyn fmt::Debug>();
+    entry.register::<i8>();
+    entry.register::<i16>();
+    entry.register::<i32>();
+    entry.register::<i64>();
+    entry.register::<u8>();
+    entry.register::<u16>();
+    entry.register::<u32>();
+    entry.register::<u64>();
+    entry.remove_namespace("unused");
+
+    for value in [
+        &1i8 as &dyn Any,
+        &2i16,
+        &3i32,
+        &4i64,
+        &5u8,
+        &6u16,
+        &7u32,
+        &8u64,
+    ] {
+        assert!(value.dyn_cast::<dyn fmt::Debug>(&db).is_ok());
+    }
+    for value in [&1.0f32 as &dyn Any, &2.0f64, &'c', &true, &()] {
+        assert!(value.dyn_cast::<dyn fmt::Debug>(&db).is_err());
+    }
+}
+
+#[test]
+#[cfg(feature = "compact-metadata")]
+fn compact_metadata_is_interned_once() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+    db.get_entry_mut::<dyn fmt::Debug>()
+        .register_lazy::<String>();
+    let footprint = db.memory_footprint();
+
+    let entry = db.get_entry_mut::<dyn fmt::Debug>();
+    assert!(entry.remove(TypeId::of::<i32>()));
+    entry.register::<i32>();
+    assert_eq!(db.memory_footprint(), footprint);
+
+    let value = String::from("foo");
+    let casted = (&value as &dyn Any)
+        .dyn_cast::<dyn fmt::Debug>(&db)
+        .unwrap();
+    assert_eq!(format!("{:?}", casted), "\"foo\"");
+    assert!((&1i32 as &dyn Any).dyn_cast::<dyn fmt::Debug>(&db).is_ok());
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Default)]
+struct Counters(Mutex<std::collections::HashMap<String, Arc<std::sync::atomic::AtomicU64>>>);
+
+#[cfg(feature = "metrics")]
+impl Counters {
+    fn get(&self, name: &str, target: &str) -> u64 {
+        self.0
+            .lock()
+            .unwrap()
+            .get(&format!("{}<{}>", name, target))
+            .map_or(0, |counter| {
+                counter.load(std::sync::atomic::Ordering::SeqCst)
+            })
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl metrics::Recorder for Counters {
+    fn describe_counter(
+        &self,
+        _: metrics::KeyName,
+        _: Option<metrics::Unit>,
+        _: metrics::SharedString,
+    ) {
+    }
+    fn describe_gauge(
+        &self,
+        _: metrics::KeyName,
+        _: Option<metrics::Unit>,
+        _: metrics::SharedString,
+    ) {
+    }
+    fn describe_histogram(
+        &self,
+        _: metrics::KeyName,
+        _: Option<metrics::Unit>,
+        _: metrics::SharedString,
+    ) {
+    }
+
+    fn register_counter(&self, key: &metrics::Key, _: &metrics::Metadata<'_>) -> metrics::Counter {
+        let target = key.labels().find(|label| label.key() == "target").unwrap();
+        let counter = self
+            .0
+            .lock()
+            .unwrap()
+            .entry(format!("{}<{}>", key.name(), target.value()))
+            .or_default()
+            .clone();
+        metrics::Counter::from_arc(counter)
+    }
+
+    fn register_gauge(&self, _: &metrics::Key, _: &metrics::Metadata<'_>) -> metrics::Gauge {
+        metrics::Gauge::noop()
+    }
+
+    fn register_histogram(
+        &self,
+        _: &metrics::Key,
+        _: &metrics::Metadata<'_>,
+    ) -> metrics::Histogram {
+        metrics::Histogram::noop()
+    }
+}
+
+#[test]
+#[cfg(feature = "metrics")]
+fn casts_and_registrations_are_counted() {
+    let counters = Counters::default();
+    metrics::with_local_recorder(&counters, || {
+        let mut db = HashMapTypeDatabase::default();
+        db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+
+        assert!((&1i32 as &dyn Any).dyn_cast::<dyn fmt::Debug>(&db).is_ok());
+        assert!((&1u8 as &dyn Any).dyn_cast::<dyn fmt::Debug>(&db).is_err());
+        assert!((&1i32 as &dyn Any)
+            .dyn_cast::<dyn fmt::Display>(&db)
+            .is_err());
+    });
+
+    let debug = std::any::type_name::<dyn fmt::Debug>();
+    let display = std::any::type_name::<dyn fmt::Display>();
+    assert_eq!(counters.get("rattish_registrations_total", debug), 1);
+    assert_eq!(counters.get("rattish_casts_attempted_total", debug), 2);
+    assert_eq!(counters.get("rattish_casts_succeeded_total", debug), 1);
+    assert_eq!(counters.get("rattish_casts_failed_total", debug), 1);
+    assert_eq!(counters.get("rattish_casts_failed_total", display), 1);
+}
+
+#[cfg(feature = "names")]
+#[test]
+fn databases_debug_with_names() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn fmt::Display>().register::<i32>();
+    db.get_entry_mut::<dyn fmt::Debug>().register::<String>();
+    db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+
+    assert_eq!(
+        format!("{:?}", db.fmt_compact()),
+        "{dyn core::fmt::Debug: {alloc::string::String, i32}, dyn core::fmt::Display: {i32}}",
+    );
+    let verbose = format!("{:?}", db.fmt_verbose());
+    assert!(verbose.starts_with("{dyn core::fmt::Debug: {alloc::string::String: Implementor {"));
+    assert!(verbose.contains("namespace: None, predicated: false"));
+}
+
+#[cfg(any(feature = "tracing", feature = "cast-failure-events"))]
+#[derive(Default)]
+struct Traced(Mutex<Vec<String>>);
+
+#[cfg(any(feature = "tracing", feature = "cast-failure-events"))]
+impl Traced {
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut self.0.lock().unwrap())
+    }
+}
+
+#[cfg(any(feature = "tracing", feature = "cast-failure-events"))]
+impl tracing::Subscriber for Traced {
+    fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("span {}", span.metadata().name()));
+        tracing::span::Id::from_u64(1)
+    }
+
+    fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+    fn event(&self, event: &tracing::Event<'_>) {
+        struct Fields<'a>(&'a mut String);
+
+        impl tracing::field::Visit for Fields<'_> {
+            fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+                self.0.push_str(&format!(" {}={:?}", field.name(), value));
+            }
+        }
+
+        let mut fields = format!("event {}", event.metadata().target());
+        event.record(&mut Fields(&mut fields));
+        self.0.lock().unwrap().push(fields);
+    }
+
+    fn enter(&self, _: &tracing::span::Id) {}
+
+    fn exit(&self, _: &tracing::span::Id) {}
+}
+
+#[test]
+#[cfg(feature = "cast-failure-events")]
+fn cast_failures_emit_structured_events() {
+    let traced = Arc::new(Traced::default());
+    tracing::subscriber::with_default(traced.clone(), || {
+        let mut db = HashMapTypeDatabase::default();
+        db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+
+        assert!((&1i32 as &dyn Any).dyn_cast::<dyn fmt::Debug>(&db).is_ok());
+        assert!((&1u8 as &dyn Any).dyn_cast::<dyn fmt::Debug>(&db).is_err());
+    });
+
+    let failures: Vec<_> = traced
+        .take()
+        .into_iter()
+        .filter(|event| event.starts_with("event rattish::cast_failure "))
+        .collect();
+    assert_eq!(failures.len(), 1);
+    assert!(failures[0].starts_with(&format!(
+        "event rattish::cast_failure message=cast failed target_type=\"dyn core::fmt::Debug\" pointer_type=\"&dyn core::any::Any\" concrete_type_id={:?}",
+        TypeId::of::<u8>(),
+    )));
+    assert!(failures[0].ends_with(" kind=TypeNotRegisteredForTarget"));
+}
+
+#[test]
+#[cfg(feature = "tracing")]
+fn traces_are_filtered_at_runtime() {
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+    db.get_entry_mut::<dyn fmt::Display>().register::<i32>();
+    db.get_entry_mut::<dyn fmt::Binary>()
+        .register_if::<i32>(|_| {
+            tracing::info!(target: "predicate", "called");
+            true
+        });
+
+    let traced = Arc::new(Traced::default());
+    tracing::subscriber::with_default(traced.clone(), || {
+        crate::trace::set_filter(crate::trace::Filter::default().target::<dyn fmt::Display>());
+        assert!((&1i32 as &dyn Any).dyn_cast::<dyn fmt::Debug>(&db).is_ok());
+        assert!(traced.take().is_empty());
+        assert!((&1i32 as &dyn Any).dyn_cast::<dyn fmt::Binary>(&db).is_ok());
+        assert_eq!(traced.take(), ["event predicate message=called"]);
+        assert!((&1i32 as &dyn Any)
+            .dyn_cast::<dyn fmt::Display>(&db)
+            .is_ok());
+        assert!(traced.take().contains(&"span dyn_cast".to_owned()));
+
+        crate::trace::set_filter(crate::trace::Filter::default().failures_only());
+        assert!((&1i32 as &dyn Any)
+            .dyn_cast::<dyn fmt::Display>(&db)
+            .is_ok());
+        assert!(traced.take().is_empty());
+        assert!((&1u8 as &dyn Any)
+            .dyn_cast::<dyn fmt::Display>(&db)
+            .is_err());
+        let traced = traced.take();
+        assert!(traced.iter().all(|trace| trace.starts_with("event ")));
+        assert!(traced
+            .iter()
+            .any(|trace| trace.contains("cast to <dyn core::fmt::Display> failed")));
+
+        crate::trace::set_filter(crate::trace::Filter::default());
+    });
+}
+
+#[test]
+#[cfg(feature = "puffin")]
+fn casts_are_profiled() {
+    let view = puffin::GlobalFrameView::default();
+    puffin::set_scopes_on(true);
+
+    let mut db = HashMapTypeDatabase::default();
+    db.get_entry_mut::<dyn fmt::Debug>().register::<i32>();
+    assert!((&1i32 as &dyn Any).dyn_cast::<dyn fmt::Debug>(&db).is_ok());
+    puffin::GlobalProfiler::lock().new_frame();
+
+    assert!(view
+        .lock()
+        .scope_collection()
+        .scopes_by_id()
+        .values()
+        .any(|scope| scope.scope_name.as_deref() == Some("dyn_cast")));
+}
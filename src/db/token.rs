@@ -0,0 +1,418 @@
+//! Handles to individual registrations, by which they can later be revoked.
+
+use super::{Detail, DetailKind, Predicate, TypeDatabaseEntry, TypeDatabaseMut};
+use crate::container::{metadata_of, Metadata};
+use core::{
+    any::{type_name, TypeId},
+    array, fmt,
+    marker::Unsize,
+    ops::{Deref, DerefMut},
+};
+
+/// A handle to the registration of a concrete type as an implementor of `U`,
+/// which can be passed to [`unregister`][super::TypeDatabaseExt::unregister]
+/// in order to revoke that registration.
+#[must_use = "a registration can only be revoked through its token"]
+pub struct RegistrationToken<U>
+where
+    U: ?Sized,
+{
+    type_id: TypeId,
+    revocation: Revocation<U>,
+}
+
+/// How to revoke a registration.
+// tokens are seldom moved, so the size of a captured registration is immaterial
+#[allow(clippy::large_enum_variant)]
+enum Revocation<U>
+where
+    U: ?Sized,
+{
+    /// The registration was never installed (for example, because the entry's
+    /// [`OverwritePolicy`][super::OverwritePolicy] discarded it), so there is
+    /// nothing to revoke.
+    None,
+    /// The registration is revoked by removing it.
+    Remove,
+    /// The registration replaced another, which is reinstated.
+    Restore(Replaced<U>),
+}
+
+impl<U> RegistrationToken<U>
+where
+    U: ?Sized,
+{
+    /// A token for the registration of the concrete type with the given
+    /// `type_id` in `entry`, which is about to be made; or, if not `installed`,
+    /// would have been.
+    pub(super) fn new<E>(entry: &E, type_id: TypeId, installed: bool) -> Self
+    where
+        E: ?Sized + TypeDatabaseEntry<U>,
+    {
+        let revocation = if !installed {
+            Revocation::None
+        } else {
+            match Replaced::capture(entry, type_id) {
+                Some(replaced) => Revocation::Restore(replaced),
+                None => Revocation::Remove,
+            }
+        };
+        Self {
+            type_id,
+            revocation,
+        }
+    }
+
+    /// The [`TypeId`] of the registered concrete type.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Revoke the registration from `entry`, reinstating any that it replaced,
+    /// and return whether it was still present.
+    pub(super) fn revoke<E>(self, entry: &mut E) -> bool
+    where
+        E: ?Sized + TypeDatabaseEntry<U>,
+    {
+        match self.revocation {
+            Revocation::None => false,
+            Revocation::Remove => entry.remove(self.type_id),
+            Revocation::Restore(replaced) => {
+                let present = entry.contains(self.type_id);
+                replaced.restore(entry, self.type_id);
+                present
+            }
+        }
+    }
+}
+
+/// Everything recorded by an entry about the registration of a concrete type,
+/// so that it can be reinstated after being replaced.
+struct Replaced<U>
+where
+    U: ?Sized,
+{
+    metadata: Metadata<U>,
+    predicate: Option<Predicate>,
+    details: [Option<Detail<U>>; DetailKind::ALL.len()],
+}
+
+impl<U> Replaced<U>
+where
+    U: ?Sized,
+{
+    /// The registration, if any, of the concrete type with the given `type_id`
+    /// in `entry`.
+    fn capture<E>(entry: &E, type_id: TypeId) -> Option<Self>
+    where
+        E: ?Sized + TypeDatabaseEntry<U>,
+    {
+        Some(Self {
+            metadata: entry.metadata(type_id)?,
+            predicate: entry.predicate(type_id),
+            details: array::from_fn(|i| entry.detail(type_id, DetailKind::ALL[i])),
+        })
+    }
+
+    /// Reinstate this registration of the concrete type with the given
+    /// `type_id` in `entry`.
+    fn restore<E>(self, entry: &mut E, type_id: TypeId)
+    where
+        E: ?Sized + TypeDatabaseEntry<U>,
+    {
+        // the metadata was captured from an entry for the same target
+        unsafe { entry.add(type_id, self.metadata) };
+        if let Some(predicate) = self.predicate {
+            entry.set_predicate(type_id, predicate);
+        }
+        for &detail in self.details.iter().flatten() {
+            entry.set_detail(type_id, detail);
+        }
+    }
+}
+
+impl<U> fmt::Debug for RegistrationToken<U>
+where
+    U: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RegistrationToken<{}>", type_name::<U>())?;
+        f.debug_tuple("").field(&self.type_id).finish()
+    }
+}
+
+/// A record of the registration of a concrete type as an implementor of `U`,
+/// which can be installed in an entry with
+/// [`register_iter`][super::TypeDatabaseEntryExt::register_iter].
+pub struct Registration<U>
+where
+    U: ?Sized,
+{
+    pub(super) type_id: TypeId,
+    pub(super) metadata: Metadata<U>,
+    #[cfg(feature = "names")]
+    pub(super) type_name: Option<&'static str>,
+}
+
+impl<U> Registration<U>
+where
+    U: ?Sized,
+{
+    /// The registration of concrete type `I` as an implementor of `U`.
+    pub fn of<I>() -> Self
+    where
+        I: 'static + Unsize<U>,
+    {
+        Self {
+            type_id: TypeId::of::<I>(),
+            metadata: metadata_of::<U, I>(),
+            #[cfg(feature = "names")]
+            type_name: Some(type_name::<I>()),
+        }
+    }
+
+    /// The registration of `metadata` for the concrete type with the given
+    /// `type_id`.
+    ///
+    /// # Safety
+    /// `metadata` must be the correct [`Metadata<U>`] for the concrete type
+    /// represented by `type_id`.
+    pub unsafe fn from_raw_parts(type_id: TypeId, metadata: Metadata<U>) -> Self {
+        Self {
+            type_id,
+            metadata,
+            #[cfg(feature = "names")]
+            type_name: None,
+        }
+    }
+
+    /// The [`TypeId`] of the registered concrete type.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// The [`Metadata<U>`] of the registered concrete type.
+    pub fn metadata(&self) -> Metadata<U> {
+        self.metadata
+    }
+
+    /// The name of the registered concrete type, if known.
+    #[cfg(feature = "names")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "names")))]
+    pub fn type_name(&self) -> Option<&'static str> {
+        self.type_name
+    }
+}
+
+impl<U> Clone for Registration<U>
+where
+    U: ?Sized,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<U> Copy for Registration<U> where U: ?Sized {}
+
+impl<U> fmt::Debug for Registration<U>
+where
+    U: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Registration<{}>", type_name::<U>())?;
+        let mut f = f.debug_tuple("");
+        #[cfg(feature = "names")]
+        f.field(&self.type_name.unwrap_or("?"));
+        f.field(&self.type_id).finish()
+    }
+}
+
+/// A record of the registration of a concrete type as an implementor of `U`,
+/// whose metadata is evaluated at compile time, such that tables of them can be
+/// `static` (see [`static_registrations`][crate::static_registrations]).
+///
+/// Their [`TypeId`]s cannot yet be evaluated at compile time, so must still be
+/// determined upon installation in an entry with
+/// [`register_iter`][super::TypeDatabaseEntryExt::register_iter].
+pub struct StaticRegistration<U>
+where
+    U: ?Sized,
+{
+    type_id: fn() -> TypeId,
+    type_name: fn() -> &'static str,
+    metadata: Metadata<U>,
+}
+
+impl<U> StaticRegistration<U>
+where
+    U: ?Sized,
+{
+    /// The [`Registration`] that this records.
+    pub fn registration(&self) -> Registration<U> {
+        Registration {
+            type_id: (self.type_id)(),
+            metadata: self.metadata,
+            #[cfg(feature = "names")]
+            type_name: Some((self.type_name)()),
+        }
+    }
+}
+
+impl<U> Clone for StaticRegistration<U>
+where
+    U: ?Sized,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<U> Copy for StaticRegistration<U> where U: ?Sized {}
+
+impl<U> fmt::Debug for StaticRegistration<U>
+where
+    U: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "StaticRegistration<{}>", type_name::<U>())?;
+        f.debug_tuple("").field(&(self.type_name)()).finish()
+    }
+}
+
+/// A concrete type whose registration as an implementor of `U` can be
+/// evaluated at compile time.
+pub trait StaticImplementor<U>
+where
+    U: ?Sized,
+{
+    /// The registration of `Self` as an implementor of `U`.
+    const REGISTRATION: StaticRegistration<U>;
+}
+
+impl<U, I> StaticImplementor<U> for I
+where
+    U: ?Sized,
+    I: 'static + Unsize<U>,
+{
+    const REGISTRATION: StaticRegistration<U> = StaticRegistration {
+        type_id: TypeId::of::<I>,
+        type_name: type_name::<I>,
+        metadata: metadata_of::<U, I>(),
+    };
+}
+
+/// Evaluates to an array of the [`StaticRegistration`]s of the given types as
+/// implementors of the given trait, which can be used to initialize a `static`:
+///
+/// ```rust
+/// # #[cfg(feature = "std")] {
+/// use rattish::{
+///     db::{
+///         hash_map::HashMapTypeDatabase, token::StaticRegistration, TypeDatabaseEntryExt,
+///         TypeDatabaseMut,
+///     },
+///     static_registrations, DynImplements,
+/// };
+/// use std::{any::Any, fmt::Debug};
+///
+/// static DEBUG: [StaticRegistration<dyn Debug>; 2] =
+///     static_registrations!(Debug: i32 String);
+///
+/// let mut db = HashMapTypeDatabase::default();
+/// db.get_entry_mut::<dyn Debug>()
+///     .register_iter(DEBUG.iter().map(StaticRegistration::registration));
+///
+/// let value: &dyn Any = &String::new();
+/// assert!(value.dyn_implements::<dyn Debug>(&db).unwrap());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! static_registrations {
+    ($trait:path: $( $ty:ty )+) => {
+        [$(
+            <$ty as $crate::db::token::StaticImplementor<dyn $trait>>::REGISTRATION,
+        )+]
+    };
+}
+
+/// A scoped registration of a concrete type as an implementor of `U` in the
+/// borrowed database, which is revoked when the guard is dropped (reinstating
+/// any registration that it replaced).
+///
+/// The database remains accessible through the guard, which dereferences to it.
+pub struct RegistrationGuard<'a, DB, U>
+where
+    DB: ?Sized + TypeDatabaseMut,
+    U: 'static + ?Sized,
+{
+    db: &'a mut DB,
+    token: Option<RegistrationToken<U>>,
+}
+
+impl<'a, DB, U> RegistrationGuard<'a, DB, U>
+where
+    DB: ?Sized + TypeDatabaseMut,
+    U: 'static + ?Sized,
+{
+    pub(super) fn new(db: &'a mut DB, token: RegistrationToken<U>) -> Self {
+        Self {
+            db,
+            token: Some(token),
+        }
+    }
+
+    /// Release the registration from this guard, so that it is not revoked on
+    /// drop, and return its token.
+    pub fn into_token(mut self) -> RegistrationToken<U> {
+        // only taken here or on drop
+        unsafe { self.token.take().unwrap_unchecked() }
+    }
+}
+
+impl<DB, U> Deref for RegistrationGuard<'_, DB, U>
+where
+    DB: ?Sized + TypeDatabaseMut,
+    U: 'static + ?Sized,
+{
+    type Target = DB;
+
+    fn deref(&self) -> &DB {
+        self.db
+    }
+}
+
+impl<DB, U> DerefMut for RegistrationGuard<'_, DB, U>
+where
+    DB: ?Sized + TypeDatabaseMut,
+    U: 'static + ?Sized,
+{
+    fn deref_mut(&mut self) -> &mut DB {
+        self.db
+    }
+}
+
+impl<DB, U> Drop for RegistrationGuard<'_, DB, U>
+where
+    DB: ?Sized + TypeDatabaseMut,
+    U: 'static + ?Sized,
+{
+    fn drop(&mut self) {
+        if let Some(token) = self.token.take() {
+            token.revoke(self.db.get_entry_mut::<U>());
+        }
+    }
+}
+
+impl<DB, U> fmt::Debug for RegistrationGuard<'_, DB, U>
+where
+    DB: ?Sized + TypeDatabaseMut,
+    U: 'static + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RegistrationGuard<{}>", type_name::<U>())?;
+        f.debug_tuple("")
+            .field(&self.token.as_ref().map(RegistrationToken::type_id))
+            .finish()
+    }
+}
@@ -0,0 +1,218 @@
+//! A dense index of a database's targets, by which the targets that a concrete
+//! type implements can be tested with a mask rather than a lookup apiece.
+
+use super::{small_map::TypeIdMap, targets::Targets};
+use std::any::TypeId;
+
+/// A set of targets, identified by their indices in a [`TraitIndex`].
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct TraitSet(Vec<u64>);
+
+impl TraitSet {
+    /// Whether the target with the given `index` is in this set.
+    pub fn contains(&self, index: usize) -> bool {
+        self.0.get(index / 64).copied().unwrap_or(0) & (1 << (index % 64)) != 0
+    }
+
+    /// Add the target with the given `index` to this set.
+    pub fn insert(&mut self, index: usize) {
+        let word = index / 64;
+        if word >= self.0.len() {
+            self.0.resize(word + 1, 0);
+        }
+        self.0[word] |= 1 << (index % 64);
+    }
+
+    /// Whether every target in `other` is also in this set.
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other
+            .0
+            .iter()
+            .enumerate()
+            .all(|(i, &word)| self.0.get(i).copied().unwrap_or(0) & word == word)
+    }
+
+    /// Whether any target in `other` is also in this set.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.0.iter().zip(&other.0).any(|(a, b)| a & b != 0)
+    }
+
+    /// Iterate over the indices of the targets in this set.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(i, &word)| {
+            (0..64)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| i * 64 + bit)
+        })
+    }
+}
+
+/// A snapshot of the targets of a database, each assigned a small dense index,
+/// and of the set of targets for which each concrete type is registered (see
+/// [`HashMapTypeDatabase::trait_index`][super::hash_map::HashMapTypeDatabase::trait_index]).
+///
+/// Implementations found by fallback to another target (such as a subtrait) are
+/// indexed alongside registrations; those subject to a predicate are indexed
+/// too, but are also noted as such so that the predicate can be evaluated.  The
+/// index does not reflect any subsequent changes to the database, so is best
+/// built once the database is complete, for example when it is sealed.
+#[derive(Clone, Debug, Default)]
+pub struct TraitIndex {
+    targets: TypeIdMap<usize>,
+    traits: TypeIdMap<TraitSet>,
+    predicated: TypeIdMap<TraitSet>,
+}
+
+impl TraitIndex {
+    /// Index the given targets, each with the concrete types that implement it
+    /// and whether that is subject to a predicate.
+    pub(super) fn new<T, I>(targets: T) -> Self
+    where
+        T: IntoIterator<Item = (TypeId, I)>,
+        I: IntoIterator<Item = (TypeId, bool)>,
+    {
+        let mut targets: Vec<_> = targets.into_iter().collect();
+        // assign indices deterministically, irrespective of map order
+        targets.sort_unstable_by_key(|&(target, _)| target);
+
+        let mut index = Self::default();
+        for (i, (target, implementors)) in targets.into_iter().enumerate() {
+            index.targets.insert(target, i);
+            for (type_id, predicated) in implementors {
+                index.traits.entry(type_id).or_default().insert(i);
+                if predicated {
+                    index.predicated.entry(type_id).or_default().insert(i);
+                }
+            }
+        }
+        index
+    }
+
+    /// The number of indexed targets.
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Whether there are no indexed targets.
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// The index of target `U`, if indexed.
+    pub fn index_of<U>(&self) -> Option<usize>
+    where
+        U: 'static + ?Sized,
+    {
+        self.index_of_id(TypeId::of::<U>())
+    }
+
+    /// The index of the target with the given [`TypeId`], if indexed.
+    pub fn index_of_id(&self, target: TypeId) -> Option<usize> {
+        self.targets.get(&target).copied()
+    }
+
+    /// The set of every target in `T`, if they are all indexed.
+    pub fn mask<T>(&self) -> Option<TraitSet>
+    where
+        T: Targets,
+    {
+        let mut mask = TraitSet::default();
+        let mut complete = true;
+        T::for_each_type_id(&mut |target| match self.index_of_id(target) {
+            Some(index) => mask.insert(index),
+            None => complete = false,
+        });
+        if complete {
+            Some(mask)
+        } else {
+            None
+        }
+    }
+
+    /// The set of targets for which the concrete type with the given `type_id`
+    /// is registered, if any.
+    pub fn traits_of(&self, type_id: TypeId) -> Option<&TraitSet> {
+        self.traits.get(&type_id)
+    }
+
+    /// Whether the concrete type with the given `type_id` is registered for
+    /// every target in `mask`.
+    pub fn implements_all(&self, type_id: TypeId, mask: &TraitSet) -> bool {
+        match self.traits_of(type_id) {
+            Some(traits) => traits.is_superset(mask),
+            None => mask.iter().next().is_none(),
+        }
+    }
+
+    /// Whether the concrete type with the given `type_id` is registered for
+    /// any target in `mask`.
+    pub fn implements_any(&self, type_id: TypeId, mask: &TraitSet) -> bool {
+        match self.traits_of(type_id) {
+            Some(traits) => traits.intersects(mask),
+            None => false,
+        }
+    }
+
+    /// Whether the concrete type with the given `type_id` implements every
+    /// target in `T`; or `None` if that cannot be determined without
+    /// evaluating predicates, or if any target is not indexed.
+    pub(crate) fn resolves_all<T>(&self, type_id: TypeId) -> Option<bool>
+    where
+        T: Targets,
+    {
+        let (mut unknown, mut missing) = (false, false);
+        self.for_each_implementation::<T>(type_id, &mut |implemented| match implemented {
+            Some(true) => {}
+            Some(false) => missing = true,
+            None => unknown = true,
+        });
+        if missing {
+            Some(false)
+        } else if unknown {
+            None
+        } else {
+            Some(true)
+        }
+    }
+
+    /// Whether the concrete type with the given `type_id` implements any
+    /// target in `T`; or `None` if that cannot be determined without
+    /// evaluating predicates, or if any target is not indexed.
+    pub(crate) fn resolves_any<T>(&self, type_id: TypeId) -> Option<bool>
+    where
+        T: Targets,
+    {
+        let (mut unknown, mut found) = (false, false);
+        self.for_each_implementation::<T>(type_id, &mut |implemented| match implemented {
+            Some(true) => found = true,
+            Some(false) => {}
+            None => unknown = true,
+        });
+        if found {
+            Some(true)
+        } else if unknown {
+            None
+        } else {
+            Some(false)
+        }
+    }
+
+    /// Call `f` with whether the concrete type with the given `type_id`
+    /// implements each target in `T`, or `None` if that is subject to a
+    /// predicate or the target is not indexed.
+    fn for_each_implementation<T>(&self, type_id: TypeId, f: &mut dyn FnMut(Option<bool>))
+    where
+        T: Targets,
+    {
+        let traits = self.traits.get(&type_id);
+        let predicated = self.predicated.get(&type_id);
+        let has = |set: Option<&TraitSet>, index| set.is_some_and(|set| set.contains(index));
+        T::for_each_type_id(&mut |target| {
+            f(match self.index_of_id(target) {
+                Some(index) if has(predicated, index) => None,
+                Some(index) => Some(has(traits, index)),
+                None => None,
+            })
+        });
+    }
+}
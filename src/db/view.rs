@@ -0,0 +1,87 @@
+//! Read-only views of a [`TypeDatabase`].
+
+use super::{observer::Observer, Metadata, Predicate, TypeDatabase};
+use core::{any::TypeId, fmt};
+
+#[cfg(feature = "std")]
+use super::trait_index::TraitIndex;
+
+/// A read-only view of a [`TypeDatabase`], through which it can be queried and
+/// used for casting but not mutated.
+pub struct DatabaseView<'a, DB>(&'a DB)
+where
+    DB: ?Sized;
+
+impl<'a, DB> DatabaseView<'a, DB>
+where
+    DB: ?Sized,
+{
+    /// Instantiate a read-only view of `db`.
+    pub fn new(db: &'a DB) -> Self {
+        Self(db)
+    }
+}
+
+impl<DB> Clone for DatabaseView<'_, DB>
+where
+    DB: ?Sized,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<DB> Copy for DatabaseView<'_, DB> where DB: ?Sized {}
+
+impl<'a, DB> From<&'a DB> for DatabaseView<'a, DB>
+where
+    DB: ?Sized,
+{
+    fn from(db: &'a DB) -> Self {
+        Self::new(db)
+    }
+}
+
+impl<DB> fmt::Debug for DatabaseView<'_, DB>
+where
+    DB: ?Sized + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DatabaseView").field(&self.0).finish()
+    }
+}
+
+unsafe impl<DB> TypeDatabase for DatabaseView<'_, DB>
+where
+    DB: ?Sized + TypeDatabase,
+{
+    type Entry<U: ?Sized> = DB::Entry<U>;
+
+    fn get_entry<U>(&self) -> Option<&Self::Entry<U>>
+    where
+        U: 'static + ?Sized,
+    {
+        self.0.get_entry()
+    }
+
+    fn observer(&self) -> Option<&dyn Observer> {
+        self.0.observer()
+    }
+
+    #[cfg(feature = "names")]
+    fn concrete_type_name(&self, type_id: TypeId) -> Option<&'static str> {
+        self.0.concrete_type_name(type_id)
+    }
+
+    #[cfg(feature = "std")]
+    fn cached_trait_index(&self) -> Option<&TraitIndex> {
+        self.0.cached_trait_index()
+    }
+
+    fn fallback_metadata<U>(&self, type_id: TypeId) -> Option<(Metadata<U>, Option<Predicate>)>
+    where
+        U: 'static + ?Sized,
+    {
+        self.0.fallback_metadata::<U>(type_id)
+    }
+}
@@ -0,0 +1,206 @@
+//! A database keyed by the metadata of a *source* trait object, rather than by
+//! the [`TypeId`] of its concrete type.
+//!
+//! This enables casting between trait objects whose traits cannot have [`Any`]
+//! as a supertrait (for example, because they are foreign), and which
+//! therefore offer no means of determining their concrete type.  Each
+//! registration is made for a particular combination of source trait, concrete
+//! type and target trait.
+//!
+//! Note that the compiler does not guarantee that vtables are unique: the same
+//! concrete type and source trait may produce distinct vtables in different
+//! codegen units, in which case a pointer whose metadata was produced elsewhere
+//! than the registration will not be found; conversely, distinct concrete types
+//! may share a vtable if their implementations are identical, which is why
+//! registration is unsafe.  Prefer a [`TypeId`]-keyed database wherever the
+//! source trait can have [`Any`] as a supertrait.
+
+use super::Metadata;
+use crate::container::metadata_of;
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    fmt,
+    marker::{PhantomData, Unsize},
+    ptr,
+};
+
+/// A database of casts between trait objects, keyed by the metadata of the
+/// source trait object.
+#[derive(Default)]
+pub struct VtableTypeDatabase {
+    entries: HashMap<(TypeId, TypeId), Box<dyn Any + Send + Sync>>,
+}
+
+impl VtableTypeDatabase {
+    /// Register concrete type `I` as an implementor of both source `S` and
+    /// target `U`.
+    ///
+    /// # Safety
+    /// No other concrete type may share `I`'s metadata as `S`.
+    pub unsafe fn register<S, U, I>(&mut self)
+    where
+        S: 'static + ?Sized,
+        U: 'static + ?Sized,
+        I: 'static + Unsize<S> + Unsize<U>,
+    {
+        self.get_entry_mut::<S, U>().register::<I>();
+    }
+
+    /// Get the entry for casts from `S` to `U`, if one exists.
+    pub fn get_entry<S, U>(&self) -> Option<&VtableEntry<S, U>>
+    where
+        S: 'static + ?Sized,
+        U: 'static + ?Sized,
+    {
+        self.entries
+            .get(&(TypeId::of::<S>(), TypeId::of::<U>()))
+            .map(|entry| entry.downcast_ref().expect("entry of wrong type"))
+    }
+
+    /// Get the entry for casts from `S` to `U`, creating it if need be.
+    pub fn get_entry_mut<S, U>(&mut self) -> &mut VtableEntry<S, U>
+    where
+        S: 'static + ?Sized,
+        U: 'static + ?Sized,
+    {
+        self.entries
+            .entry((TypeId::of::<S>(), TypeId::of::<U>()))
+            .or_insert_with(|| Box::new(VtableEntry::<S, U>::default()))
+            .downcast_mut()
+            .expect("entry of wrong type")
+    }
+
+    /// Cast `pointer` to `U`, if its concrete type is registered as an
+    /// implementor of `U` for source `S`.
+    pub fn cast_ref<'a, S, U>(&self, pointer: &'a S) -> Option<&'a U>
+    where
+        S: 'static + ?Sized,
+        U: 'static + ?Sized,
+    {
+        let metadata = self.get_entry::<S, U>()?.metadata(ptr::metadata(pointer))?;
+        let pointer = ptr::from_raw_parts::<U>((pointer as *const S).cast::<()>(), metadata);
+        // the metadata was registered for the concrete type that underlies
+        // `pointer`'s own metadata
+        Some(unsafe { &*pointer })
+    }
+
+    /// Cast `pointer` to `U`, if its concrete type is registered as an
+    /// implementor of `U` for source `S`.
+    pub fn cast_mut<'a, S, U>(&self, pointer: &'a mut S) -> Option<&'a mut U>
+    where
+        S: 'static + ?Sized,
+        U: 'static + ?Sized,
+    {
+        let metadata = self.get_entry::<S, U>()?.metadata(ptr::metadata(pointer))?;
+        let pointer = ptr::from_raw_parts_mut::<U>((pointer as *mut S).cast::<()>(), metadata);
+        Some(unsafe { &mut *pointer })
+    }
+
+    /// Cast `pointer` to `U`, if its concrete type is registered as an
+    /// implementor of `U` for source `S`; otherwise return it unchanged.
+    pub fn cast_box<S, U>(&self, pointer: Box<S>) -> Result<Box<U>, Box<S>>
+    where
+        S: 'static + ?Sized,
+        U: 'static + ?Sized,
+    {
+        let metadata = match self
+            .get_entry::<S, U>()
+            .and_then(|entry| entry.metadata(ptr::metadata(&*pointer)))
+        {
+            Some(metadata) => metadata,
+            None => return Err(pointer),
+        };
+        let pointer = ptr::from_raw_parts_mut::<U>(Box::into_raw(pointer).cast::<()>(), metadata);
+        Ok(unsafe { Box::from_raw(pointer) })
+    }
+}
+
+impl fmt::Debug for VtableTypeDatabase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VtableTypeDatabase")
+            .field("entries", &self.entries.len())
+            .finish()
+    }
+}
+
+/// The registered casts from source `S` to target `U`, keyed by the metadata
+/// of `S` for each concrete type.
+pub struct VtableEntry<S, U>
+where
+    S: ?Sized,
+    U: ?Sized,
+{
+    implementors: HashMap<Metadata<S>, Metadata<U>>,
+    source: PhantomData<fn(&S)>,
+}
+
+impl<S, U> VtableEntry<S, U>
+where
+    S: ?Sized,
+    U: ?Sized,
+{
+    /// Register concrete type `I` as an implementor of both `S` and `U`.
+    ///
+    /// # Safety
+    /// No other concrete type may share `I`'s metadata as `S`.
+    pub unsafe fn register<I>(&mut self)
+    where
+        I: Unsize<S> + Unsize<U>,
+    {
+        self.add(metadata_of::<S, I>(), metadata_of::<U, I>());
+    }
+
+    /// Register `target` as the metadata of `U` for whichever concrete type
+    /// has metadata `source` as `S`, for example as obtained from the
+    /// codegen unit that will produce the pointers to be cast.
+    ///
+    /// # Safety
+    /// `source` and `target` must be for the same concrete type, and no other
+    /// concrete type may share its metadata as `S`.
+    pub unsafe fn add(&mut self, source: Metadata<S>, target: Metadata<U>) {
+        self.implementors.insert(source, target);
+    }
+
+    /// The metadata of `U` for the concrete type whose metadata as `S` is
+    /// `source`, if registered.
+    pub fn metadata(&self, source: Metadata<S>) -> Option<Metadata<U>> {
+        self.implementors.get(&source).copied()
+    }
+
+    /// The number of registered concrete types.
+    pub fn len(&self) -> usize {
+        self.implementors.len()
+    }
+
+    /// Whether no concrete types are registered.
+    pub fn is_empty(&self) -> bool {
+        self.implementors.is_empty()
+    }
+}
+
+impl<S, U> Default for VtableEntry<S, U>
+where
+    S: ?Sized,
+    U: ?Sized,
+{
+    fn default() -> Self {
+        Self {
+            implementors: HashMap::new(),
+            source: PhantomData,
+        }
+    }
+}
+
+impl<S, U> fmt::Debug for VtableEntry<S, U>
+where
+    S: ?Sized,
+    U: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VtableEntry<{}, {}>", type_name::<S>(), type_name::<U>())?;
+        f.debug_struct("")
+            .field("implementors", &self.implementors.len())
+            .finish()
+    }
+}
@@ -0,0 +1,154 @@
+//! Downcasting in the manner of the [downcast-rs] crate, implemented in terms
+//! of rattish's containers.
+//!
+//! Unlike downcast-rs, no per-trait macro is required beyond
+//! [`coercible_trait`][crate::coercible_trait]: every trait object (and every
+//! pointer or wrapper thereto) through which the concrete value can be borrowed
+//! directly gains these methods.
+//!
+//! [downcast-rs]: https://docs.rs/downcast-rs
+
+#[cfg(test)]
+mod tests;
+
+use crate::container::{InnermostAny, InnermostAnyMut, InnermostTypeId};
+use core::any::{Any, TypeId};
+
+#[cfg(feature = "alloc")]
+use crate::container::Coercible;
+
+#[cfg(all(feature = "alloc", not(feature = "std"), target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::{boxed::Box, rc::Rc};
+
+#[cfg(feature = "std")]
+use std::{boxed::Box, rc::Rc, sync::Arc};
+
+/// Identification of the concrete type underlying `Self`, through any stack of
+/// wrappers and without reference to a database.
+pub trait DynIs
+where
+    Self: InnermostTypeId,
+{
+    /// Whether the concrete value is of type `T`; `false` if its type cannot be
+    /// determined.
+    fn dyn_is<T>(&self) -> bool
+    where
+        T: Any,
+    {
+        matches!(self.innermost_type_id(), Ok(type_id) if type_id == TypeId::of::<T>())
+    }
+}
+
+impl<P> DynIs for P where P: ?Sized + InnermostTypeId {}
+
+/// Shared access to the concrete value underlying `Self`.
+pub trait Downcast
+where
+    Self: InnermostAny,
+{
+    /// Returns the concrete value as [`dyn Any`][Any].
+    fn as_any(&self) -> &dyn Any {
+        self.innermost_any()
+    }
+
+    /// Whether the concrete value is of type `T`.
+    fn is<T>(&self) -> bool
+    where
+        T: Any,
+    {
+        self.innermost_any().is::<T>()
+    }
+
+    /// Returns the concrete value, if it is of type `T`.
+    fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: Any,
+    {
+        self.innermost_any().downcast_ref()
+    }
+}
+
+impl<P> Downcast for P where P: ?Sized + InnermostAny {}
+
+/// Exclusive access to the concrete value underlying `Self`.
+pub trait DowncastMut
+where
+    Self: InnermostAnyMut,
+{
+    /// Returns the concrete value as [`dyn Any`][Any].
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self.innermost_any_mut()
+    }
+
+    /// Returns the concrete value, if it is of type `T`.
+    fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: Any,
+    {
+        self.innermost_any_mut().downcast_mut()
+    }
+}
+
+impl<P> DowncastMut for P where P: ?Sized + InnermostAnyMut {}
+
+/// Ownership of the concrete value underlying a leaf, such as a `dyn Trait`.
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub trait DowncastOwned
+where
+    Self: Coercible<Innermost = Self> + InnermostTypeId,
+{
+    /// Returns the boxed concrete value, if it is of type `T`.
+    fn downcast<T>(self: Box<Self>) -> Result<Box<T>, Box<Self>>
+    where
+        T: Any,
+    {
+        if self.dyn_is::<T>() {
+            Ok(unsafe { Box::from_raw(Box::into_raw(self).cast()) })
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Returns the reference-counted concrete value, if it is of type `T`.
+    fn downcast_rc<T>(self: Rc<Self>) -> Result<Rc<T>, Rc<Self>>
+    where
+        T: Any,
+    {
+        if self.dyn_is::<T>() {
+            Ok(unsafe { Rc::from_raw(Rc::into_raw(self).cast()) })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<X> DowncastOwned for X where X: ?Sized + Coercible<Innermost = X> + InnermostTypeId {}
+
+/// Ownership of the concrete value underlying a thread-safe leaf, such as a
+/// `dyn Trait + Send + Sync`.
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub trait DowncastSync
+where
+    Self: DowncastOwned + Send + Sync,
+{
+    /// Returns the atomically reference-counted concrete value, if it is of
+    /// type `T`.
+    fn downcast_arc<T>(self: Arc<Self>) -> Result<Arc<T>, Arc<Self>>
+    where
+        T: Any + Send + Sync,
+    {
+        if self.dyn_is::<T>() {
+            Ok(unsafe { Arc::from_raw(Arc::into_raw(self).cast()) })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl<X> DowncastSync for X where X: ?Sized + DowncastOwned + Send + Sync {}
@@ -0,0 +1,55 @@
+use super::{Downcast, DowncastMut, DynIs};
+use core::{any::Any, cell::RefCell};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+#[cfg(feature = "alloc")]
+use super::{DowncastOwned, DowncastSync};
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::{boxed::Box, rc::Rc, sync::Arc};
+
+#[cfg(feature = "std")]
+use std::{boxed::Box, rc::Rc, sync::Arc};
+
+#[cfg(feature = "alloc")]
+trait Shared: Any + Send + Sync {}
+#[cfg(feature = "alloc")]
+impl Shared for i32 {}
+#[cfg(feature = "alloc")]
+crate::coercible_trait!(Shared);
+
+#[test]
+fn borrowed_values_downcast() {
+    let mut cell = RefCell::new(12345);
+    let compound: &mut RefCell<dyn Any> = &mut cell;
+
+    assert!(compound.borrow().is::<i32>());
+    assert_eq!(compound.borrow().downcast_ref::<u32>(), None);
+    *compound.downcast_mut::<i32>().unwrap() *= 2;
+    assert_eq!(cell.into_inner(), 24690);
+}
+
+#[test]
+fn concrete_type_is_identified_through_wrappers() {
+    let cell = RefCell::new(12345);
+    let compound: &RefCell<dyn Any> = &cell;
+
+    assert!(compound.dyn_is::<i32>());
+    assert!(!compound.dyn_is::<u32>());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn owned_values_downcast() {
+    let boxed: Box<dyn Any> = Box::new(12345);
+    let boxed = boxed.downcast::<u32>().unwrap_err();
+    assert_eq!(*boxed.downcast::<i32>().unwrap(), 12345);
+
+    let rc: Rc<dyn Any> = Rc::new(12345);
+    assert_eq!(*rc.downcast_rc::<i32>().unwrap(), 12345);
+
+    let arc: Arc<dyn Shared> = Arc::new(12345);
+    assert_eq!(arc.downcast_arc::<i32>().ok().map(|arc| *arc), Some(12345));
+}
@@ -1,11 +1,6 @@
 #![cfg_attr(not(feature = "std"), no_std)]
-#![cfg_attr(
-    any(feature = "global", all(feature = "std", test)),
-    feature(once_cell)
-)]
-#![cfg_attr(doc, feature(doc_cfg))]
-#![cfg_attr(feature = "std", feature(option_result_unwrap_unchecked))]
-#![feature(generic_associated_types, ptr_metadata, unsize)]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+#![feature(ptr_metadata, unsize)]
 #![deny(missing_docs)]
 
 //! rattish enables dynamic casting between different trait objects.
@@ -14,18 +9,12 @@
 //! automatically created by the Rust compiler and so must be generated
 //! manually.
 //!
-//! rattish is presently only experimental, and depends on unstable compiler
-//! features including [`generic_associated_types`], [`ptr_metadata`] and
-//! [`unsize`].
-#![cfg_attr(
-    feature = "global",
-    doc = "[`once_cell`] is used by [`DB`] (enabled by the `global` feature)."
-)]
-//! Accordingly, a nightly toolchain is required.
+//! rattish is presently only experimental, and depends on the unstable
+//! [`ptr_metadata`] and [`unsize`] compiler features.  Accordingly, a nightly
+//! toolchain is required.
 //!
 //! # Example
 //! ```rust
-//! #![feature(generic_associated_types, once_cell)]
 //! # #[cfg(feature = "global")] {
 //!
 //! use rattish::{coercible_trait, rtti_global, GlobalDynCast};
@@ -84,39 +73,133 @@
 //! # main() }
 //! ```
 //!
+//! # Diagnostics
+//!
+//! With the `tracing` feature, registrations, lookups and casts are
+//! instrumented with [`tracing`] spans and events.  With the `log` feature,
+//! the same events are instead emitted through the [`log`] facade.  If both
+//! features are enabled (as, for example, on docs.rs), `tracing` takes
+//! precedence and nothing is emitted through `log`.
+//!
 //! # Extending rattish to additional pointer/wrapper types
 //!
 //! You will need to implement [`Coercible`] and [`InnermostTypeId`] for
 //! your type; and also [`Pointer`] if your type is a pointer-type (that
 //! is, if it is `Sized + Deref`).
 //!
-//! [`generic_associated_types`]: https://doc.rust-lang.org/nightly/unstable-book/language-features/generic-associated-types.html
-//! [`once_cell`]: https://doc.rust-lang.org/nightly/unstable-book/library-features/once-cell.html
 //! [`ptr_metadata`]: https://doc.rust-lang.org/nightly/unstable-book/library-features/ptr-metadata.html
 //! [`unsize`]: https://doc.rust-lang.org/nightly/unstable-book/library-features/unsize.html
+//! [`tracing`]: https://docs.rs/tracing
+//! [`log`]: https://docs.rs/log
 
 #[cfg(all(feature = "alloc", not(feature = "std")))]
 extern crate alloc;
 
+/// Emit a diagnostic event at the given `level` through `tracing` or, if only
+/// the `log` feature is enabled, through `log`.
+///
+/// Events `for` a target (or that report a `failed` cast to one) are subject to
+/// any [`trace::Filter`].
+macro_rules! diagnostic {
+    ($level:ident for failed $target:expr, $( $arg:tt )+) => {
+        diagnostic!(@filtered $level, $target, true, $( $arg )+)
+    };
+    ($level:ident for $target:expr, $( $arg:tt )+) => {
+        diagnostic!(@filtered $level, $target, false, $( $arg )+)
+    };
+    (@filtered $level:ident, $target:expr, $failure:expr, $( $arg:tt )+) => {{
+        #[cfg(feature = "tracing")]
+        crate::trace::emit($target, $failure, || tracing::$level!($( $arg )+));
+        #[cfg(all(feature = "log", not(feature = "tracing")))]
+        log::$level!($( $arg )+);
+    }};
+    ($level:ident, $( $arg:tt )+) => {{
+        #[cfg(feature = "tracing")]
+        tracing::$level!($( $arg )+);
+        #[cfg(all(feature = "log", not(feature = "tracing")))]
+        log::$level!($( $arg )+);
+    }};
+}
+
+/// Enter, with the `tracing` feature, a span of the given `name` and `fields`
+/// for an operation on target `U`, unless filtered out by any
+/// [`trace::Filter`]; and, with the `puffin` or `tracy` features, a profiler
+/// scope of the same `name`, annotated with `U`.
+macro_rules! trace_span {
+    ($U:ty, $name:literal $( , $( $fields:tt )+ )?) => {
+        #[cfg(feature = "tracing")]
+        let _span = crate::trace::enter(type_name::<$U>(), || {
+            tracing::info_span!($name $( , $( $fields )+ )?)
+        });
+        #[cfg(feature = "puffin")]
+        puffin::profile_scope!($name, type_name::<$U>());
+        #[cfg(feature = "tracy")]
+        let _zone = tracy_client::Client::running().map(|client| {
+            let zone = client.span(tracy_client::span_location!($name), 0);
+            zone.emit_text(type_name::<$U>());
+            zone
+        });
+    };
+}
+
+/// Enter, with the `tracing` feature, a span of the given `name` and `fields`
+/// for an operation, unless it is nested within a cast that has been filtered
+/// out by any [`trace::Filter`].
+macro_rules! nested_span {
+    ($name:literal $( , $( $fields:tt )+ )?) => {
+        #[cfg(feature = "tracing")]
+        let _span = crate::trace::nested(|| {
+            tracing::info_span!($name $( , $( $fields )+ )?).entered()
+        });
+    };
+}
+
 pub mod container;
 pub mod db;
+pub mod downcast;
+pub mod prelude;
+#[cfg(feature = "tracing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+pub mod trace;
 
 #[doc(hidden)]
 #[cfg(feature = "tracing")]
 pub use tracing;
 
-use container::{Coerced, Coercible, InnermostTypeId, Metadata, Pointer};
-use core::ptr;
+#[cfg(feature = "global")]
+pub use db::hash_map::defer_registration;
+
+use container::{Coerced, Coercible, InnermostAny, InnermostTypeId, Metadata, Pointer};
+use core::{
+    marker::Unsize,
+    ops::{Deref, DerefMut},
+    ptr,
+};
 use db::{
-    error::{CastError, DatabaseEntryError},
-    TypeDatabaseEntryExt, TypeDatabaseExt,
+    cast_resolved,
+    error::{CastError, ChainedCastError, DatabaseEntryError, DatabaseError, MultiCastError},
+    iter::CastIter,
+    resolve_in,
+    targets::Targets,
+    TypeDatabaseEntry, TypeDatabaseEntryExt, TypeDatabaseExt,
 };
 
+#[cfg(any(feature = "alloc", feature = "tracing"))]
+use core::any::type_name;
+
 #[cfg(feature = "global")]
-use db::{error::DatabaseError, hash_map::DB};
+use db::hash_map::DB;
 
-#[cfg(feature = "tracing")]
-use core::any::type_name;
+#[cfg(feature = "alloc")]
+use db::error::UpgradeCastError;
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::rc;
+#[cfg(all(feature = "alloc", not(feature = "std"), target_has_atomic = "ptr"))]
+use alloc::sync;
+
+#[cfg(feature = "std")]
+use std::{rc, sync};
 
 /// A type whose implementations can be dynamically determined.
 pub trait DynImplements<DB>
@@ -125,15 +208,92 @@ where
     DB: TypeDatabaseExt,
 {
     /// Lookup whether `self`'s ultimate concrete type implements `U` in `db`.
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
-        Self = type_name::<Self>(),
-        U = type_name::<U>(),
-    )))]
     fn dyn_implements<U>(&self, db: &DB) -> Result<bool, DatabaseEntryError<U, &Self>>
     where
         U: 'static + ?Sized,
     {
-        db.get_db_entry::<U>()?.implements(&self)
+        trace_span!(
+            U,
+            "dyn_implements",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        let type_id = self.innermost_type_id()?;
+        match resolve_in(db, type_id, &self) {
+            Ok(_) => Ok(true),
+            Err(
+                DatabaseEntryError::ConcreteTypeNotRegisteredForTarget { .. }
+                | DatabaseEntryError::PredicateNotSatisfied { .. },
+            ) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Lookup whether `self`'s ultimate concrete type implements `U` in `db`,
+    /// treating the absence of any entry for `U` as an entry without
+    /// implementors: useful for probing optional capabilities.
+    fn dyn_implements_or_false<U>(&self, db: &DB) -> Result<bool, DatabaseEntryError<U, &Self>>
+    where
+        U: 'static + ?Sized,
+    {
+        trace_span!(
+            U,
+            "dyn_implements_or_false",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        match self.dyn_implements(db) {
+            Err(DatabaseEntryError::DatabaseError {
+                error: DatabaseError::RequestedTypeNotInDatabase { .. },
+            }) => Ok(false),
+            result => result,
+        }
+    }
+
+    /// Lookup whether `self`'s ultimate concrete type implements every one of
+    /// the `T`argets in `db`.
+    fn dyn_implements_all<T>(&self, db: &DB) -> Result<bool, DatabaseEntryError<T, &Self>>
+    where
+        T: Targets,
+    {
+        trace_span!(
+            T,
+            "dyn_implements_all",
+            Self = type_name::<Self>(),
+            T = type_name::<T>()
+        );
+        let type_id = self.innermost_type_id()?;
+        #[cfg(feature = "std")]
+        if let Some(implemented) = db
+            .cached_trait_index()
+            .and_then(|index| index.resolves_all::<T>(type_id))
+        {
+            return Ok(implemented);
+        }
+        Ok(T::implemented_by_all(db, type_id, self)?)
+    }
+
+    /// Lookup whether `self`'s ultimate concrete type implements any of the
+    /// `T`argets in `db`.
+    fn dyn_implements_any<T>(&self, db: &DB) -> Result<bool, DatabaseEntryError<T, &Self>>
+    where
+        T: Targets,
+    {
+        trace_span!(
+            T,
+            "dyn_implements_any",
+            Self = type_name::<Self>(),
+            T = type_name::<T>()
+        );
+        let type_id = self.innermost_type_id()?;
+        #[cfg(feature = "std")]
+        if let Some(implemented) = db
+            .cached_trait_index()
+            .and_then(|index| index.resolves_any::<T>(type_id))
+        {
+            return Ok(implemented);
+        }
+        Ok(T::implemented_by_any(db, type_id, self)?)
     }
 }
 
@@ -146,24 +306,374 @@ where
 {
     /// Cast `self`'s ultimate concrete type to `U`, if registered as an
     /// implementor of `U` in `db`.
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
-        Self = type_name::<Self>(),
-        U = type_name::<U>(),
-    )))]
+    #[cfg_attr(feature = "debug-casts", track_caller)]
     fn dyn_cast<U>(self, db: &DB) -> Result<Self::Coerced<U>, CastError<U, Self>>
     where
         U: 'static + ?Sized,
         Self::Coerced<U>: Sized,
         Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
     {
-        match db.get_db_entry() {
-            Ok(entry) => entry.cast(self),
-            Err(source) => Err(CastError {
-                source: source.into(),
-                pointer: self,
-            }),
-        }
+        trace_span!(
+            U,
+            "dyn_cast",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        let entry = db.get_db_entry::<U>();
+        let found = entry.as_ref().ok().copied();
+        let observer = match found {
+            Some(entry) => entry.observer(),
+            None => db.observer(),
+        };
+        #[cfg(feature = "names")]
+        let name = |type_id| {
+            found
+                .and_then(|entry| entry.type_name(type_id))
+                .or_else(|| db.concrete_type_name(type_id))
+        };
+        #[cfg(not(feature = "names"))]
+        let name = |_| None;
+        cast_resolved(
+            entry,
+            observer,
+            self,
+            |type_id| db.fallback_metadata::<U>(type_id),
+            name,
+        )
+        .map_err(|error| error.named_by(db))
+    }
+
+    /// Cast `self`'s ultimate concrete type to `U`, if registered as an
+    /// implementor of `U` in `db`; otherwise drop `self` and return `None`.
+    fn dyn_cast_opt<U>(self, db: &DB) -> Option<Self::Coerced<U>>
+    where
+        U: 'static + ?Sized,
+        Self::Coerced<U>: Sized,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(
+            U,
+            "dyn_cast_opt",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        DynCast::dyn_cast(self, db).ok()
     }
+
+    /// Cast a clone of `self` (typically a reference-counted pointer) to `U`,
+    /// if its ultimate concrete type is registered as an implementor of `U` in
+    /// `db`, leaving `self` untouched.
+    fn dyn_cast_cloned<U>(&self, db: &DB) -> Result<Self::Coerced<U>, DatabaseEntryError<U, Self>>
+    where
+        Self: Clone,
+        U: 'static + ?Sized,
+        Self::Coerced<U>: Sized,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(
+            U,
+            "dyn_cast_cloned",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        DynCast::dyn_cast(self.clone(), db).map_err(|error| error.source)
+    }
+
+    /// Cast `self`'s ultimate concrete type to `U`, if registered as an
+    /// implementor of `U` in `db`; otherwise drop `self` and return `default`.
+    fn dyn_cast_or<U>(self, db: &DB, default: Self::Coerced<U>) -> Self::Coerced<U>
+    where
+        U: 'static + ?Sized,
+        Self::Coerced<U>: Sized,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(
+            U,
+            "dyn_cast_or",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        DynCast::dyn_cast(self, db).unwrap_or(default)
+    }
+
+    /// Cast `self`'s ultimate concrete type to `U`, if registered as an
+    /// implementor of `U` in `db`; otherwise return the result of calling `f`
+    /// with the recovered `self`.
+    fn dyn_cast_or_else<U, F>(self, db: &DB, f: F) -> Self::Coerced<U>
+    where
+        U: 'static + ?Sized,
+        Self::Coerced<U>: Sized,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+        F: FnOnce(Self) -> Self::Coerced<U>,
+    {
+        trace_span!(
+            U,
+            "dyn_cast_or_else",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        DynCast::dyn_cast(self, db).unwrap_or_else(|error| f(error.pointer))
+    }
+
+    /// Cast `self`'s ultimate concrete type to `U`, if registered as an
+    /// implementor of `U` in `entry`, which has already been looked up in a
+    /// database of type `DB`.
+    ///
+    /// Unlike [`dyn_cast`][DynCast::dyn_cast], only the registrations in
+    /// `entry` are consulted, and not any database fallbacks (such as declared
+    /// subtraits).
+    #[cfg_attr(feature = "debug-casts", track_caller)]
+    fn dyn_cast_with<U>(self, entry: &DB::Entry<U>) -> Result<Self::Coerced<U>, CastError<U, Self>>
+    where
+        U: 'static + ?Sized,
+        Self::Coerced<U>: Sized,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(
+            U,
+            "dyn_cast_with",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        entry.cast(self)
+    }
+
+    /// Cast `self`'s ultimate concrete type to `U`, without checking that it is
+    /// registered as an implementor of `U` in `db`.
+    ///
+    /// # Safety
+    /// [`dyn_implements::<U>`][DynImplements::dyn_implements] must have
+    /// returned `Ok(true)` for `self` and `db`, neither of which may since
+    /// have been mutated.  Moreover,
+    /// [`innermost_type_id`][InnermostTypeId::innermost_type_id] must still
+    /// succeed for `self`: casting a `Weak` reference that has since become
+    /// dangling, or a [`RefCell`][core::cell::RefCell] that is now mutably
+    /// borrowed elsewhere (which would block), is undefined behaviour.
+    unsafe fn dyn_cast_unchecked<U>(self, db: &DB) -> Self::Coerced<U>
+    where
+        U: 'static + ?Sized,
+        Self::Coerced<U>: Sized,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(
+            U,
+            "dyn_cast_unchecked",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        let type_id = self.innermost_type_id().unwrap_unchecked();
+        let metadata = db
+            .get_entry::<U>()
+            .and_then(|entry| entry.metadata(type_id))
+            .or_else(|| Some(db.fallback_metadata::<U>(type_id)?.0))
+            .unwrap_unchecked();
+        self.coerce(metadata)
+    }
+
+    /// Cast the ultimate concrete type of each pointer yielded by `pointers` to
+    /// `U`, if registered as an implementor of `U` in `db`, looking up `U` in
+    /// `db` only once.
+    ///
+    /// Only the registrations in `db`'s entry for `U` are consulted, and not
+    /// any database fallbacks (such as declared subtraits).
+    fn dyn_cast_iter<U, I>(pointers: I, db: &DB) -> CastIter<'_, U, DB::Entry<U>, I::IntoIter>
+    where
+        U: 'static + ?Sized,
+        I: IntoIterator<Item = Self>,
+    {
+        trace_span!(
+            U,
+            "dyn_cast_iter",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        CastIter::new(db.get_entry(), pointers.into_iter())
+    }
+
+    /// Cast `self` to `U`, a supertrait object of `self`'s innermost trait
+    /// object, using `db`.
+    ///
+    /// This is merely a [`dyn_cast`][DynCast::dyn_cast] whose target is
+    /// statically known to be implemented; nevertheless, each concrete type
+    /// that might underlie the cast must still be registered as an implementor
+    /// of `U` in `db`.
+    #[cfg_attr(feature = "debug-casts", track_caller)]
+    fn dyn_upcast<U>(self, db: &DB) -> Result<Self::Coerced<U>, CastError<U, Self>>
+    where
+        U: 'static + ?Sized,
+        Self::Innermost: Unsize<U>,
+        Self::Coerced<U>: Sized,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(
+            U,
+            "dyn_upcast",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        DynCast::dyn_cast(self, db)
+    }
+}
+
+/// A type to which pointers of type `P` can be dynamically cast.
+///
+/// This is the mirror of [`DynCast`], and enables the destination type to be
+/// named first.  Its innermost type must itself be [`Coercible`] (for example,
+/// by way of [`coercible_trait`]).
+pub trait DynCastFrom<P, DB>
+where
+    Self: Sized + Coercible,
+    DB: TypeDatabaseExt,
+{
+    /// Cast `pointer`'s ultimate concrete type to `Self`, if registered as an
+    /// implementor of `Self`'s innermost type in `db`.
+    fn dyn_cast_from(pointer: P, db: &DB) -> Result<Self, CastError<Self::Innermost, P>>
+    where
+        Self::Innermost: 'static;
+}
+
+/// A type that can be dynamically cast by reference, without giving up
+/// ownership.
+pub trait DynCastRef<DB>
+where
+    Self: Deref,
+    Self::Target: Coercible + InnermostTypeId,
+    DB: TypeDatabaseExt,
+{
+    /// Cast a reference to `self`'s target to `U`, if its ultimate concrete
+    /// type is registered as an implementor of `U` in `db`.
+    #[allow(clippy::type_complexity)]
+    #[cfg_attr(feature = "debug-casts", track_caller)]
+    fn dyn_cast_ref<U>(
+        &self,
+        db: &DB,
+    ) -> Result<&Coerced<Self::Target, U>, CastError<U, &Self::Target>>
+    where
+        U: 'static + ?Sized,
+        Coerced<Self::Target, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(
+            U,
+            "dyn_cast_ref",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        DynCast::dyn_cast::<U>(&**self, db)
+    }
+
+    /// A reference to `self`'s target as `U`, if its ultimate concrete type is
+    /// registered as an implementor of `U` in `db`.
+    fn dyn_query<U>(&self, db: &DB) -> Option<&Coerced<Self::Target, U>>
+    where
+        U: 'static + ?Sized,
+        Coerced<Self::Target, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(
+            U,
+            "dyn_query",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        DynCastRef::dyn_cast_ref::<U>(self, db).ok()
+    }
+
+    /// Cast a reference to `self`'s target to every target in `T`, a tuple of
+    /// references to each target type (for example, `(&dyn Bar, &dyn Baz)`),
+    /// determining the ultimate concrete type only once.
+    fn dyn_cast_multi<T>(&self, db: &DB) -> Result<T::Views<'_>, MultiCastError<T, &Self::Target>>
+    where
+        T: Targets,
+        Self::Target: InnermostAny,
+    {
+        trace_span!(
+            T,
+            "dyn_cast_multi",
+            Self = type_name::<Self>(),
+            T = type_name::<T>()
+        );
+        let data = (**self).innermost_any();
+        T::view_all(db, data)
+    }
+}
+
+/// A type that can be dynamically cast by exclusive reference, without giving
+/// up ownership.
+pub trait DynCastMut<DB>
+where
+    Self: DerefMut,
+    Self::Target: Coercible + InnermostTypeId,
+    DB: TypeDatabaseExt,
+{
+    /// Cast an exclusive reference to `self`'s target to `U`, if its ultimate
+    /// concrete type is registered as an implementor of `U` in `db`.
+    #[allow(clippy::type_complexity)]
+    #[cfg_attr(feature = "debug-casts", track_caller)]
+    fn dyn_cast_mut<U>(
+        &mut self,
+        db: &DB,
+    ) -> Result<&mut Coerced<Self::Target, U>, CastError<U, &mut Self::Target>>
+    where
+        U: 'static + ?Sized,
+        Coerced<Self::Target, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(
+            U,
+            "dyn_cast_mut",
+            Self = type_name::<Self>(),
+            U = type_name::<U>()
+        );
+        DynCast::dyn_cast::<U>(&mut **self, db)
+    }
+}
+
+/// A weak reference that can be upgraded and dynamically cast in one step.
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub trait DynCastUpgraded<DB>
+where
+    DB: TypeDatabaseExt,
+{
+    /// The strong reference to which `Self` upgrades.
+    type Strong: Pointer + InnermostTypeId;
+
+    /// Upgrade `self` and cast the resulting strong reference's ultimate
+    /// concrete type to `U`, if registered as an implementor of `U` in `db`.
+    fn dyn_cast_upgraded<U>(
+        &self,
+        db: &DB,
+    ) -> Result<Coerced<Self::Strong, U>, UpgradeCastError<U, Self::Strong>>
+    where
+        U: 'static + ?Sized,
+        Coerced<Self::Strong, U>: Sized,
+        Coerced<<Self::Strong as Coercible>::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>;
+}
+
+/// The result of a cast that can itself be cast onward, so that conversions
+/// through several targets read as a pipeline.
+pub trait AndThenCast<DB>
+where
+    DB: TypeDatabaseExt,
+{
+    /// The pointer that results if the previous cast succeeded.
+    type Pointer: Pointer + InnermostTypeId;
+
+    /// The error that results if the previous cast failed.
+    type Error;
+
+    /// Cast the pointer that resulted from the previous cast to `U`, if its
+    /// ultimate concrete type is registered as an implementor of `U` in `db`.
+    ///
+    /// Whichever cast fails returns its (unmodified) pointer in the error.
+    #[allow(clippy::type_complexity)]
+    fn and_then_cast<U>(
+        self,
+        db: &DB,
+    ) -> Result<Coerced<Self::Pointer, U>, ChainedCastError<Self::Error, U, Self::Pointer>>
+    where
+        U: 'static + ?Sized,
+        <Self::Pointer as Coercible>::Inner: Coercible,
+        Coerced<Self::Pointer, U>: Sized,
+        Coerced<<Self::Pointer as Coercible>::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>;
 }
 
 impl<DB, P: ?Sized> DynImplements<DB> for P
@@ -181,6 +691,156 @@ where
 {
 }
 
+impl<DB, P, T> DynCastFrom<P, DB> for T
+where
+    Self: Sized + Coercible,
+    Self::Innermost: 'static,
+    P: Pointer + InnermostTypeId + Coercible<Coerced<Self::Innermost> = Self>,
+    P::Inner: Coercible,
+    Coerced<P::Inner, Self::Innermost>: ptr::Pointee<Metadata = Metadata<Self::Innermost>>,
+    DB: TypeDatabaseExt,
+{
+    #[cfg_attr(feature = "debug-casts", track_caller)]
+    fn dyn_cast_from(pointer: P, db: &DB) -> Result<Self, CastError<Self::Innermost, P>> {
+        trace_span!(
+            Self::Innermost,
+            "dyn_cast_from",
+            Self = type_name::<Self>(),
+            P = type_name::<P>()
+        );
+        DynCast::dyn_cast(pointer, db)
+    }
+}
+
+impl<DB, P: ?Sized> DynCastRef<DB> for P
+where
+    Self: Deref,
+    Self::Target: Coercible + InnermostTypeId,
+    DB: TypeDatabaseExt,
+{
+}
+
+impl<DB, P: ?Sized> DynCastMut<DB> for P
+where
+    Self: DerefMut,
+    Self::Target: Coercible + InnermostTypeId,
+    DB: TypeDatabaseExt,
+{
+}
+
+impl<DB, P, E> AndThenCast<DB> for Result<P, E>
+where
+    P: Pointer + InnermostTypeId,
+    DB: TypeDatabaseExt,
+{
+    type Pointer = P;
+    type Error = E;
+
+    fn and_then_cast<U>(self, db: &DB) -> Result<P::Coerced<U>, ChainedCastError<E, U, P>>
+    where
+        U: 'static + ?Sized,
+        P::Inner: Coercible,
+        P::Coerced<U>: Sized,
+        Coerced<P::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(
+            U,
+            "and_then_cast",
+            P = type_name::<P>(),
+            U = type_name::<U>()
+        );
+        match self {
+            Ok(pointer) => DynCast::dyn_cast(pointer, db).map_err(ChainedCastError::Cast),
+            Err(error) => Err(ChainedCastError::Previous(error)),
+        }
+    }
+}
+
+macro_rules! dyn_cast_upgraded {
+    ($( $weak:ident::Weak => $strong:ident, )+) => {$(
+        #[cfg(feature = "alloc")]
+        impl<DB, T> DynCastUpgraded<DB> for $weak::Weak<T>
+        where
+            T: ?Sized + Coercible + InnermostTypeId,
+            DB: TypeDatabaseExt,
+        {
+            type Strong = $weak::$strong<T>;
+
+            fn dyn_cast_upgraded<U>(
+                &self,
+                db: &DB,
+            ) -> Result<$weak::$strong<T::Coerced<U>>, UpgradeCastError<U, Self::Strong>>
+            where
+                U: 'static + ?Sized,
+                T::Coerced<U>: ptr::Pointee<Metadata = Metadata<U>>,
+            {
+                trace_span!(U, "dyn_cast_upgraded", Self = type_name::<Self>(), U = type_name::<U>());
+                let strong = self.upgrade().ok_or(UpgradeCastError::Dangling {
+                    type_name: type_name::<Self>(),
+                })?;
+                DynCast::dyn_cast(strong, db).map_err(UpgradeCastError::Cast)
+            }
+        }
+    )+};
+}
+
+dyn_cast_upgraded! {
+    rc::Weak => Rc,
+}
+
+// `alloc::sync` is only available on targets with atomic pointers
+#[cfg(target_has_atomic = "ptr")]
+dyn_cast_upgraded! {
+    sync::Weak => Arc,
+}
+
+/// Attempts to cast a pointer to each of a prioritized sequence of targets in
+/// turn, evaluating the arm of whichever succeeds first or else the `else` arm
+/// with the (unmodified) pointer.
+///
+/// ```rust
+/// # #[cfg(feature = "std")] {
+/// use rattish::{dyn_cast_first, rtti};
+/// use std::{any::Any, fmt};
+///
+/// let db = rtti! {
+///     fmt::LowerExp: f32,
+///     fmt::Display: f32 i32,
+/// };
+///
+/// let describe = |pointer: &dyn Any| {
+///     dyn_cast_first!(pointer, &db;
+///         dyn fmt::LowerExp => |exp| format!("{:e}", exp),
+///         dyn fmt::Display => |display| display.to_string(),
+///         else |_| String::from("?"),
+///     )
+/// };
+///
+/// assert_eq!(describe(&1.5f32), "1.5e0");
+/// assert_eq!(describe(&15i32), "15");
+/// assert_eq!(describe(&'x'), "?");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! dyn_cast_first {
+    ($pointer:expr, $db:expr; $( $arms:tt )+) => {{
+        let db = $db;
+        $crate::dyn_cast_first!(@arms $pointer, db; $($arms)+)
+    }};
+    (@arms $pointer:expr, $db:ident; else |$otherwise:pat_param| $default:expr $(,)?) => {{
+        let $otherwise = $pointer;
+        $default
+    }};
+    (@arms $pointer:expr, $db:ident; $target:ty => |$casted:pat_param| $then:expr, $( $rest:tt )+) => {
+        match $crate::DynCast::dyn_cast::<$target>($pointer, $db) {
+            ::core::result::Result::Ok($casted) => $then,
+            ::core::result::Result::Err(error) => {
+                $crate::dyn_cast_first!(@arms error.pointer, $db; $($rest)+)
+            }
+        }
+    };
+}
+
 #[cfg(feature = "global")]
 /// A type whose implementations can be dynamically determined using the global
 /// [`DB`].
@@ -190,14 +850,48 @@ where
 {
     /// Lookup whether `self`'s ultimate concrete type implements `U` in the
     /// global [`DB`].
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     fn dyn_implements<U>(&self) -> Result<bool, DatabaseEntryError<U, &Self>>
     where
         U: 'static + ?Sized,
     {
+        trace_span!(U, "dyn_implements");
         let db = DB.get().ok_or(DatabaseError::NotInitialized)?;
         DynImplements::dyn_implements::<U>(self, db)
     }
+
+    /// Lookup whether `self`'s ultimate concrete type implements `U` in the
+    /// global [`DB`], treating the absence of any entry for `U` as an entry
+    /// without implementors.
+    fn dyn_implements_or_false<U>(&self) -> Result<bool, DatabaseEntryError<U, &Self>>
+    where
+        U: 'static + ?Sized,
+    {
+        trace_span!(U, "dyn_implements_or_false");
+        let db = DB.get().ok_or(DatabaseError::NotInitialized)?;
+        DynImplements::dyn_implements_or_false::<U>(self, db)
+    }
+
+    /// Lookup whether `self`'s ultimate concrete type implements every one of
+    /// the `T`argets in the global [`DB`].
+    fn dyn_implements_all<T>(&self) -> Result<bool, DatabaseEntryError<T, &Self>>
+    where
+        T: Targets,
+    {
+        trace_span!(T, "dyn_implements_all");
+        let db = DB.get().ok_or(DatabaseError::NotInitialized)?;
+        DynImplements::dyn_implements_all::<T>(self, db)
+    }
+
+    /// Lookup whether `self`'s ultimate concrete type implements any of the
+    /// `T`argets in the global [`DB`].
+    fn dyn_implements_any<T>(&self) -> Result<bool, DatabaseEntryError<T, &Self>>
+    where
+        T: Targets,
+    {
+        trace_span!(T, "dyn_implements_any");
+        let db = DB.get().ok_or(DatabaseError::NotInitialized)?;
+        DynImplements::dyn_implements_any::<T>(self, db)
+    }
 }
 
 #[cfg(feature = "global")]
@@ -209,21 +903,165 @@ where
 {
     /// Cast `self`'s ultimate concrete type to `U`, if registered as an
     /// implementor of `U` in the global [`DB`].
-    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    #[cfg_attr(feature = "debug-casts", track_caller)]
     fn dyn_cast<U>(self) -> Result<Self::Coerced<U>, CastError<U, Self>>
     where
         U: 'static + ?Sized,
         Self::Coerced<U>: Sized,
         Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
     {
+        trace_span!(U, "dyn_cast");
         match DB.get() {
             Some(db) => DynCast::dyn_cast::<U>(self, db),
-            None => Err(CastError {
-                source: DatabaseError::NotInitialized.into(),
-                pointer: self,
-            }),
+            None => Err(CastError::new(DatabaseError::NotInitialized.into(), self)),
         }
     }
+
+    /// Cast `self`'s ultimate concrete type to `U`, if registered as an
+    /// implementor of `U` in the global [`DB`]; otherwise drop `self` and
+    /// return `None`.
+    fn dyn_cast_opt<U>(self) -> Option<Self::Coerced<U>>
+    where
+        U: 'static + ?Sized,
+        Self::Coerced<U>: Sized,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(U, "dyn_cast_opt");
+        GlobalDynCast::dyn_cast(self).ok()
+    }
+
+    /// Cast a clone of `self` (typically a reference-counted pointer) to `U`,
+    /// if its ultimate concrete type is registered as an implementor of `U` in
+    /// the global [`DB`], leaving `self` untouched.
+    fn dyn_cast_cloned<U>(&self) -> Result<Self::Coerced<U>, DatabaseEntryError<U, Self>>
+    where
+        Self: Clone,
+        U: 'static + ?Sized,
+        Self::Coerced<U>: Sized,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(U, "dyn_cast_cloned");
+        GlobalDynCast::dyn_cast(self.clone()).map_err(|error| error.source)
+    }
+
+    /// Cast `self`'s ultimate concrete type to `U`, if registered as an
+    /// implementor of `U` in the global [`DB`]; otherwise drop `self` and
+    /// return `default`.
+    fn dyn_cast_or<U>(self, default: Self::Coerced<U>) -> Self::Coerced<U>
+    where
+        U: 'static + ?Sized,
+        Self::Coerced<U>: Sized,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(U, "dyn_cast_or");
+        GlobalDynCast::dyn_cast(self).unwrap_or(default)
+    }
+
+    /// Cast `self`'s ultimate concrete type to `U`, if registered as an
+    /// implementor of `U` in the global [`DB`]; otherwise return the result of
+    /// calling `f` with the recovered `self`.
+    fn dyn_cast_or_else<U, F>(self, f: F) -> Self::Coerced<U>
+    where
+        U: 'static + ?Sized,
+        Self::Coerced<U>: Sized,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+        F: FnOnce(Self) -> Self::Coerced<U>,
+    {
+        trace_span!(U, "dyn_cast_or_else");
+        GlobalDynCast::dyn_cast(self).unwrap_or_else(|error| f(error.pointer))
+    }
+
+    /// Cast `self`'s ultimate concrete type to `U`, without checking that it is
+    /// registered as an implementor of `U` in the global [`DB`].
+    ///
+    /// # Safety
+    /// [`dyn_implements::<U>`][GlobalDynImplements::dyn_implements] must have
+    /// returned `Ok(true)` for `self`, which may not since have been mutated.
+    /// Moreover, the concrete type of `self` must still be determinable (see
+    /// [`DynCast::dyn_cast_unchecked`]).
+    unsafe fn dyn_cast_unchecked<U>(self) -> Self::Coerced<U>
+    where
+        U: 'static + ?Sized,
+        Self::Coerced<U>: Sized,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(U, "dyn_cast_unchecked");
+        DynCast::dyn_cast_unchecked::<U>(self, DB.get().unwrap_unchecked())
+    }
+
+    /// Cast `self` to `U`, a supertrait object of `self`'s innermost trait
+    /// object, using the global [`DB`].
+    ///
+    /// See [`DynCast::dyn_upcast`].
+    #[cfg_attr(feature = "debug-casts", track_caller)]
+    fn dyn_upcast<U>(self) -> Result<Self::Coerced<U>, CastError<U, Self>>
+    where
+        U: 'static + ?Sized,
+        Self::Innermost: Unsize<U>,
+        Self::Coerced<U>: Sized,
+        Coerced<Self::Inner, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(U, "dyn_upcast");
+        GlobalDynCast::dyn_cast(self)
+    }
+}
+
+#[cfg(feature = "global")]
+/// A type that can be dynamically cast by reference, without giving up
+/// ownership, using the global [`DB`].
+pub trait GlobalDynCastRef
+where
+    Self: Deref,
+    Self::Target: Coercible + InnermostTypeId,
+{
+    /// Cast a reference to `self`'s target to `U`, if its ultimate concrete
+    /// type is registered as an implementor of `U` in the global [`DB`].
+    #[allow(clippy::type_complexity)]
+    #[cfg_attr(feature = "debug-casts", track_caller)]
+    fn dyn_cast_ref<U>(&self) -> Result<&Coerced<Self::Target, U>, CastError<U, &Self::Target>>
+    where
+        U: 'static + ?Sized,
+        Coerced<Self::Target, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(U, "dyn_cast_ref");
+        GlobalDynCast::dyn_cast::<U>(&**self)
+    }
+
+    /// A reference to `self`'s target as `U`, if its ultimate concrete type is
+    /// registered as an implementor of `U` in the global [`DB`].
+    fn dyn_query<U>(&self) -> Option<&Coerced<Self::Target, U>>
+    where
+        U: 'static + ?Sized,
+        Coerced<Self::Target, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(U, "dyn_query");
+        GlobalDynCastRef::dyn_cast_ref::<U>(self).ok()
+    }
+}
+
+#[cfg(feature = "global")]
+/// A type that can be dynamically cast by exclusive reference, without giving
+/// up ownership, using the global [`DB`].
+pub trait GlobalDynCastMut
+where
+    Self: DerefMut,
+    Self::Target: Coercible + InnermostTypeId,
+{
+    /// Cast an exclusive reference to `self`'s target to `U`, if its ultimate
+    /// concrete type is registered as an implementor of `U` in the global
+    /// [`DB`].
+    #[allow(clippy::type_complexity)]
+    #[cfg_attr(feature = "debug-casts", track_caller)]
+    fn dyn_cast_mut<U>(
+        &mut self,
+    ) -> Result<&mut Coerced<Self::Target, U>, CastError<U, &mut Self::Target>>
+    where
+        U: 'static + ?Sized,
+        Coerced<Self::Target, U>: ptr::Pointee<Metadata = Metadata<U>>,
+    {
+        trace_span!(U, "dyn_cast_mut");
+        GlobalDynCast::dyn_cast::<U>(&mut **self)
+    }
 }
 
 #[cfg(feature = "global")]
@@ -236,3 +1074,19 @@ where
     Self::Inner: Coercible,
 {
 }
+
+#[cfg(feature = "global")]
+impl<P: ?Sized> GlobalDynCastRef for P
+where
+    Self: Deref,
+    Self::Target: Coercible + InnermostTypeId,
+{
+}
+
+#[cfg(feature = "global")]
+impl<P: ?Sized> GlobalDynCastMut for P
+where
+    Self: DerefMut,
+    Self::Target: Coercible + InnermostTypeId,
+{
+}
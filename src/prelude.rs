@@ -0,0 +1,85 @@
+//! Everything needed to start casting, in one import.
+//!
+//! ```rust
+//! # #[cfg(feature = "std")] {
+//!
+//! use rattish::prelude::*;
+//! use std::{any::Any, error::Error, fmt};
+//!
+//! let db = standard_database();
+//!
+//! let value: &dyn Any = &"abc".parse::<u8>().unwrap_err();
+//! let error = value.dyn_cast::<dyn Error>(&db).ok().unwrap();
+//! assert_eq!(error.to_string(), "invalid digit found in string");
+//!
+//! let value: &dyn Any = &42;
+//! let display = value.dyn_cast::<dyn fmt::Display>(&db).ok().unwrap();
+//! assert_eq!(display.to_string(), "42");
+//! # }
+//! ```
+
+pub use crate::{
+    coercible_trait, coercible_type,
+    container::{CoerceTo, Coercible, InnermostTypeId},
+    db::{
+        iter::DynIterator, std_fmt::register_std_fmt, TypeDatabaseEntryExt, TypeDatabaseExt,
+        TypeDatabaseMut,
+    },
+    downcast::DynIs,
+    dyn_cast_first, AndThenCast, DynCast, DynCastFrom, DynCastMut, DynCastRef, DynImplements,
+};
+
+#[cfg(feature = "alloc")]
+pub use crate::DynCastUpgraded;
+
+#[cfg(feature = "std")]
+pub use crate::{db::hash_map::HashMapTypeDatabase, rtti};
+
+/// The counterparts of the prelude for the global [`DB`][crate::db::hash_map::DB].
+///
+/// These are not in the prelude itself, as their methods share names with
+/// those of the traits that are.
+#[cfg(feature = "global")]
+#[cfg_attr(docsrs, doc(cfg(feature = "global")))]
+pub mod global {
+    pub use crate::{
+        rtti_global, GlobalDynCast, GlobalDynCastMut, GlobalDynCastRef, GlobalDynImplements,
+    };
+}
+
+#[cfg(feature = "std")]
+use std::{char, error::Error, fmt, io, num, str, string};
+
+/// A new database in which the standard library's common types are
+/// registered as implementors of [`Error`] and of the [`fmt`] traits (see
+/// [`register_std_fmt`]).
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub fn standard_database() -> HashMapTypeDatabase {
+    let mut db = HashMapTypeDatabase::default();
+    register_std_fmt(&mut db);
+
+    macro_rules! register_errors {
+        ($( $ty:ty )+) => {
+            rtti! {
+                @register &mut db;
+                Error: $( $ty )+,
+                fmt::Debug: $( $ty )+,
+                fmt::Display: $( $ty )+,
+            }
+        };
+    }
+
+    register_errors!(
+        fmt::Error
+        io::Error
+        char::ParseCharError
+        num::ParseFloatError
+        num::ParseIntError
+        num::TryFromIntError
+        str::ParseBoolError
+        str::Utf8Error
+        string::FromUtf8Error
+    );
+    db
+}
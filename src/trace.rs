@@ -0,0 +1,179 @@
+//! Runtime filtering of the spans and events that are emitted through
+//! `tracing`, which would otherwise be emitted for every cast.
+//!
+//! A [`Filter`] can restrict them to casts to particular targets, or to
+//! failures only:
+//!
+//! ```rust
+//! # #[cfg(feature = "std")] {
+//! # use std::fmt;
+//! rattish::trace::set_filter(
+//!     rattish::trace::Filter::default()
+//!         .target::<dyn fmt::Display>()
+//!         .failures_only(),
+//! );
+//! # }
+//! ```
+//!
+//! Casts that are filtered out emit no spans, and nor do any operations that
+//! rattish performs on their behalf; spans and events emitted by other code
+//! that they call (such as predicates, observers and lazy metadata providers)
+//! are unaffected.  Filtering requires the `std` feature: without it,
+//! everything is emitted.
+
+use tracing::{span::EnteredSpan, Span};
+
+#[cfg(feature = "std")]
+use core::{any::type_name, cell::Cell};
+#[cfg(feature = "std")]
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    LazyLock, RwLock,
+};
+
+/// Which spans and events are emitted: by default, all of them.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    targets: Option<Vec<&'static str>>,
+    failures_only: bool,
+}
+
+#[cfg(feature = "std")]
+impl Filter {
+    /// Also emit spans and events for casts to `U`, and only for casts to
+    /// those targets so added.
+    pub fn target<U>(mut self) -> Self
+    where
+        U: 'static + ?Sized,
+    {
+        self.targets
+            .get_or_insert_with(Vec::new)
+            .push(type_name::<U>());
+        self
+    }
+
+    /// Emit no spans, and only those events that report failed casts.
+    pub fn failures_only(mut self) -> Self {
+        self.failures_only = true;
+        self
+    }
+
+    /// Whether to emit spans and events for casts to `target`.
+    fn permits(&self, target: &str) -> bool {
+        match self.targets {
+            Some(ref targets) => targets.contains(&target),
+            None => true,
+        }
+    }
+}
+
+/// Whether any filter has been set, so that the lock need not be taken if not.
+#[cfg(feature = "std")]
+static FILTERED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "std")]
+static FILTER: LazyLock<RwLock<Filter>> = LazyLock::new(Default::default);
+
+#[cfg(feature = "std")]
+thread_local! {
+    /// Whether a filtered out cast is in progress on this thread, whose
+    /// operations therefore emit no spans.
+    static SKIPPING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Restrict the spans and events that are emitted to those permitted by
+/// `filter`, in place of any filter previously set.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub fn set_filter(filter: Filter) {
+    let filtered = filter.targets.is_some() || filter.failures_only;
+    *FILTER.write().unwrap_or_else(|error| error.into_inner()) = filter;
+    FILTERED.store(filtered, Ordering::Release);
+}
+
+/// Apply `f` to the current filter, if any has been set.
+#[cfg(feature = "std")]
+fn with_filter<F, R>(f: F) -> Option<R>
+where
+    F: FnOnce(&Filter) -> R,
+{
+    if FILTERED.load(Ordering::Acquire) {
+        Some(f(&FILTER.read().unwrap_or_else(|error| error.into_inner())))
+    } else {
+        None
+    }
+}
+
+/// The guard of a span entered by [`enter`].
+#[allow(dead_code)] // held only to be dropped
+pub(crate) struct Guard {
+    /// The span, unless filtered out.
+    span: Option<EnteredSpan>,
+    /// Whether spans were being skipped on this thread before the span was
+    /// entered, to be restored on drop.
+    #[cfg(feature = "std")]
+    skipping: bool,
+}
+
+#[cfg(feature = "std")]
+impl Drop for Guard {
+    fn drop(&mut self) {
+        SKIPPING.with(|skipping| skipping.set(self.skipping));
+    }
+}
+
+/// Enter the `span` of a cast to `target`, unless filtered out (in which case
+/// the spans of any operations nested within it are skipped too).
+pub(crate) fn enter<F>(target: &'static str, span: F) -> Guard
+where
+    F: FnOnce() -> Span,
+{
+    #[cfg(feature = "std")]
+    {
+        let skip = with_filter(|filter| filter.failures_only || !filter.permits(target));
+        let skip = skip == Some(true);
+        Guard {
+            span: (!skip).then(|| span().entered()),
+            skipping: SKIPPING.with(|skipping| skipping.replace(skip)),
+        }
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        let _ = target;
+        Guard {
+            span: Some(span().entered()),
+        }
+    }
+}
+
+/// Call `f`, which emits a span or event of an operation that rattish performs,
+/// unless it is nested within a filtered out cast.
+#[doc(hidden)]
+pub fn nested<F, R>(f: F) -> Option<R>
+where
+    F: FnOnce() -> R,
+{
+    #[cfg(feature = "std")]
+    if SKIPPING.with(Cell::get) {
+        return None;
+    }
+    Some(f())
+}
+
+/// Emit an event about `target` by calling `f`, unless filtered out: events
+/// that report a `failure` survive a [`Filter::failures_only`].
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+pub(crate) fn emit<F>(target: &'static str, failure: bool, f: F)
+where
+    F: FnOnce(),
+{
+    #[cfg(feature = "std")]
+    if with_filter(|filter| (filter.failures_only && !failure) || !filter.permits(target))
+        == Some(true)
+    {
+        return;
+    }
+    f()
+}